@@ -6,17 +6,72 @@ use crate::{AppError, AppResult};
 #[command(name = "Jarvis")]
 #[command(version, about = "Your personal AI agent", long_about = None)]
 pub struct Cli {
-    /// Enable commands execution
-    #[arg(short = 'x', long)]
+    /// Enable the tools that make changes (e.g. `git.commit`)
+    #[arg(short = 'x', long, global = true)]
     pub execute: bool,
 
-    /// Select a module to run
-    #[arg(short, long)]
-    pub module: Option<String>,
+    /// Write the files proposed in the response, after showing a diff and
+    /// asking for confirmation (`-x` is taken by `--execute`)
+    #[arg(short = 'A', long)]
+    pub apply: bool,
 
-    /// Input file to be used with current prompt
-    #[arg(short, long)]
-    pub input: Option<String>,
+    /// List the files proposed in the response without writing them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Module to expose to the model, repeatable (every module by default)
+    #[arg(short, long, value_name = "NAME", global = true)]
+    pub module: Vec<String>,
+
+    /// Module to hide from the model (repeatable)
+    #[arg(long, value_name = "NAME", global = true)]
+    pub no_module: Vec<String>,
+
+    /// Only expose the tools matching this pattern, e.g. `math.*` or
+    /// `fs.read_*` (repeatable)
+    #[arg(long, value_name = "PATTERN", global = true)]
+    pub allow_tool: Vec<String>,
+
+    /// Never expose the tools matching this pattern (repeatable)
+    #[arg(long, value_name = "PATTERN", global = true)]
+    pub deny_tool: Vec<String>,
+
+    /// Don't expose any tool to the model
+    #[arg(long, global = true)]
+    pub no_tools: bool,
+
+    /// Offer at most this many tools per message, picking the ones relevant
+    /// to it, when more are enabled (0 offers them all)
+    #[arg(long, value_name = "N", global = true)]
+    pub max_tools: Option<usize>,
+
+    /// Input files, directories or globs to be used with current prompt (`-` reads stdin)
+    #[arg(short, long, global = true)]
+    pub input: Vec<String>,
+
+    /// Image to attach to the prompt, for vision models (repeatable)
+    #[arg(long, value_name = "PATH", global = true)]
+    pub image: Vec<String>,
+
+    /// Show token usage and speed after each answer
+    #[arg(long, global = true)]
+    pub stats: bool,
+
+    /// Profile to use (prompt, model, options, modules and approval policy)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// System prompt template to use
+    #[arg(long = "prompt", value_name = "NAME", global = true)]
+    pub template: Option<String>,
+
+    /// JSON schema file the response must match, the validated JSON is printed
+    #[arg(long, value_name = "FILE")]
+    pub schema: Option<String>,
+
+    /// Times to re-prompt when the response doesn't match the schema
+    #[arg(long, default_value_t = 2, requires = "schema")]
+    pub schema_retries: usize,
 
     /// Subcommands (e.g., chat)
     #[command(subcommand)]
@@ -30,7 +85,138 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Start a personal chat session
-    Chat,
+    Chat {
+        /// Resume the saved session with this name, or save the new one under it
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+
+    /// Embed texts and print the vectors as JSON (reads lines from stdin if no text is given)
+    Embed {
+        /// Embedding model to use
+        #[arg(short = 'M', long)]
+        model: Option<String>,
+
+        /// Texts to embed
+        texts: Vec<String>,
+    },
+
+    /// Index files and directories for retrieval (shows the index if no path is given)
+    Index {
+        /// Files or directories to index
+        paths: Vec<String>,
+
+        /// Embedding model to use
+        #[arg(short = 'M', long)]
+        model: Option<String>,
+
+        /// Remove everything from the index first
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Inspect system prompt templates
+    Prompt {
+        #[command(subcommand)]
+        command: PromptCommand,
+    },
+
+    /// Manage local models
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
+    },
+
+    /// Inspect modules and run their tools
+    Modules {
+        #[command(subcommand)]
+        command: ModulesCommand,
+    },
+
+    /// Manage the todo list without a model (lists the open todos if no
+    /// subcommand is given)
+    Todo {
+        #[command(subcommand)]
+        command: Option<TodoCommand>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PromptCommand {
+    /// List available templates
+    List,
+
+    /// Show exactly what would be sent to the model for a prompt
+    Render {
+        /// Text prompt to render with
+        text: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModelsCommand {
+    /// List installed models
+    List,
+
+    /// Show details of an installed model
+    Show { name: String },
+
+    /// Download a model from the registry
+    Pull { name: String },
+
+    /// Remove an installed model
+    Rm { name: String },
+
+    /// List models currently loaded into memory
+    Ps,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModulesCommand {
+    /// List modules and their tools
+    List,
+
+    /// Show the prompt of a module and the parameters of its tools
+    Show { name: String },
+
+    /// Run a tool directly, without a model
+    Call {
+        #[arg(value_name = "MODULE")]
+        name: String,
+
+        tool: String,
+
+        /// Arguments of the tool as a JSON object
+        #[arg(long, default_value = "{}")]
+        args: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TodoCommand {
+    /// List the open todos, the ones due first
+    List {
+        /// Also list the completed todos
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Add a todo
+    Add {
+        /// The task
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+
+        /// Due date, e.g. `2026-10-20`, `tomorrow` or `next friday`
+        #[arg(short, long)]
+        due: Option<String>,
+    },
+
+    /// Mark a todo as done
+    Done { id: u64 },
+
+    /// List the todos due today or overdue
+    Today,
 }
 
 impl Cli {
@@ -47,7 +233,16 @@ impl Cli {
         }
     }
 
-    pub fn create_full_prompt(&self) {
-        
+    pub fn create_full_prompt(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
     }
 }
@@ -1,158 +1,315 @@
+use super::{
+    EMBEDDING_MODEL, HOST, LLM_MODEL, PORT,
+    apply::apply_file_blocks,
+    index::retrieve_context,
+    memory::recall_memories,
+    structured::{generate_structured, load_schema},
+};
 use crate::{
     AppError, AppResult, Cli,
-    modules::ModuleRegistry,
-    providers::{OllamaConfig, create_ollama_client},
+    model::{ApprovalPolicy, Usage},
+    modules::{DEFAULT_MAX_TOOLS, ModuleRegistry, ModuleScope, ToolCallFunction, ToolRouter},
+    profile::{DEFAULT_PROFILE, Profile, load_profile},
+    prompt::{DEFAULT_PROMPT, PromptVariables, load_template},
+    providers::{OllamaConfig, OllamaModelOptions, OllamaProvider, create_ollama_client},
     streaming::{OutputStreamer, StreamEvent, create_cli_streamer},
-    utils::get_file_content,
+    utils::{InputSource, LoadedInputs, expand_inputs, is_data_file, load_image, load_inputs},
 };
 use std::sync::Arc;
 
-const SYSTEM_PROMPT: &str = r#"You are a helpful assistant. You are given a task and you must answer the user's query following the rules and using available modules when appropriate.
-
-<context>
-__CONTEXT__
-</context>
-
-<modules>
-__MODULES__
-</modules>
-
-<rules>
-- If you don't know, say so.
-- If you are not sure, ask for clarification.
-- Answer in the same language as the user query.
-- Answer directly and without using XML tags.
-- When proposing files, use the file block syntax (see examples below).
-- For Markdown files, use four backticks to wrap the file content so inner code blocks are preserved.
-- When a tool is called, you MUST ONLY return the JSON array. Do not include any other text, reasoning, or explanations outside the JSON array.
-- If no tool is required, respond normally as plain text (not JSON).
-- When a tool returns a result, you will see it in the conversation history as 'TOOL_RESULT: ...'. Your final answer MUST be a direct, concise summary of the result in natural language. Do not add any conversational text, explanations, or extraneous details unless asked.
-- When a numerical result is obtained, present it as a plain number without any additional text, symbols, or currency signs.
-- You must only use the function names provided in the module registry. Do not invent new functions.
-
---- TOOL / MODULE USAGE ---
-You have access to a set of tools (functions) within modules. Each tool has a `name`, `module`, and `parameters`. To use a tool, you must return a **single JSON array** of one or more tool call objects.
-
-**IMPORTANT RULES for Tool Calls:**
-1. You **MUST** return only a JSON array. No text, explanations, or other characters should appear outside of the JSON.
-2. Each object in the array **MUST** strictly follow this structure:
-
-[
-  {
-    "type": "function",
-    "function": {
-      "name": "<tool_name>",
-      "module": "<module_name>",
-      "arguments": { /* arguments matching the tool's parameters schema */ }
-    }
-  }
-]
+/// Modules and tools exposed to the model. Flags win over the profile,
+/// except denied tools which add up.
+fn module_scope(cli: &Cli, profile: &Profile) -> ModuleScope {
+    let modules = if cli.module.is_empty() {
+        profile.modules.clone()
+    } else {
+        Some(cli.module.clone())
+    };
 
-3. The `module` field is required and must match the module name shown in the `<modules>` section exactly.
-4. Argument objects must strictly conform to the parameter schema provided with the tool (no missing required fields and no extra unexpected fields).
-5. You may return multiple tool call objects in the array (for multi-step operations).
+    let allowed_tools = if cli.allow_tool.is_empty() {
+        profile.allow_tools.clone().unwrap_or_default()
+    } else {
+        cli.allow_tool.clone()
+    };
 
-Examples:
+    let mut denied_tools = cli.deny_tool.clone();
+    denied_tools.extend(profile.deny_tools.iter().flatten().cloned());
 
-* If the user asks: "What is 10 divided by 3?", and the `math.eval` tool is appropriate, return:
+    ModuleScope {
+        modules,
+        excluded_modules: cli.no_module.clone(),
+        allowed_tools,
+        denied_tools,
+        no_tools: cli.no_tools,
+        execute: cli.execute,
+    }
+}
 
-[
-  {
-    "type": "function",
-    "function": {
-      "name": "eval",
-      "module": "math",
-      "arguments": { "expression": "10.0/3" }
+/// Settings for a run, resolved from the CLI flags and the active profile.
+/// Flags win over the profile, the profile wins over the defaults.
+pub(super) struct RunSettings {
+    pub config: OllamaConfig,
+    /// The registry limited to the modules and tools of this run
+    pub registry: Arc<ModuleRegistry>,
+    /// Set when the run has more tools than it offers at once
+    pub router: Option<ToolRouter>,
+    pub modules_for_prompt: String,
+    pub template: String,
+    pub approval: ApprovalPolicy,
+}
+
+impl RunSettings {
+    pub fn new(cli: &Cli, registry: &ModuleRegistry, profile: &Profile) -> AppResult<Self> {
+        let registry = Arc::new(registry.scoped(module_scope(cli, profile))?);
+        let tools = registry.all_tools();
+
+        // Too many tools for one request, the client offers the relevant ones
+        // for each message and the prompt only lists the modules
+        let max_tools = cli
+            .max_tools
+            .or(profile.max_tools)
+            .unwrap_or(DEFAULT_MAX_TOOLS);
+        let (router, modules_for_prompt) = if max_tools > 0 && tools.len() > max_tools {
+            log::info!(
+                "Routing {} tools, up to {} per message",
+                tools.len(),
+                max_tools
+            );
+            (
+                Some(ToolRouter::new(tools.clone(), max_tools)),
+                registry.get_catalog_prompt(),
+            )
+        } else {
+            (None, registry.get_system_prompt())
+        };
+
+        let mut options = OllamaModelOptions::default();
+        if let Some(overrides) = &profile.options {
+            options = options.merge(overrides);
+        }
+
+        let config = OllamaConfig::new()
+            .host(HOST.to_string())
+            .model(profile.model.clone().unwrap_or(LLM_MODEL.to_string()))
+            .port(PORT)
+            .embedding_model(EMBEDDING_MODEL.to_string())
+            .tools(tools)
+            .options(options)
+            .build()?;
+
+        let template = cli
+            .template
+            .clone()
+            .or_else(|| profile.prompt.clone())
+            .unwrap_or(DEFAULT_PROMPT.to_string());
+
+        Ok(Self {
+            config,
+            registry,
+            router,
+            modules_for_prompt,
+            template,
+            approval: profile.approval.unwrap_or_default(),
+        })
     }
-  }
-]
+}
 
-* If no tool is needed, reply with plain language and not JSON.
+/// Opens the data files among the inputs with the data module, so the model
+/// queries them instead of reading them raw. The context describes each
+/// opened file, which is removed from `sources`. Files the module can't open
+/// stay inputs.
+fn open_data_inputs(registry: &ModuleRegistry, sources: &mut Vec<InputSource>) -> LoadedInputs {
+    let mut blocks = Vec::new();
+    let mut files = Vec::new();
+
+    sources.retain(|source| {
+        let InputSource::File(path) = source else {
+            return true;
+        };
+        if !is_data_file(path) {
+            return true;
+        }
+
+        let opened = registry.execute(&ToolCallFunction {
+            name: "open".to_string(),
+            module: "data".to_string(),
+            arguments: serde_json::json!({ "path": path }),
+        });
+        let opened = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                log::info!("Including {} as text: {}", path.display(), e);
+                return true;
+            }
+        };
+
+        let describe = |table: &serde_json::Value| {
+            format!(
+                "- `{}` ({} rows): {}",
+                table["table"].as_str().unwrap_or(""),
+                table["rows"],
+                table["columns"].as_str().unwrap_or("")
+            )
+        };
+        let tables = match opened["tables"].as_array() {
+            Some(tables) => tables.iter().map(describe).collect::<Vec<_>>(),
+            None => vec![describe(&opened)],
+        };
+        blocks.push(format!(
+            "<data path=\"{}\">\nOpened in the data module, query it with `data.query`:\n{}\n</data>",
+            source.label(),
+            tables.join("\n")
+        ));
+        files.push(source.label());
+        false
+    });
+
+    LoadedInputs {
+        context: blocks.join("\n\n"),
+        files,
+        notices: Vec::new(),
+    }
+}
 
-\--- FILE BLOCK EXAMPLES ---
-If you propose a file, represent it as a code block with a `name` in the header. Example TypeScript file:
+pub(super) fn active_profile(cli: &Cli) -> AppResult<Profile> {
+    load_profile(cli.profile.as_deref().unwrap_or(DEFAULT_PROFILE))
+}
 
-```typescript name=filename.ts
-// file contents here
-```
+/// Builds the system prompt exactly as it is sent to the model: the selected
+/// template rendered with the environment, inputs, retrieved sources and modules
+pub(super) async fn build_system_prompt(
+    cli: &Cli,
+    settings: &RunSettings,
+    provider: &OllamaProvider,
+    prompt: Option<&str>,
+    streamer: &mut dyn OutputStreamer,
+) -> AppResult<String> {
+    let mut sources = expand_inputs(&cli.input)?;
+
+    for source in &sources {
+        streamer
+            .handle_event(StreamEvent::Status(format!(
+                "Loading input: {}",
+                source.label()
+            )))
+            .await?;
+        log::info!("Loading input from: {}", source.label());
+    }
 
-Markdown file example (use four backticks to wrap the file):
+    let data = open_data_inputs(&settings.registry, &mut sources);
+    let inputs = load_inputs(&sources)?;
+    for notice in &inputs.notices {
+        log::warn!("{}", notice);
+        eprintln!("Warning: {}", notice);
+    }
 
-````markdown name=filename.md
-```js
-console.log("inner code block preserved");
-```
-````
+    // Pull the relevant chunks from the document index, if one exists
+    let retrieved = match prompt {
+        Some(prompt) => match retrieve_context(provider, &settings.config, prompt).await {
+            Ok(sources) => sources,
+            Err(e) => {
+                log::warn!("Skipping retrieval: {}", e);
+                String::new()
+            }
+        },
+        None => String::new(),
+    };
 
-</rules>"#;
+    // What Jarvis learned in earlier sessions
+    let memories = recall_memories(provider, &settings.config, prompt)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Skipping memories: {}", e);
+            String::new()
+        });
+
+    let context = [memories, data.context, inputs.context.clone(), retrieved]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let files = [data.files, inputs.files].concat().join(", ");
+
+    let template = load_template(&settings.template)?;
+
+    PromptVariables::from_environment()
+        .set("context", context)
+        .set("files", files)
+        .set("modules", settings.modules_for_prompt.clone())
+        .render(&template)
+}
 
-const HOST: &str = "http://localhost";
-const PORT: u16 = 11434;
-const LLM_MODEL: &str = "llama3.2";
+/// Prints usage on stderr so it never mixes with the answer
+pub(super) fn print_stats(model: &str, usage: Usage) {
+    eprintln!("[{}] {}", model, usage);
+}
 
 pub async fn process_prompt(cli: &Cli, module_registry: &Arc<ModuleRegistry>) -> AppResult<()> {
     let mut streamer = create_cli_streamer(false);
 
-    // Determine which tools to expose based on --module
-    let (tools_for_payload, modules_for_prompt) = if let Some(module_name) = cli.module.as_deref() {
-        let module = module_registry
-            .get_module(module_name)
-            .ok_or_else(|| AppError::from(&format!("Module {} not found", module_name)))?;
-
-        // Machine-readable for LLM
-        let tool_schemas = module.tools();
-
-        // Human-readable for system prompt
-        let module_desc = format!("{}\n", module.get_prompt());
-
-        (tool_schemas, module_desc)
-    } else {
-        // All tools (machine-readable)
-        let tool_schemas = module_registry.all_tools();
-
-        // All modules description (human-readable)
-        let module_desc = module_registry.get_system_prompt();
-
-        (tool_schemas, module_desc)
-    };
+    let profile = active_profile(cli)?;
+    let mut settings = RunSettings::new(cli, module_registry, &profile)?;
+
+    // Tool calls can't follow the schema, so structured output runs without tools
+    let schema = cli.schema.as_deref().map(load_schema).transpose()?;
+    if let Some((schema, _)) = &schema {
+        settings.config.format = Some(schema.clone());
+        settings.config.tools = None;
+        settings.router = None;
+        settings.modules_for_prompt = String::new();
+        settings.registry = Arc::new(settings.registry.scoped(ModuleScope {
+            no_tools: true,
+            ..Default::default()
+        })?);
+    }
 
-    let config = OllamaConfig::new()
-        .host(HOST.to_string())
-        .model(LLM_MODEL.to_string())
-        .port(PORT)
-        .tools(tools_for_payload)
-        // .options(options)
-        .build()?;
+    let mut client =
+        create_ollama_client(settings.config.clone(), settings.registry.clone()).await?;
+    client.set_approval_policy(settings.approval);
+    client.set_tool_router(settings.router.clone());
 
-    let mut client = create_ollama_client(config, module_registry.clone())?;
+    for path in &cli.image {
+        client.attach_image(load_image(path)?).await?;
+    }
 
     let prompt_text: Option<String> = cli.text()?;
     let prompt = prompt_text.as_deref().ok_or(AppError::InvalidInput)?;
 
-    let file_path = cli.input.as_deref().unwrap_or("");
-
-    // Load file content if provided
-    let file_content = if file_path.is_empty() {
-        String::new()
-    } else {
-        streamer
-            .handle_event(StreamEvent::Status(format!("Loading file: {}", file_path)))
-            .await?;
-
-        log::info!("Loading file content from: {}", file_path);
-        get_file_content(file_path.to_string())?
-    };
-
-    let oneshot_prompt = SYSTEM_PROMPT
-        .replace("__CONTEXT__", &file_content)
-        .replace("__MODULES__", &modules_for_prompt);
+    let oneshot_prompt = build_system_prompt(
+        cli,
+        &settings,
+        client.provider(),
+        Some(prompt),
+        &mut streamer,
+    )
+    .await?;
 
     log::info!("One shot prompt: {}", oneshot_prompt);
 
     client.set_system_message(&oneshot_prompt);
-    client.chat_streaming(prompt, &mut streamer).await?;
+
+    if let Some((schema, validator)) = &schema {
+        let value =
+            generate_structured(&mut client, prompt, schema, validator, cli.schema_retries).await?;
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        if cli.stats {
+            print_stats(&settings.config.model, client.total_usage());
+        }
+        return Ok(());
+    }
+
+    let response = client.chat_streaming(prompt, &mut streamer).await?;
 
     streamer.finish().await?;
+
+    if cli.stats {
+        print_stats(&settings.config.model, client.total_usage());
+    }
+
+    if cli.apply || cli.dry_run {
+        apply_file_blocks(&response, cli.dry_run).await?;
+    }
     log::info!("Prompt processing completed successfully");
 
     Ok(())
@@ -0,0 +1,75 @@
+use crate::{
+    AppResult,
+    files::{FileBlock, parse_file_blocks, unified_diff, validate_block_path},
+    utils::{confirm, write_atomic},
+};
+use std::{fs, io, path::Path};
+
+/// What writing a block would do to the working directory
+fn block_status(current: Option<&str>, block: &FileBlock) -> &'static str {
+    match current {
+        None => "new",
+        Some(current) if current == block.content => "unchanged",
+        Some(_) => "modified",
+    }
+}
+
+fn read_current(path: &str) -> AppResult<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes the file blocks of a response after showing their diff and asking
+/// for confirmation. With `dry_run` the proposed files are only listed.
+pub async fn apply_file_blocks(response: &str, dry_run: bool) -> AppResult<()> {
+    let blocks = parse_file_blocks(response);
+    if blocks.is_empty() {
+        eprintln!("No file blocks found in the response");
+        return Ok(());
+    }
+
+    eprintln!();
+    for block in &blocks {
+        if let Err(e) = validate_block_path(&block.path) {
+            eprintln!("Skipping: {}", e);
+            continue;
+        }
+
+        let current = read_current(&block.path)?;
+        let status = block_status(current.as_deref(), block);
+
+        if dry_run {
+            println!(
+                "{} ({}, {} lines)",
+                block.path,
+                status,
+                block.content.lines().count()
+            );
+            continue;
+        }
+
+        if status == "unchanged" {
+            eprintln!("{} is unchanged", block.path);
+            continue;
+        }
+
+        eprint!(
+            "{}",
+            unified_diff(&block.path, current.as_deref(), &block.content)
+        );
+
+        if !confirm(&format!("Write {} ({})?", block.path, status))? {
+            eprintln!("Skipped {}", block.path);
+            continue;
+        }
+
+        write_atomic(Path::new(&block.path), &block.content)?;
+        log::info!("Wrote file block to {}", block.path);
+        eprintln!("Wrote {}", block.path);
+    }
+
+    Ok(())
+}
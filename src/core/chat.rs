@@ -0,0 +1,381 @@
+use super::agent::{RunSettings, active_profile, build_system_prompt, print_stats};
+use crate::{
+    AppError, AppResult, Cli,
+    model::{Message, MessageRole},
+    modules::ModuleRegistry,
+    profile::{Profile, list_profiles, load_profile},
+    providers::{OllamaClient, create_ollama_client},
+    session::{Session, list_sessions, load_session, usage_by_model},
+    streaming::{CliStreamer, OutputStreamer, create_cli_streamer},
+    utils::load_image,
+};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::Arc,
+};
+
+const CHAT_HELP: &str = r#"Commands:
+  /profile           List profiles
+  /profile <name>    Switch to another profile, keeping the conversation
+  /image <path>      Attach an image to your next message
+  /regenerate        Answer your last message again
+  /edit <id> <text>  Replace one of your messages and answer from there
+  /history           Show the messages of the current branch
+  /branches          List the branches of the conversation
+  /branch <id>       Switch to the branch ending at message <id>
+  /fork <id>         Start a new branch after message <id>
+  /save <name>       Save the conversation, and keep saving it after each turn
+  /load <name>       Resume a saved conversation
+  /sessions          List saved conversations
+  /stats             Show token usage of the conversation per model
+  /clear             Start a new conversation
+  /help              Show this help
+  /exit              Quit the chat"#;
+
+/// Chat session state that commands can change
+struct ChatSession {
+    profile: Profile,
+    client: OllamaClient,
+    /// Where the conversation is saved, if it is
+    saved: Option<Session>,
+}
+
+impl ChatSession {
+    /// Shows the usage of the last answer when `--stats` is set
+    fn show_stats(&self, cli: &Cli) {
+        if cli.stats {
+            print_stats(&self.client.config().model, self.client.last_usage());
+        }
+    }
+
+    /// Saves the conversation and the state of the modules if it has a name
+    fn persist(&mut self, registry: &ModuleRegistry) -> AppResult<()> {
+        if let Some(saved) = &mut self.saved {
+            saved.context = self.client.get_context().clone();
+            saved.profile = self.profile.name.clone();
+            saved.modules = registry.states();
+            saved.save()?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn start_client(
+    cli: &Cli,
+    registry: &Arc<ModuleRegistry>,
+    profile: &Profile,
+    streamer: &mut CliStreamer,
+) -> AppResult<OllamaClient> {
+    let settings = RunSettings::new(cli, registry, profile)?;
+
+    let mut client =
+        create_ollama_client(settings.config.clone(), settings.registry.clone()).await?;
+    client.set_approval_policy(settings.approval);
+    client.set_tool_router(settings.router.clone());
+
+    let system_prompt =
+        build_system_prompt(cli, &settings, client.provider(), None, streamer).await?;
+    log::info!("Chat system prompt: {}", system_prompt);
+    client.set_system_message(&system_prompt);
+
+    Ok(client)
+}
+
+/// Resumes a saved conversation with the profile it was using
+async fn resume_session(
+    cli: &Cli,
+    registry: &Arc<ModuleRegistry>,
+    saved: Session,
+    streamer: &mut CliStreamer,
+) -> AppResult<ChatSession> {
+    let profile = load_profile(&saved.profile)?;
+    let mut client = start_client(cli, registry, &profile, streamer).await?;
+    *client.get_context_mut() = saved.context.clone();
+    registry.restore_states(&saved.modules)?;
+
+    Ok(ChatSession {
+        profile,
+        client,
+        saved: Some(saved),
+    })
+}
+
+/// First line of a message, shortened for listings
+fn preview(message: &Message) -> String {
+    let line = message.content.lines().next().unwrap_or_default();
+    if line.chars().count() > 60 {
+        format!("{}...", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn role_label(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "you",
+        MessageRole::Assistant => "jarvis",
+    }
+}
+
+fn parse_message_id(arg: &str) -> AppResult<usize> {
+    arg.trim_start_matches('#')
+        .parse()
+        .map_err(|_| AppError::from(&format!("Invalid message id {} (see /history)", arg)))
+}
+
+pub async fn process_chat(
+    cli: &Cli,
+    registry: &Arc<ModuleRegistry>,
+    session_name: Option<&str>,
+) -> AppResult<()> {
+    let mut streamer = create_cli_streamer(false);
+
+    let saved = session_name.map(load_session).transpose()?.flatten();
+    let mut session = match saved {
+        Some(saved) => {
+            let session = resume_session(cli, registry, saved, &mut streamer).await?;
+            println!("Resumed session {}", session_name.unwrap_or_default());
+            session
+        }
+        None => {
+            let profile = active_profile(cli)?;
+            let client = start_client(cli, registry, &profile, &mut streamer).await?;
+            let saved = session_name
+                .map(|name| Session::new(name, &profile.name, client.get_context().clone()))
+                .transpose()?;
+            ChatSession {
+                profile,
+                client,
+                saved,
+            }
+        }
+    };
+
+    for path in &cli.image {
+        session.client.attach_image(load_image(path)?).await?;
+    }
+
+    println!(
+        "Chatting with profile {} (/help for commands, /exit to quit)",
+        session.profile.name
+    );
+
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let result = if let Some(command) = line.strip_prefix('/') {
+            match run_command(cli, registry, &mut session, command, &mut streamer).await {
+                Ok(true) => Ok(()),
+                Ok(false) => break,
+                Err(e) => Err(e),
+            }
+        } else {
+            let result = session
+                .client
+                .chat_streaming(line, &mut streamer)
+                .await
+                .map(|_| ());
+            streamer.finish().await?;
+            if result.is_ok() {
+                session.show_stats(cli);
+            }
+            result
+        };
+
+        // A failed turn shouldn't end the whole session
+        if let Err(e) = result.and_then(|_| session.persist(registry)) {
+            log::error!("Chat error: {}", e);
+            eprintln!("Error: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a `/command`, returning false when the chat should end
+async fn run_command(
+    cli: &Cli,
+    registry: &Arc<ModuleRegistry>,
+    session: &mut ChatSession,
+    command: &str,
+    streamer: &mut CliStreamer,
+) -> AppResult<bool> {
+    let (name, arg) = command
+        .split_once(' ')
+        .map(|(name, arg)| (name, arg.trim()))
+        .unwrap_or((command, ""));
+
+    match name {
+        "exit" | "quit" => return Ok(false),
+        "help" => println!("{}", CHAT_HELP),
+        "clear" => {
+            session.client = start_client(cli, registry, &session.profile, streamer).await?;
+            session.saved = None;
+            registry.restore_states(&BTreeMap::new())?;
+            println!("Started a new conversation");
+        }
+        "image" if arg.is_empty() => println!("Usage: /image <path>"),
+        "image" => {
+            session.client.attach_image(load_image(arg)?).await?;
+            println!(
+                "Attached {} ({} image(s) will be sent with your next message)",
+                arg,
+                session.client.pending_images()
+            );
+        }
+        "regenerate" => {
+            let result = session.client.regenerate_streaming(streamer).await;
+            streamer.finish().await?;
+            result?;
+            session.show_stats(cli);
+        }
+        "edit" => {
+            let Some((id, text)) = arg.split_once(' ') else {
+                println!("Usage: /edit <id> <text>");
+                return Ok(true);
+            };
+
+            let id = parse_message_id(id)?;
+            let result = session
+                .client
+                .edit_streaming(id, text.trim(), streamer)
+                .await;
+            streamer.finish().await?;
+            result?;
+            session.show_stats(cli);
+        }
+        "history" => {
+            for (id, message) in session.client.get_context().history() {
+                if matches!(message.role, MessageRole::System) {
+                    continue;
+                }
+                println!(
+                    "#{:<4} {:<7} {}",
+                    id,
+                    role_label(&message.role),
+                    preview(message)
+                );
+            }
+        }
+        "branches" => {
+            let context = session.client.get_context();
+            for id in context.branches() {
+                let marker = if Some(id) == context.head() { "*" } else { " " };
+                let message = context.message(id).expect("branch ends are messages");
+                println!(
+                    "{} #{:<4} {:<7} {}",
+                    marker,
+                    id,
+                    role_label(&message.role),
+                    preview(message)
+                );
+            }
+        }
+        "branch" | "fork" if arg.is_empty() => println!("Usage: /{} <id>", name),
+        "branch" | "fork" => {
+            let id = parse_message_id(arg)?;
+            if !session.client.get_context_mut().checkout(id) {
+                return Err(AppError::from(&format!("Message #{} not found", id)));
+            }
+
+            if name == "fork" {
+                println!(
+                    "Forked after #{}, your next message starts a new branch",
+                    id
+                );
+            } else {
+                println!("Switched to the branch ending at #{}", id);
+            }
+        }
+        "save" if arg.is_empty() => println!("Usage: /save <name>"),
+        "save" => {
+            session.saved = Some(Session::new(
+                arg,
+                &session.profile.name,
+                session.client.get_context().clone(),
+            )?);
+            println!("Saved the conversation as {}", arg);
+        }
+        "load" if arg.is_empty() => println!("Usage: /load <name>"),
+        "load" => {
+            let saved = load_session(arg)?
+                .ok_or_else(|| AppError::from(&format!("Session {} not found", arg)))?;
+            *session = resume_session(cli, registry, saved, streamer).await?;
+            println!(
+                "Resumed session {} with profile {}",
+                arg, session.profile.name
+            );
+        }
+        "stats" => {
+            for (model, usage) in usage_by_model(session.client.get_context()) {
+                println!("{:<24} {}", model, usage);
+            }
+        }
+        "sessions" => {
+            for saved in list_sessions()? {
+                println!(
+                    "{:<24} {:<12} {}",
+                    saved.name,
+                    saved.profile,
+                    saved.updated_at.format("%Y-%m-%d %H:%M")
+                );
+            }
+        }
+        "profile" if arg.is_empty() => {
+            for profile in list_profiles()? {
+                let marker = if profile.name == session.profile.name {
+                    "*"
+                } else {
+                    " "
+                };
+                println!(
+                    "{} {:<16} {}",
+                    marker,
+                    profile.name,
+                    profile.description.unwrap_or_default()
+                );
+            }
+        }
+        "profile" => {
+            let profile = load_profile(arg)?;
+            let mut client = start_client(cli, registry, &profile, streamer).await?;
+
+            // Carry the whole conversation tree over, only the persona changes
+            let system_prompt = client
+                .get_context()
+                .get_messages()
+                .into_iter()
+                .find(|m| matches!(m.role, MessageRole::System))
+                .map(|m| m.content);
+            let mut context = session.client.get_context().clone();
+            if let Some(prompt) = system_prompt
+                && !context.replace_system_message(&prompt)
+                && context.is_empty()
+            {
+                context.add_system_message(prompt);
+            }
+            *client.get_context_mut() = context;
+
+            session.client = client;
+            session.profile = profile;
+            println!("Switched to profile {}", session.profile.name);
+        }
+        _ => println!("Unknown command /{} (try /help)", name),
+    }
+
+    Ok(true)
+}
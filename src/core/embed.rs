@@ -0,0 +1,41 @@
+use super::{EMBEDDING_MODEL, HOST, LLM_MODEL, PORT};
+use crate::{
+    AppError, AppResult,
+    model::ModelProvider,
+    providers::{OllamaConfig, OllamaProvider},
+};
+use std::io::{self, BufRead};
+
+pub async fn process_embed_command(model: Option<&str>, texts: &[String]) -> AppResult<()> {
+    let config = OllamaConfig::new()
+        .host(HOST.to_string())
+        .model(LLM_MODEL.to_string())
+        .embedding_model(model.unwrap_or(EMBEDDING_MODEL).to_string())
+        .port(PORT)
+        .build()?;
+
+    let texts = if texts.is_empty() {
+        io::stdin()
+            .lock()
+            .lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        texts.to_vec()
+    };
+
+    if texts.is_empty() {
+        return Err(AppError::InvalidInput);
+    }
+
+    log::info!(
+        "Embedding {} texts with {}",
+        texts.len(),
+        config.embedding_model_name()
+    );
+    let embeddings = OllamaProvider::new().embed(&texts, &config).await?;
+
+    println!("{}", serde_json::to_string(&embeddings)?);
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use super::{EMBEDDING_MODEL, HOST, LLM_MODEL, PORT};
+use crate::{
+    AppError, AppResult,
+    index::{Chunk, DocumentIndex, IndexedChunk, chunk_text, collect_files, index_path},
+    model::ModelProvider,
+    providers::{OllamaConfig, OllamaProvider},
+    streaming::{OutputStreamer, ProgressInfo, StreamEvent, create_cli_streamer},
+};
+use std::{env, fs, path::Path};
+
+/// Number of chunks retrieved into the context for each prompt
+const RETRIEVAL_TOP_K: usize = 4;
+
+pub async fn process_index_command(
+    paths: &[String],
+    model: Option<&str>,
+    clear: bool,
+) -> AppResult<()> {
+    let index_file = index_path();
+    let mut index = DocumentIndex::load(&index_file)?.unwrap_or_default();
+
+    if clear {
+        index = DocumentIndex::default();
+        index.save(&index_file)?;
+        println!("Cleared index at {}", index_file.display());
+    }
+
+    if paths.is_empty() {
+        if !clear {
+            print_index_summary(&index, &index_file);
+        }
+        return Ok(());
+    }
+
+    let embedding_model = model
+        .map(str::to_string)
+        .or_else(|| (!index.embedding_model.is_empty()).then(|| index.embedding_model.clone()))
+        .unwrap_or_else(|| EMBEDDING_MODEL.to_string());
+
+    if !index.is_empty() && index.embedding_model != embedding_model {
+        return Err(AppError::from(&format!(
+            "Index was built with {}, re-run with --clear to switch to {}",
+            index.embedding_model, embedding_model
+        )));
+    }
+    index.embedding_model = embedding_model.clone();
+
+    let config = OllamaConfig::new()
+        .host(HOST.to_string())
+        .model(LLM_MODEL.to_string())
+        .embedding_model(embedding_model)
+        .port(PORT)
+        .build()?;
+    let provider = OllamaProvider::new();
+    let mut streamer = create_cli_streamer(true);
+
+    let files = collect_files(paths)?;
+    let total = files.len() as u64;
+
+    for (i, file) in files.iter().enumerate() {
+        let path = fs::canonicalize(file)?.display().to_string();
+
+        streamer
+            .handle_event(StreamEvent::Progress(ProgressInfo {
+                current: i as u64 + 1,
+                total: Some(total),
+                message: format!("Indexing {}", file.display()),
+            }))
+            .await?;
+
+        let bytes = fs::read(file)?;
+        if bytes.contains(&0) {
+            log::info!("Skipping binary file {}", path);
+            continue;
+        }
+
+        let content = String::from_utf8_lossy(&bytes);
+        let chunks = chunk_text(&path, &content);
+        let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+        let embeddings = provider.embed(&texts, &config).await?;
+
+        let indexed = chunks
+            .into_iter()
+            .zip(embeddings)
+            .map(|(chunk, embedding)| IndexedChunk { chunk, embedding })
+            .collect();
+        index.replace_file(&path, indexed);
+    }
+
+    index.save(&index_file)?;
+
+    streamer
+        .handle_event(StreamEvent::Status(format!(
+            "Indexed {} files ({} chunks total)",
+            total,
+            index.chunks.len()
+        )))
+        .await?;
+    streamer.finish().await?;
+
+    Ok(())
+}
+
+fn print_index_summary(index: &DocumentIndex, index_file: &Path) {
+    if index.is_empty() {
+        println!("Index is empty. Run `jarvis index <paths>` to add files");
+        return;
+    }
+
+    println!(
+        "Index at {} (embedding model: {})",
+        index_file.display(),
+        index.embedding_model
+    );
+    for (path, chunks) in index.files() {
+        println!("  {} ({} chunks)", display_path(path), chunks);
+    }
+}
+
+/// Shows paths relative to the working directory when possible
+fn display_path(path: &str) -> String {
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| {
+            Path::new(path)
+                .strip_prefix(cwd)
+                .ok()
+                .map(|p| p.display().to_string())
+        })
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn format_source(chunk: &Chunk) -> String {
+    format!(
+        "<source cite=\"{}:{}-{}\">\n{}\n</source>",
+        display_path(&chunk.path),
+        chunk.start_line,
+        chunk.end_line,
+        chunk.text
+    )
+}
+
+/// Retrieves the indexed chunks most relevant to the prompt, formatted for the
+/// context section. Returns an empty string if there is no index.
+pub async fn retrieve_context(
+    provider: &OllamaProvider,
+    config: &OllamaConfig,
+    prompt: &str,
+) -> AppResult<String> {
+    let Some(index) = DocumentIndex::load(&index_path())? else {
+        return Ok(String::new());
+    };
+
+    if index.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut config = config.clone();
+    config.embedding_model = Some(index.embedding_model.clone());
+
+    let query = provider
+        .embed(&[prompt.to_string()], &config)
+        .await?
+        .pop()
+        .unwrap_or_default();
+
+    let sources: Vec<String> = index
+        .search(&query, RETRIEVAL_TOP_K)
+        .into_iter()
+        .map(|(score, chunk)| {
+            log::info!("Retrieved {} (score {:.3})", chunk.citation(), score);
+            format_source(chunk)
+        })
+        .collect();
+
+    Ok(sources.join("\n"))
+}
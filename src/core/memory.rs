@@ -0,0 +1,110 @@
+use super::EMBEDDING_MODEL;
+use crate::{
+    AppResult,
+    memory::{Memory, MemoryStore, memory_path},
+    model::ModelProvider,
+    providers::{OllamaConfig, OllamaProvider},
+};
+
+/// Number of memories injected into the context
+const MEMORY_TOP_K: usize = 5;
+
+/// Memories less similar than this to the prompt are left out
+const MIN_MEMORY_SIMILARITY: f32 = 0.5;
+
+/// Embeds the memories that don't have an embedding yet (all of them when the
+/// embedding model changed) and the prompt, returning the prompt embedding
+async fn embed_memories(
+    store: &mut MemoryStore,
+    provider: &OllamaProvider,
+    config: &OllamaConfig,
+    prompt: &str,
+) -> AppResult<Vec<f32>> {
+    let model = config.embedding_model_name().to_string();
+    if store.embedding_model.as_deref() != Some(model.as_str()) {
+        for memory in &mut store.memories {
+            memory.embedding = None;
+        }
+        store.embedding_model = Some(model);
+    }
+
+    let missing: Vec<usize> = (0..store.memories.len())
+        .filter(|i| store.memories[*i].embedding.is_none())
+        .collect();
+
+    let mut texts: Vec<String> = missing
+        .iter()
+        .map(|i| store.memories[*i].fact.clone())
+        .collect();
+    texts.push(prompt.to_string());
+
+    let mut embeddings = provider.embed(&texts, config).await?;
+    let query = embeddings.pop().unwrap_or_default();
+
+    if !missing.is_empty() {
+        for (i, embedding) in missing.into_iter().zip(embeddings) {
+            store.memories[i].embedding = Some(embedding);
+        }
+        store.save(&memory_path())?;
+    }
+
+    Ok(query)
+}
+
+/// Memories relevant to the prompt, formatted for the context section. Uses
+/// embeddings when the embedding model is available and keywords otherwise.
+/// Without a prompt (e.g. at the start of a chat) the latest memories are used.
+pub async fn recall_memories(
+    provider: &OllamaProvider,
+    config: &OllamaConfig,
+    prompt: Option<&str>,
+) -> AppResult<String> {
+    let mut store = MemoryStore::load(&memory_path())?;
+    if store.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut config = config.clone();
+    if config.embedding_model.is_none() {
+        config.embedding_model = Some(EMBEDDING_MODEL.to_string());
+    }
+
+    let ids: Vec<u64> = match prompt {
+        None => store.recent(MEMORY_TOP_K).iter().map(|m| m.id).collect(),
+        Some(prompt) => {
+            let mut ids: Vec<u64> =
+                match embed_memories(&mut store, provider, &config, prompt).await {
+                    Ok(query) => store
+                        .semantic_search(&query, MEMORY_TOP_K, MIN_MEMORY_SIMILARITY)
+                        .iter()
+                        .map(|m| m.id)
+                        .collect(),
+                    Err(e) => {
+                        log::warn!("Semantic memory search failed, using keywords: {}", e);
+                        Vec::new()
+                    }
+                };
+
+            for memory in store.keyword_search(prompt, MEMORY_TOP_K) {
+                if ids.len() < MEMORY_TOP_K && !ids.contains(&memory.id) {
+                    ids.push(memory.id);
+                }
+            }
+            ids
+        }
+    };
+
+    if ids.is_empty() {
+        return Ok(String::new());
+    }
+
+    let lines: Vec<String> = ids
+        .iter()
+        .filter_map(|id| store.memories.iter().find(|m| m.id == *id))
+        .map(Memory::describe)
+        .map(|line| format!("- {}", line))
+        .collect();
+
+    log::info!("Recalled {} memories", lines.len());
+    Ok(format!("<memories>\n{}\n</memories>", lines.join("\n")))
+}
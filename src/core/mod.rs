@@ -1,3 +1,25 @@
 mod agent;
+mod apply;
+mod chat;
+mod embed;
+mod index;
+mod memory;
+mod models;
+mod modules;
+mod prompt;
+mod structured;
+mod todo;
 
 pub use agent::process_prompt;
+pub use chat::process_chat;
+pub use embed::process_embed_command;
+pub use index::process_index_command;
+pub use models::process_models_command;
+pub use modules::process_modules_command;
+pub use prompt::process_prompt_command;
+pub use todo::process_todo_command;
+
+const HOST: &str = "http://localhost";
+const PORT: u16 = 11434;
+const LLM_MODEL: &str = "llama3.2";
+const EMBEDDING_MODEL: &str = "nomic-embed-text";
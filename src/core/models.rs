@@ -0,0 +1,85 @@
+use super::{HOST, LLM_MODEL, PORT};
+use crate::{
+    AppResult, ModelsCommand,
+    providers::{OllamaConfig, OllamaProvider},
+    streaming::{OutputStreamer, create_cli_streamer},
+    utils::{format_bytes, print_table},
+};
+
+pub async fn process_models_command(command: &ModelsCommand) -> AppResult<()> {
+    let config = OllamaConfig::new()
+        .host(HOST.to_string())
+        .model(LLM_MODEL.to_string())
+        .port(PORT)
+        .build()?;
+    let provider = OllamaProvider::new();
+
+    match command {
+        ModelsCommand::List => {
+            let models = provider.list_models(&config).await?;
+            let rows = models
+                .iter()
+                .map(|m| {
+                    vec![
+                        m.name.clone(),
+                        m.digest.chars().take(12).collect(),
+                        m.details.parameter_size.clone(),
+                        m.details.quantization_level.clone(),
+                        format_bytes(m.size),
+                        m.modified_at.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+
+            print_table(&["NAME", "ID", "PARAMS", "QUANT", "SIZE", "MODIFIED"], rows);
+        }
+        ModelsCommand::Ps => {
+            let models = provider.running_models(&config).await?;
+            let rows = models
+                .iter()
+                .map(|m| {
+                    vec![
+                        m.name.clone(),
+                        format_bytes(m.size),
+                        format_bytes(m.size_vram.unwrap_or(0)),
+                        m.expires_at.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+
+            print_table(&["NAME", "SIZE", "VRAM", "UNTIL"], rows);
+        }
+        ModelsCommand::Show { name } => {
+            let info = provider.show_model(&config, name).await?;
+
+            println!("Model: {}", name);
+            println!("  family         {}", info.details.family);
+            println!("  parameters     {}", info.details.parameter_size);
+            println!("  quantization   {}", info.details.quantization_level);
+            println!("  format         {}", info.details.format);
+            if let Some(context_length) = info.context_length() {
+                println!("  context length {}", context_length);
+            }
+            if let Some(capabilities) = &info.capabilities {
+                println!("  capabilities   {}", capabilities.join(", "));
+            }
+            if let Some(parameters) = &info.parameters {
+                println!("\nParameters:");
+                for line in parameters.lines() {
+                    println!("  {}", line.trim());
+                }
+            }
+        }
+        ModelsCommand::Pull { name } => {
+            let mut streamer = create_cli_streamer(true);
+            provider.pull_model(&config, name, &mut streamer).await?;
+            streamer.finish().await?;
+        }
+        ModelsCommand::Rm { name } => {
+            provider.delete_model(&config, name).await?;
+            println!("Deleted {}", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use crate::{
+    AppError, AppResult, ModulesCommand,
+    modules::{Module, ModuleRegistry, Tool, ToolCallFunction},
+    utils::print_table,
+};
+use serde_json::Value;
+
+pub fn process_modules_command(
+    command: &ModulesCommand,
+    registry: &ModuleRegistry,
+) -> AppResult<()> {
+    match command {
+        ModulesCommand::List => {
+            let rows = registry
+                .registered_modules()
+                .iter()
+                .map(|m| {
+                    let tools: Vec<String> = m
+                        .tools()
+                        .into_iter()
+                        .map(|t| tool_label(*m, &t.function.name))
+                        .collect();
+                    vec![
+                        m.name().to_string(),
+                        tools.join(", "),
+                        m.description().to_string(),
+                    ]
+                })
+                .collect();
+
+            print_table(&["NAME", "TOOLS", "DESCRIPTION"], rows);
+            println!("\n* makes changes, needs --execute");
+        }
+        ModulesCommand::Show { name } => {
+            let module = find_module(registry, name)?;
+
+            println!("Module: {}", module.name());
+            println!("  {}", module.description());
+            println!("\nPrompt:");
+            for line in module.get_prompt().trim().lines() {
+                println!("  {}", line);
+            }
+            println!("\nTools:");
+            for tool in module.tools() {
+                print!("{}", describe_tool(&tool));
+                if module
+                    .mutating_tools()
+                    .contains(&tool.function.name.as_str())
+                {
+                    println!("    Makes changes, needs --execute");
+                }
+            }
+        }
+        ModulesCommand::Call { name, tool, args } => {
+            let module = find_module(registry, name)?;
+            let arguments: Value = serde_json::from_str(args)
+                .map_err(|e| AppError::from(&format!("Invalid --args JSON: {}", e)))?;
+
+            if !module.tools().iter().any(|t| &t.function.name == tool) {
+                return Err(AppError::from(&format!(
+                    "Module {} has no tool {} (see `jarvis modules show {}`)",
+                    name, tool, name
+                )));
+            }
+
+            let result = registry.execute(&ToolCallFunction {
+                name: tool.clone(),
+                module: name.clone(),
+                arguments,
+            })?;
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Name of a tool, with a `*` when it needs `--execute`
+fn tool_label(module: &(dyn Module + Send + Sync), tool: &str) -> String {
+    if module.mutating_tools().contains(&tool) {
+        format!("{}*", tool)
+    } else {
+        tool.to_string()
+    }
+}
+
+fn find_module<'a>(
+    registry: &'a ModuleRegistry,
+    name: &str,
+) -> AppResult<&'a (dyn Module + Send + Sync)> {
+    registry.get_module(name).ok_or_else(|| {
+        AppError::from(&format!(
+            "Module {} not found (see `jarvis modules list`)",
+            name
+        ))
+    })
+}
+
+/// Readable type of a JSON schema, e.g. `number | string` or `array<string>`
+fn schema_type(schema: &Value) -> String {
+    let base = match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => "any".to_string(),
+    };
+
+    match schema.get("items") {
+        Some(items) if base == "array" => format!("array<{}>", schema_type(items)),
+        _ => base,
+    }
+}
+
+/// Signature, description and parameters of a tool, e.g.
+///
+/// ```text
+///   sqrt(value)
+///     Calculates the square root of a number
+///     value  number  required  The number to find the square root of
+/// ```
+fn describe_tool(tool: &Tool) -> String {
+    let function = &tool.function;
+    let empty = serde_json::Map::new();
+    let properties = function
+        .parameters
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .unwrap_or(&empty);
+    let required: Vec<&str> = function
+        .parameters
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    let names: Vec<&str> = properties.keys().map(String::as_str).collect();
+    let mut text = format!(
+        "  {}({})\n    {}\n",
+        function.name,
+        names.join(", "),
+        function.description
+    );
+
+    let rows: Vec<[String; 4]> = properties
+        .iter()
+        .map(|(name, schema)| {
+            let mut description = schema
+                .get("description")
+                .and_then(|d| d.as_str())
+                .unwrap_or_default()
+                .to_string();
+            if let Some(values) = schema.get("enum").and_then(|e| e.as_array()) {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                description = format!("{} (one of {})", description, values.join(", "))
+                    .trim()
+                    .to_string();
+            }
+
+            let required = if required.contains(&name.as_str()) {
+                "required"
+            } else {
+                "optional"
+            };
+            [
+                name.clone(),
+                schema_type(schema),
+                required.to_string(),
+                description,
+            ]
+        })
+        .collect();
+
+    let name_width = rows.iter().map(|r| r[0].len()).max().unwrap_or_default();
+    let type_width = rows.iter().map(|r| r[1].len()).max().unwrap_or_default();
+    for [name, schema_type, required, description] in rows {
+        text.push_str(
+            format!(
+                "    {:<name_width$}  {:<type_width$}  {:<8}  {}",
+                name, schema_type, required, description
+            )
+            .trim_end(),
+        );
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::ToolFunction;
+    use serde_json::json;
+
+    #[test]
+    fn test_describe_tool() {
+        let tool = Tool {
+            tool_type: "function".to_string(),
+            function: ToolFunction {
+                name: "convert".to_string(),
+                module: "math".to_string(),
+                description: "Converts a value".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "values": { "type": "array", "items": { "type": ["number", "string"] } },
+                        "unit": { "type": "string", "enum": ["m", "km"], "description": "Target unit" }
+                    },
+                    "required": ["values"]
+                }),
+            },
+        };
+
+        assert_eq!(
+            describe_tool(&tool),
+            "  convert(unit, values)\n    Converts a value\n    \
+             unit    string                  optional  Target unit (one of \"m\", \"km\")\n    \
+             values  array<number | string>  required\n"
+        );
+    }
+}
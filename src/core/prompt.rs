@@ -0,0 +1,43 @@
+use super::agent::{RunSettings, active_profile, build_system_prompt};
+use crate::{
+    AppResult, Cli, PromptCommand,
+    modules::ModuleRegistry,
+    prompt::{list_templates, prompts_dir},
+    providers::OllamaProvider,
+    streaming::NullStreamer,
+};
+
+pub async fn process_prompt_command(
+    cli: &Cli,
+    command: &PromptCommand,
+    registry: &ModuleRegistry,
+) -> AppResult<()> {
+    match command {
+        PromptCommand::List => {
+            for (name, source) in list_templates()? {
+                println!("{:<16} {}", name, source);
+            }
+            println!("\nUser templates are read from {}", prompts_dir().display());
+        }
+        PromptCommand::Render { text } => {
+            let settings = RunSettings::new(cli, registry, &active_profile(cli)?)?;
+            let prompt = (!text.is_empty()).then(|| text.join(" "));
+
+            let system_prompt = build_system_prompt(
+                cli,
+                &settings,
+                &OllamaProvider::new(),
+                prompt.as_deref(),
+                &mut NullStreamer::new(),
+            )
+            .await?;
+
+            println!("[system]\n{}", system_prompt);
+            if let Some(prompt) = prompt {
+                println!("\n[user]\n{}", prompt);
+            }
+        }
+    }
+
+    Ok(())
+}
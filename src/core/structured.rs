@@ -0,0 +1,134 @@
+use crate::{AppError, AppResult, model::AIClient, model::ModelProvider, streaming::NullStreamer};
+use jsonschema::Validator;
+use serde_json::Value;
+use std::fs;
+
+pub fn load_schema(path: &str) -> AppResult<(Value, Validator)> {
+    let schema: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| AppError::from(&format!("Invalid JSON schema {}: {}", path, e)))?;
+
+    Ok((schema, validator))
+}
+
+/// Parses the JSON in a response, tolerating a surrounding code fence
+fn parse_json_response(response: &str) -> Result<Value, String> {
+    let trimmed = response.trim();
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(trimmed);
+
+    serde_json::from_str(unfenced.trim()).map_err(|e| format!("Response is not valid JSON: {}", e))
+}
+
+/// Problems with the response, empty when it is valid JSON matching the schema
+fn validation_errors(validator: &Validator, response: &str) -> (Option<Value>, Vec<String>) {
+    let value = match parse_json_response(response) {
+        Ok(value) => value,
+        Err(e) => return (None, vec![e]),
+    };
+
+    let errors = validator
+        .iter_errors(&value)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{}: {}", path, e)
+            }
+        })
+        .collect();
+
+    (Some(value), errors)
+}
+
+/// Asks for a response matching the schema, re-prompting with the validation
+/// errors up to `retries` times
+pub async fn generate_structured<P: ModelProvider>(
+    client: &mut AIClient<P>,
+    prompt: &str,
+    schema: &Value,
+    validator: &Validator,
+    retries: usize,
+) -> AppResult<Value> {
+    let mut request = format!(
+        "{}\n\nRespond only with JSON matching this schema:\n{}",
+        prompt,
+        serde_json::to_string_pretty(schema)?
+    );
+
+    for attempt in 0..=retries {
+        let response = client.chat(&request, &mut NullStreamer::new()).await?;
+
+        match validation_errors(validator, &response) {
+            (Some(value), errors) if errors.is_empty() => return Ok(value),
+            (_, errors) => {
+                log::warn!(
+                    "Structured response attempt {} failed: {:?}",
+                    attempt + 1,
+                    errors
+                );
+
+                if attempt == retries {
+                    return Err(AppError::from(&format!(
+                        "Response did not match the schema after {} attempts:\n- {}",
+                        retries + 1,
+                        errors.join("\n- ")
+                    )));
+                }
+
+                request = format!(
+                    "Your response did not match the JSON schema:\n- {}\n\nRespond again with only JSON that fixes these errors.",
+                    errors.join("\n- ")
+                );
+            }
+        }
+    }
+
+    unreachable!("the last attempt always returns")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validator() -> Validator {
+        jsonschema::validator_for(&json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "age": { "type": "integer" } },
+            "required": ["name", "age"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validation_accepts_fenced_json() {
+        let (value, errors) = validation_errors(
+            &validator(),
+            "```json\n{\"name\": \"Ana\", \"age\": 3}\n```",
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!(value, Some(json!({ "name": "Ana", "age": 3 })));
+    }
+
+    #[test]
+    fn test_validation_reports_schema_errors() {
+        let (_, errors) = validation_errors(&validator(), r#"{"name": "Ana", "age": "three"}"#);
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("/age"));
+    }
+
+    #[test]
+    fn test_validation_reports_invalid_json() {
+        let (value, errors) = validation_errors(&validator(), "Sure! Here it is");
+
+        assert!(value.is_none());
+        assert!(errors[0].starts_with("Response is not valid JSON"));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    AppError, AppResult, TodoCommand,
+    notes::{Todo, TodoList, parse_due, todos_path},
+    utils::print_table,
+};
+use chrono::{Local, NaiveDate};
+
+fn print_todos(todos: &[&Todo], today: NaiveDate) {
+    let rows = todos
+        .iter()
+        .map(|t| {
+            let due = match t.done_at {
+                Some(done_at) => format!("done {}", done_at.format("%Y-%m-%d")),
+                None => t.due_label(today),
+            };
+            vec![format!("#{}", t.id), due, t.text.clone()]
+        })
+        .collect();
+
+    print_table(&["ID", "DUE", "TODO"], rows);
+}
+
+/// The todo list of the `notes` module, without going through a model
+pub fn process_todo_command(command: Option<&TodoCommand>) -> AppResult<()> {
+    let path = todos_path();
+    let mut list = TodoList::load(&path)?;
+    let today = Local::now().date_naive();
+
+    match command.unwrap_or(&TodoCommand::List { all: false }) {
+        TodoCommand::List { all } => {
+            let mut todos = list.open();
+            if *all {
+                todos.extend(list.todos.iter().filter(|t| t.done_at.is_some()));
+            }
+
+            if todos.is_empty() {
+                println!("Nothing to do");
+            } else {
+                print_todos(&todos, today);
+            }
+        }
+        TodoCommand::Add { text, due } => {
+            let due = due.as_deref().map(parse_due).transpose()?;
+            let id = list.add(&text.join(" "), due);
+            list.save(&path)?;
+
+            match due {
+                Some(due) => println!("Added #{} (due {})", id, due),
+                None => println!("Added #{}", id),
+            }
+        }
+        TodoCommand::Done { id } => {
+            let text = list
+                .complete(*id)
+                .map(|t| t.text.clone())
+                .ok_or_else(|| AppError::from(&format!("No todo with id {}", id)))?;
+            list.save(&path)?;
+
+            println!("Done #{}: {}", id, text);
+        }
+        TodoCommand::Today => {
+            let todos = list.due_by(today);
+            if todos.is_empty() {
+                println!("Nothing due today");
+            } else {
+                print_todos(&todos, today);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,4 @@
 use crate::modules::ModuleError;
-use log::error;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
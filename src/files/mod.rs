@@ -0,0 +1,173 @@
+use crate::{AppError, AppResult};
+use regex::Regex;
+use similar::TextDiff;
+use std::{
+    path::{Component, Path},
+    sync::LazyLock,
+};
+
+/// Opening fence of a file block, e.g. "```rust name=src/main.rs"
+static FILE_BLOCK_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(`{3,})\s*([^\s`]*)\s+name=(?:"([^"]+)"|'([^']+)'|(\S+))\s*$"#).unwrap()
+});
+
+/// A file proposed by the model in a "```lang name=path" code block
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileBlock {
+    pub path: String,
+    pub language: String,
+    pub content: String,
+}
+
+/// Extracts the file blocks of a response. Blocks may be wrapped with more
+/// than three backticks so inner code blocks are preserved, a block is only
+/// closed by a fence at least as long as the one that opened it.
+pub fn parse_file_blocks(response: &str) -> Vec<FileBlock> {
+    let mut blocks = Vec::new();
+    let mut lines = response.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        let Some(captures) = FILE_BLOCK_HEADER.captures(trimmed) else {
+            // Skip over plain code blocks so their content is never parsed as a header
+            if let Some(fence) = opening_fence(trimmed) {
+                lines.by_ref().find(|l| is_closing_fence(l, fence));
+            }
+            continue;
+        };
+
+        let fence = captures[1].len();
+        let path = (3..=5)
+            .find_map(|i| captures.get(i))
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default();
+
+        let mut content = Vec::new();
+        let mut closed = false;
+        for line in lines.by_ref() {
+            if is_closing_fence(line, fence) {
+                closed = true;
+                break;
+            }
+            content.push(line);
+        }
+
+        // An unclosed block is a truncated response, don't write half a file
+        if !closed {
+            log::warn!("Ignoring unclosed file block for {}", path);
+            break;
+        }
+
+        let mut content = content.join("\n");
+        content.push('\n');
+
+        blocks.push(FileBlock {
+            path,
+            language: captures[2].to_string(),
+            content,
+        });
+    }
+
+    blocks
+}
+
+fn opening_fence(line: &str) -> Option<usize> {
+    let fence = line.chars().take_while(|c| *c == '`').count();
+    (fence >= 3).then_some(fence)
+}
+
+fn is_closing_fence(line: &str, fence: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= fence && trimmed.chars().all(|c| c == '`')
+}
+
+/// Files may only be written inside the working directory
+pub fn validate_block_path(path: &str) -> AppResult<()> {
+    let invalid = path.is_empty()
+        || Path::new(path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+
+    if invalid {
+        return Err(AppError::from(&format!(
+            "Refusing to write {}: only relative paths inside the working directory are allowed",
+            path
+        )));
+    }
+
+    Ok(())
+}
+
+/// Unified diff from the current content of a file (empty if new) to the block
+pub fn unified_diff(path: &str, current: Option<&str>, proposed: &str) -> String {
+    let old_header = match current {
+        Some(_) => format!("a/{}", path),
+        None => "/dev/null".to_string(),
+    };
+
+    TextDiff::from_lines(current.unwrap_or_default(), proposed)
+        .unified_diff()
+        .header(&old_header, &format!("b/{}", path))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_blocks() {
+        let response = "Here you go:\n\n```rust name=src/main.rs\nfn main() {}\n```\n\n```sh\necho not a file\n```\n\n```toml name=\"Cargo.toml\"\n[package]\n```\n";
+
+        let blocks = parse_file_blocks(response);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].path, "src/main.rs");
+        assert_eq!(blocks[0].language, "rust");
+        assert_eq!(blocks[0].content, "fn main() {}\n");
+        assert_eq!(blocks[1].path, "Cargo.toml");
+    }
+
+    #[test]
+    fn test_parse_four_backtick_block_keeps_inner_fences() {
+        let response =
+            "````markdown name=README.md\n# Title\n\n```js\nconsole.log(1);\n```\n````\n";
+
+        let blocks = parse_file_blocks(response);
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].content,
+            "# Title\n\n```js\nconsole.log(1);\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_unclosed_and_nested_blocks() {
+        let plain = "```md\n```rust name=inner.rs\n```\n";
+        assert!(parse_file_blocks(plain).is_empty());
+
+        let truncated = "```rust name=src/lib.rs\npub fn half(";
+        assert!(parse_file_blocks(truncated).is_empty());
+    }
+
+    #[test]
+    fn test_validate_block_path() {
+        assert!(validate_block_path("src/main.rs").is_ok());
+        assert!(validate_block_path("./notes.md").is_ok());
+        assert!(validate_block_path("../outside.rs").is_err());
+        assert!(validate_block_path("/etc/passwd").is_err());
+        assert!(validate_block_path("").is_err());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("a.txt", Some("one\ntwo\n"), "one\nthree\n");
+        assert!(diff.starts_with("--- a/a.txt\n+++ b/a.txt\n"));
+        assert!(diff.contains("-two\n+three\n"));
+
+        let new_file = unified_diff("b.txt", None, "hello\n");
+        assert!(new_file.starts_with("--- /dev/null\n+++ b/b.txt\n"));
+    }
+}
@@ -0,0 +1,104 @@
+use super::Chunk;
+use crate::AppResult;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Target size of a chunk in characters
+const CHUNK_CHARS: usize = 1500;
+
+/// Lines repeated at the start of the next chunk so context isn't cut mid-thought
+const OVERLAP_LINES: usize = 3;
+
+const INDEXABLE_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "rst", "adoc", "org", "rs", "py", "js", "jsx", "ts", "tsx", "go",
+    "java", "kt", "scala", "swift", "c", "h", "cc", "cpp", "hpp", "cs", "rb", "php", "lua", "sh",
+    "bash", "zsh", "fish", "ps1", "sql", "html", "css", "scss", "vue", "svelte", "toml", "yaml",
+    "yml", "json", "xml", "ini", "cfg", "ex", "exs", "hs", "ml", "clj", "dart", "r",
+];
+
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+
+pub fn is_indexable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| INDEXABLE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Expands files and directories into the list of files to index. Files given
+/// explicitly are always included, directories are walked for known extensions.
+pub fn collect_files(paths: &[String]) -> AppResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        let path = PathBuf::from(path);
+        if path.is_dir() {
+            walk_dir(&path, &mut files)?;
+        } else {
+            // Surface a clear IO error for missing files
+            fs::metadata(&path)?;
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) -> AppResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            if !SKIPPED_DIRS.contains(&name) {
+                walk_dir(&path, files)?;
+            }
+        } else if is_indexable(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits text into overlapping chunks of whole lines
+pub fn chunk_text(path: &str, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < lines.len() {
+        let mut end = start;
+        let mut size = 0;
+
+        while end < lines.len() && (end == start || size + lines[end].len() < CHUNK_CHARS) {
+            size += lines[end].len() + 1;
+            end += 1;
+        }
+
+        let text = lines[start..end].join("\n");
+        if !text.trim().is_empty() {
+            chunks.push(Chunk {
+                path: path.to_string(),
+                start_line: start + 1,
+                end_line: end,
+                text,
+            });
+        }
+
+        if end >= lines.len() {
+            break;
+        }
+
+        start = end.saturating_sub(OVERLAP_LINES).max(start + 1);
+    }
+
+    chunks
+}
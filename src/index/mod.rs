@@ -0,0 +1,86 @@
+mod chunker;
+mod store;
+
+pub use chunker::*;
+pub use store::*;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A range of lines from an indexed file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+impl Chunk {
+    /// Citation shown to the model, e.g. `src/main.rs:10-42`
+    pub fn citation(&self) -> String {
+        format!("{}:{}-{}", self.path, self.start_line, self.end_line)
+    }
+}
+
+pub fn index_path() -> PathBuf {
+    crate::utils::data_dir().join("index.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_text_covers_all_lines() {
+        let content = (1..=500)
+            .map(|i| format!("line number {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let chunks = chunk_text("notes.md", &content);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.first().unwrap().start_line, 1);
+        assert_eq!(chunks.last().unwrap().end_line, 500);
+        for pair in chunks.windows(2) {
+            // Consecutive chunks overlap but always make progress
+            assert!(pair[1].start_line <= pair[0].end_line);
+            assert!(pair[1].start_line > pair[0].start_line);
+        }
+    }
+
+    #[test]
+    fn test_chunk_text_skips_blank_content() {
+        assert!(chunk_text("empty.txt", "\n\n   \n").is_empty());
+    }
+
+    #[test]
+    fn test_search_orders_by_similarity() {
+        let mut index = DocumentIndex::default();
+        let chunk = |line: usize| Chunk {
+            path: "a.txt".to_string(),
+            start_line: line,
+            end_line: line,
+            text: String::new(),
+        };
+        index.replace_file(
+            "a.txt",
+            vec![
+                IndexedChunk {
+                    chunk: chunk(1),
+                    embedding: vec![0.0, 1.0],
+                },
+                IndexedChunk {
+                    chunk: chunk(2),
+                    embedding: vec![1.0, 0.1],
+                },
+            ],
+        );
+
+        let results = index.search(&[1.0, 0.0], 5);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.citation(), "a.txt:2-2");
+    }
+}
@@ -0,0 +1,84 @@
+use super::Chunk;
+use crate::{AppResult, utils::write_atomic};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedChunk {
+    #[serde(flatten)]
+    pub chunk: Chunk,
+    pub embedding: Vec<f32>,
+}
+
+/// On-disk vector index of document chunks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentIndex {
+    /// Model used to create the embeddings, queries must use the same one
+    pub embedding_model: String,
+    pub chunks: Vec<IndexedChunk>,
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+impl DocumentIndex {
+    /// Loads the index, returning `None` if it hasn't been created yet
+    pub fn load(path: &Path) -> AppResult<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        write_atomic(path, &serde_json::to_string(self)?)
+    }
+
+    /// Replaces all chunks of a file with new ones
+    pub fn replace_file(&mut self, path: &str, chunks: Vec<IndexedChunk>) {
+        self.chunks.retain(|c| c.chunk.path != path);
+        self.chunks.extend(chunks);
+    }
+
+    /// Number of chunks per indexed file
+    pub fn files(&self) -> BTreeMap<&str, usize> {
+        let mut files = BTreeMap::new();
+        for chunk in &self.chunks {
+            *files.entry(chunk.chunk.path.as_str()).or_insert(0) += 1;
+        }
+        files
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the `top_k` chunks most similar to the query, best first
+    pub fn search(&self, query: &[f32], top_k: usize) -> Vec<(f32, &Chunk)> {
+        let mut scored: Vec<(f32, &Chunk)> = self
+            .chunks
+            .iter()
+            .map(|c| (cosine_similarity(query, &c.embedding), &c.chunk))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(top_k);
+        scored
+    }
+}
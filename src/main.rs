@@ -5,13 +5,20 @@ use std::sync::Arc;
 mod cli;
 mod core;
 mod error;
+mod files;
+mod index;
+mod memory;
 mod model;
 mod modules;
+mod notes;
+mod profile;
+mod prompt;
 mod providers;
+mod session;
 mod streaming;
 mod utils;
 
-pub use crate::cli::{Cli, Commands};
+pub use crate::cli::{Cli, Commands, ModelsCommand, ModulesCommand, PromptCommand, TodoCommand};
 pub use crate::error::AppError;
 pub type AppResult<T, E = crate::error::AppError> = std::result::Result<T, E>;
 
@@ -22,13 +29,40 @@ async fn main() -> AppResult<()> {
     log::info!("Starting Program...");
 
     let registry = Arc::new(modules::ModuleRegistry::new());
-    let modules = registry.list_modules();
 
     let cli = Cli::parse();
 
-    match cli.command {
-        Some(Commands::Chat) => {
+    match &cli.command {
+        Some(Commands::Chat { session }) => {
             log::info!("Starting chat...");
+            core::process_chat(&cli, &registry, session.as_deref()).await?;
+        }
+        Some(Commands::Embed { model, texts }) => {
+            core::process_embed_command(model.as_deref(), texts).await?;
+        }
+        Some(Commands::Index {
+            paths,
+            model,
+            clear,
+        }) => {
+            core::process_index_command(paths, model.as_deref(), *clear).await?;
+        }
+        Some(Commands::Prompt { command }) => {
+            core::process_prompt_command(&cli, command, &registry).await?;
+        }
+        Some(Commands::Models { command }) => {
+            core::process_models_command(command).await?;
+        }
+        Some(Commands::Modules { command }) => {
+            // Tools making changes need --execute here too
+            let registry = registry.scoped(modules::ModuleScope {
+                execute: cli.execute,
+                ..Default::default()
+            })?;
+            core::process_modules_command(command, &registry)?;
+        }
+        Some(Commands::Todo { command }) => {
+            core::process_todo_command(command.as_ref())?;
         }
         None => {
             core::process_prompt(&cli, &registry).await?;
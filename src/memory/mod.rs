@@ -0,0 +1,215 @@
+use crate::{
+    AppResult,
+    index::cosine_similarity,
+    utils::{data_dir, write_atomic},
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Words too common to tell memories apart
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "was", "with", "that", "this", "you", "our", "what", "how", "use",
+    "from", "have", "has", "not", "but", "can", "all", "any", "its", "who", "why", "when", "does",
+];
+
+/// A fact Jarvis was asked to remember
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Memory {
+    pub id: u64,
+    pub fact: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Local>,
+    /// Embedding of the fact, computed the first time it is searched semantically
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// On-disk store of long-term memories
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryStore {
+    next_id: u64,
+    /// Model used for the stored embeddings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    pub memories: Vec<Memory>,
+}
+
+pub fn memory_path() -> PathBuf {
+    data_dir().join("memory.json")
+}
+
+/// Lowercased words worth matching on
+pub fn keywords(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(|w| w.to_lowercase())
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+impl MemoryStore {
+    /// Loads the store, empty if nothing was remembered yet
+    pub fn load(path: &Path) -> AppResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    /// Stores a fact and returns its id. A fact that is already known keeps
+    /// its id and gains the new tags.
+    pub fn remember(&mut self, fact: &str, tags: &[String]) -> u64 {
+        let fact = fact.trim();
+        if let Some(existing) = self
+            .memories
+            .iter_mut()
+            .find(|m| m.fact.eq_ignore_ascii_case(fact))
+        {
+            for tag in tags {
+                if !existing.tags.contains(tag) {
+                    existing.tags.push(tag.clone());
+                }
+            }
+            return existing.id;
+        }
+
+        self.next_id += 1;
+        self.memories.push(Memory {
+            id: self.next_id,
+            fact: fact.to_string(),
+            tags: tags.to_vec(),
+            created_at: Local::now(),
+            embedding: None,
+        });
+
+        self.next_id
+    }
+
+    /// Removes a memory, returning it if it existed
+    pub fn forget(&mut self, id: u64) -> Option<Memory> {
+        let position = self.memories.iter().position(|m| m.id == id)?;
+        Some(self.memories.remove(position))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memories.is_empty()
+    }
+
+    /// Memories sharing words with the query, best first. Tags count double.
+    pub fn keyword_search(&self, query: &str, limit: usize) -> Vec<&Memory> {
+        let query = keywords(query);
+
+        let mut scored: Vec<(usize, &Memory)> = self
+            .memories
+            .iter()
+            .map(|memory| {
+                let fact = keywords(&memory.fact);
+                let tags: Vec<String> = memory.tags.iter().map(|t| t.to_lowercase()).collect();
+                let score = query
+                    .iter()
+                    .map(|word| fact.contains(word) as usize + 2 * tags.contains(word) as usize)
+                    .sum();
+                (score, memory)
+            })
+            .filter(|(score, _)| *score > 0)
+            .collect();
+
+        // Stable sort, so equal scores keep the oldest memory first
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().take(limit).map(|(_, m)| m).collect()
+    }
+
+    /// Memories whose embedding is at least `min_score` similar to the query, best first
+    pub fn semantic_search(&self, query: &[f32], limit: usize, min_score: f32) -> Vec<&Memory> {
+        let mut scored: Vec<(f32, &Memory)> = self
+            .memories
+            .iter()
+            .filter_map(|m| {
+                let score = cosine_similarity(query, m.embedding.as_ref()?);
+                (score >= min_score).then_some((score, m))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(limit).map(|(_, m)| m).collect()
+    }
+
+    /// The most recently stored memories, newest first
+    pub fn recent(&self, limit: usize) -> Vec<&Memory> {
+        self.memories.iter().rev().take(limit).collect()
+    }
+}
+
+impl Memory {
+    /// One line description shown to the model
+    pub fn describe(&self) -> String {
+        if self.tags.is_empty() {
+            format!("[#{}] {}", self.id, self.fact)
+        } else {
+            format!(
+                "[#{}] {} (tags: {})",
+                self.id,
+                self.fact,
+                self.tags.join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> MemoryStore {
+        let mut store = MemoryStore::default();
+        store.remember(
+            "We use tabs for indentation in Go code",
+            &["style".to_string()],
+        );
+        store.remember("Deployments happen on Tuesdays", &["ops".to_string()]);
+        store
+    }
+
+    #[test]
+    fn test_remember_deduplicates() {
+        let mut store = store();
+        let id = store.remember("deployments happen on tuesdays", &["release".to_string()]);
+
+        assert_eq!(id, 2);
+        assert_eq!(store.memories.len(), 2);
+        assert_eq!(store.memories[1].tags, ["ops", "release"]);
+    }
+
+    #[test]
+    fn test_keyword_search() {
+        let store = store();
+
+        let found = store.keyword_search("When do deployments happen?", 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, 2);
+
+        let found = store.keyword_search("code style", 5);
+        assert_eq!(found[0].id, 1);
+
+        assert!(store.keyword_search("the weather", 5).is_empty());
+    }
+
+    #[test]
+    fn test_forget() {
+        let mut store = store();
+
+        assert_eq!(store.forget(1).map(|m| m.id), Some(1));
+        assert!(store.forget(1).is_none());
+        assert_eq!(store.remember("New fact", &[]), 3);
+    }
+}
@@ -1,21 +1,51 @@
-use super::{Context, ModelConfig, ModelProvider};
+use super::{
+    Context, Message, MessageRole, ModelCapabilities, ModelConfig, ModelProvider, Usage,
+    context_window_for,
+};
 use crate::{
     AppError, AppResult,
-    modules::{ModuleRegistry, ToolCall},
+    modules::{ModuleRegistry, ROUTER_MODULE, Tool, ToolCall, ToolRouter, is_tool_search},
     streaming::{NullStreamer, OutputStreamer, ProgressInfo, StreamEvent},
+    utils::confirm,
 };
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// Whether tool calls requested by the model run without asking the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalPolicy {
+    /// Run every tool call
+    #[default]
+    Auto,
+    /// Ask before each tool call
+    Ask,
+    /// Never run tool calls
+    Never,
+}
+
 #[derive(Debug)]
 pub struct AIClient<P: ModelProvider> {
     provider: P,
     config: P::Config,
     context: Context,
     registry: Arc<ModuleRegistry>,
+    capabilities: Option<ModelCapabilities>,
+    approval: ApprovalPolicy,
+    pending_images: Vec<String>,
+    /// Usage of the last answer, across its tool iterations
+    last_usage: Usage,
+    /// Usage since the client was created
+    total_usage: Usage,
+    /// Picks the tools offered for each message when there are too many
+    router: Option<ToolRouter>,
+    /// Rules of the modules whose tools are offered for the current message
+    routed_prompt: Option<String>,
 }
 
 #[allow(dead_code)]
 impl<P: ModelProvider> AIClient<P> {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> AIClientBuilder<P> {
         AIClientBuilder {
             provider: None,
@@ -23,6 +53,229 @@ impl<P: ModelProvider> AIClient<P> {
             modules: None,
             max_context_history: 100,
             system_message: None,
+            approval: ApprovalPolicy::default(),
+        }
+    }
+
+    /// Discovers the model capabilities on first use and adapts the config to
+    /// them, e.g. native tool calling vs. the prompt based JSON protocol
+    pub async fn capabilities(&mut self) -> AppResult<ModelCapabilities> {
+        if let Some(capabilities) = &self.capabilities {
+            return Ok(capabilities.clone());
+        }
+
+        let capabilities = self.provider.capabilities(&self.config).await?;
+        self.config.apply_capabilities(&capabilities);
+        self.capabilities = Some(capabilities.clone());
+
+        Ok(capabilities)
+    }
+
+    /// Attaches a base64 encoded image to the next user message
+    pub async fn attach_image(&mut self, image: String) -> AppResult<()> {
+        if !self.capabilities().await?.vision {
+            return Err(AppError::from(&format!(
+                "Model {} does not support images, pick a vision model (e.g. llava)",
+                self.config.model_name()
+            )));
+        }
+
+        self.pending_images.push(image);
+        Ok(())
+    }
+
+    pub fn pending_images(&self) -> usize {
+        self.pending_images.len()
+    }
+
+    fn add_user_message(&mut self, prompt: &str) {
+        let images = std::mem::take(&mut self.pending_images);
+        self.context
+            .add_user_message_with_images(prompt.to_string(), images);
+    }
+
+    /// Keeps the usage of an answer, and stores it with the answer so saved
+    /// sessions can be compared across models
+    fn record_usage(&mut self, usage: Usage) {
+        self.last_usage = usage;
+        self.total_usage += usage;
+
+        if let Some(head) = self.context.head()
+            && matches!(
+                self.context.message(head).map(|m| &m.role),
+                Some(MessageRole::Assistant)
+            )
+        {
+            self.context.set_metadata(
+                head,
+                serde_json::json!({ "model": self.config.model_name(), "usage": usage }),
+            );
+        }
+    }
+
+    pub fn last_usage(&self) -> Usage {
+        self.last_usage
+    }
+
+    pub fn total_usage(&self) -> Usage {
+        self.total_usage
+    }
+
+    /// Grows the context window of the config so the messages fit
+    async fn fit_context_window(&mut self, messages: &[Message]) -> AppResult<()> {
+        let max = self.capabilities().await?.context_length;
+        let current = self.config.context_window();
+        let window = context_window_for(messages, current, max);
+
+        if window != current {
+            log::info!("Resizing context window from {} to {}", current, window);
+            self.config.set_context_window(window);
+        }
+
+        Ok(())
+    }
+
+    /// Embeds the query for tool routing, with the tools the first time.
+    /// Returns `None` when routing uses keywords only.
+    async fn embed_for_routing(&mut self, query: &str) -> Option<Vec<f32>> {
+        let router = self.router.as_ref().filter(|r| r.wants_embeddings())?;
+
+        let mut texts = if router.has_embeddings() {
+            Vec::new()
+        } else {
+            router.tool_texts()
+        };
+        texts.push(query.to_string());
+
+        match self.provider.embed(&texts, &self.config).await {
+            Ok(mut embeddings) => {
+                let query = embeddings.pop();
+                if let Some(router) = self.router.as_mut()
+                    && !embeddings.is_empty()
+                {
+                    router.set_embeddings(embeddings);
+                }
+                query
+            }
+            Err(e) => {
+                log::warn!("Routing tools by keywords, embeddings failed: {}", e);
+                if let Some(router) = self.router.as_mut() {
+                    router.disable_embeddings();
+                }
+                None
+            }
+        }
+    }
+
+    /// Offers the tools relevant to the last user message, when routing
+    async fn route_tools(&mut self) {
+        if self.router.is_none() {
+            return;
+        }
+
+        let query = self.context.get_last_user_prompt().unwrap_or_default();
+        let query_embedding = self.embed_for_routing(&query).await;
+        if let Some(router) = &self.router {
+            let tools = router.route(&query, query_embedding.as_deref());
+            self.offer_tools(tools);
+        }
+    }
+
+    /// Offers `tools` to the model, along with the rules of their modules
+    fn offer_tools(&mut self, tools: Vec<Tool>) {
+        let mut modules: Vec<&str> = tools
+            .iter()
+            .map(|t| t.function.module.as_str())
+            .filter(|m| *m != ROUTER_MODULE)
+            .collect();
+        modules.sort();
+        modules.dedup();
+
+        let rules: String = modules
+            .iter()
+            .filter_map(|m| self.registry.get_module(m))
+            .map(|m| format!("{}\n", m.get_prompt()))
+            .collect();
+        log::info!(
+            "Offering tools: {}",
+            tools
+                .iter()
+                .map(|t| format!("{}.{}", t.function.module, t.function.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        self.routed_prompt = (!rules.is_empty()).then(|| format!("<modules>\n{}</modules>", rules));
+        self.config.set_tools(Some(tools));
+    }
+
+    /// Loads the tools matching the query of a `search_tools` call
+    async fn search_tools(&mut self, arguments: &serde_json::Value) -> serde_json::Value {
+        let Some(query) = arguments.get("query").and_then(|q| q.as_str()) else {
+            return serde_json::json!({ "error": "Missing 'query' argument" });
+        };
+
+        let query_embedding = self.embed_for_routing(query).await;
+        let Some(router) = self.router.as_mut() else {
+            return serde_json::json!({ "error": "Every tool is already offered" });
+        };
+
+        let mut offered = self.config.tools().unwrap_or_default();
+        let found = router.search(query, query_embedding.as_deref(), &offered);
+        if found.is_empty() {
+            return serde_json::json!({ "error": format!("No tool found for '{}'", query) });
+        }
+
+        let loaded: Vec<serde_json::Value> = found
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "name": t.function.name,
+                    "module": t.function.module,
+                    "description": t.function.description,
+                    "parameters": t.function.parameters,
+                })
+            })
+            .collect();
+
+        offered.extend(found);
+        self.offer_tools(offered);
+        serde_json::json!({ "loaded": loaded })
+    }
+
+    /// Messages of the current branch as sent to the model, with the rules
+    /// of the routed modules appended to the system prompt
+    fn request_messages(&self) -> Vec<Message> {
+        let mut messages = self.context.get_messages();
+
+        if let Some(rules) = &self.routed_prompt {
+            match messages.first_mut() {
+                Some(system) if matches!(system.role, MessageRole::System) => {
+                    system.content = format!("{}\n\n{}", system.content.trim_end(), rules);
+                }
+                _ => messages.insert(
+                    0,
+                    Message {
+                        role: MessageRole::System,
+                        content: rules.clone(),
+                        metadata: None,
+                        images: Vec::new(),
+                    },
+                ),
+            }
+        }
+
+        messages
+    }
+
+    fn is_tool_call_approved(&self, tool_call: &ToolCall) -> AppResult<bool> {
+        match self.approval {
+            ApprovalPolicy::Auto => Ok(true),
+            ApprovalPolicy::Never => Ok(false),
+            ApprovalPolicy::Ask => confirm(&format!(
+                "Run {}.{} with {}?",
+                tool_call.function.module, tool_call.function.name, tool_call.function.arguments
+            )),
         }
     }
 
@@ -34,7 +287,13 @@ impl<P: ModelProvider> AIClient<P> {
         let mut tool_result_context = Vec::new();
 
         for tool_call in tool_calls {
-            let result = self.registry.execute(&tool_call.function)?;
+            let result = if is_tool_search(&tool_call.function) {
+                self.search_tools(&tool_call.function.arguments).await
+            } else if self.is_tool_call_approved(tool_call)? {
+                self.registry.execute(&tool_call.function)?
+            } else {
+                serde_json::json!({ "error": "The user did not allow this tool call" })
+            };
 
             results.push(result.clone());
 
@@ -68,10 +327,15 @@ impl<P: ModelProvider> AIClient<P> {
         prompt: &str,
         streamer: &mut dyn OutputStreamer,
     ) -> AppResult<String> {
-        self.context.add_user_message(prompt.to_string());
+        self.add_user_message(prompt);
+        self.respond_with_tools(streamer).await
+    }
 
+    /// Answers the current branch, running the tool calls the model asks for
+    async fn respond_with_tools(&mut self, streamer: &mut dyn OutputStreamer) -> AppResult<String> {
         let max_iterations = 10;
         let mut iteration = 0;
+        let mut usage = Usage::default();
         let mut final_response = String::new();
 
         streamer
@@ -82,6 +346,8 @@ impl<P: ModelProvider> AIClient<P> {
             }))
             .await?;
 
+        self.route_tools().await;
+
         loop {
             iteration += 1;
             if iteration > max_iterations {
@@ -94,13 +360,15 @@ impl<P: ModelProvider> AIClient<P> {
                 break;
             }
 
-            let messages = self.context.get_messages();
+            let messages = self.request_messages();
             log::debug!("Messages : {:#?}", messages);
+            self.fit_context_window(&messages).await?;
 
             let result = self
                 .provider
                 .generate(&messages, &self.config, &mut NullStreamer::new())
                 .await?;
+            usage += result.usage;
 
             log::debug!("GenerateResult : {:#?}", result);
 
@@ -114,10 +382,8 @@ impl<P: ModelProvider> AIClient<P> {
             if let Some(tool_calls) = &result.tool_calls {
                 let tool_calls_json = serde_json::to_string(tool_calls)?;
                 self.context.add_assistant_message(tool_calls_json);
-                self.execute_tool_calls(&tool_calls).await?;
+                self.execute_tool_calls(tool_calls).await?;
             } else {
-                self.context.add_assistant_message(result.response.clone());
-
                 for c in result.response.chars() {
                     streamer
                         .handle_event(StreamEvent::Token(c.to_string()))
@@ -128,6 +394,7 @@ impl<P: ModelProvider> AIClient<P> {
             }
         }
 
+        self.record_usage(usage);
         Ok(final_response)
     }
 
@@ -136,19 +403,64 @@ impl<P: ModelProvider> AIClient<P> {
         prompt: &str,
         streamer: &mut dyn OutputStreamer,
     ) -> AppResult<String> {
-        if self.provider.supports_tools() {
-            return self.chat_streaming_with_tools(prompt, streamer).await;
+        self.add_user_message(prompt);
+        self.respond_streaming(streamer).await
+    }
+
+    /// Answers the last user message again, keeping the previous answer on
+    /// its own branch
+    pub async fn regenerate_streaming(
+        &mut self,
+        streamer: &mut dyn OutputStreamer,
+    ) -> AppResult<String> {
+        let last_user = self
+            .context
+            .last_user_message()
+            .ok_or_else(|| AppError::from("There is no message to regenerate"))?;
+
+        self.context.checkout(last_user);
+        self.respond_streaming(streamer).await
+    }
+
+    /// Replaces a user message with a new prompt on a new branch and answers it
+    pub async fn edit_streaming(
+        &mut self,
+        id: usize,
+        prompt: &str,
+        streamer: &mut dyn OutputStreamer,
+    ) -> AppResult<String> {
+        match self.context.message(id) {
+            Some(message) if matches!(message.role, MessageRole::User) => {}
+            _ => {
+                return Err(AppError::from(&format!(
+                    "Message #{} is not a user message",
+                    id
+                )));
+            }
+        }
+
+        // The edited message keeps its images
+        if let Some(original) = self.context.rewind_before(id) {
+            self.pending_images = original.images;
         }
 
-        self.context.add_user_message(prompt.to_string());
+        self.chat_streaming(prompt, streamer).await
+    }
+
+    async fn respond_streaming(&mut self, streamer: &mut dyn OutputStreamer) -> AppResult<String> {
+        if !self.registry.all_tools().is_empty() {
+            return self.respond_with_tools(streamer).await;
+        }
 
         let messages = self.context.get_messages();
+        self.fit_context_window(&messages).await?;
         let result = self
             .provider
             .generate_streaming(&messages, &self.config, streamer)
             .await?;
 
         self.context.add_assistant_message(result.response.clone());
+        self.record_usage(result.usage);
         Ok(result.response)
     }
 
@@ -157,15 +469,17 @@ impl<P: ModelProvider> AIClient<P> {
         prompt: &str,
         streamer: &mut dyn OutputStreamer,
     ) -> AppResult<String> {
-        self.context.add_user_message(prompt.to_string());
+        self.add_user_message(prompt);
 
         let messages = self.context.get_messages();
+        self.fit_context_window(&messages).await?;
         let result = self
             .provider
             .generate(&messages, &self.config, streamer)
             .await?;
 
         self.context.add_assistant_message(result.response.clone());
+        self.record_usage(result.usage);
         Ok(result.response)
     }
 
@@ -185,6 +499,10 @@ impl<P: ModelProvider> AIClient<P> {
         &self.context
     }
 
+    pub fn get_context_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
     pub fn config(&self) -> &P::Config {
         &self.config
     }
@@ -193,6 +511,19 @@ impl<P: ModelProvider> AIClient<P> {
         &mut self.config
     }
 
+    pub fn approval_policy(&self) -> ApprovalPolicy {
+        self.approval
+    }
+
+    pub fn set_approval_policy(&mut self, approval: ApprovalPolicy) {
+        self.approval = approval;
+    }
+
+    /// Routes the tools offered for each message instead of offering them all
+    pub fn set_tool_router(&mut self, router: Option<ToolRouter>) {
+        self.router = router;
+    }
+
     pub fn registry(&self) -> &Arc<ModuleRegistry> {
         &self.registry
     }
@@ -208,6 +539,7 @@ pub struct AIClientBuilder<P: ModelProvider> {
     max_context_history: usize,
     modules: Option<Arc<ModuleRegistry>>,
     system_message: Option<String>,
+    approval: ApprovalPolicy,
 }
 
 #[allow(dead_code)]
@@ -219,6 +551,7 @@ impl<P: ModelProvider> AIClientBuilder<P> {
             modules: None,
             max_context_history: 100,
             system_message: None,
+            approval: ApprovalPolicy::default(),
         }
     }
 
@@ -242,12 +575,17 @@ impl<P: ModelProvider> AIClientBuilder<P> {
         self
     }
 
+    pub fn approval_policy(mut self, approval: ApprovalPolicy) -> Self {
+        self.approval = approval;
+        self
+    }
+
     pub fn modules(mut self, modules: Arc<ModuleRegistry>) -> Self {
         self.modules = Some(modules);
         self
     }
 
-    pub fn build(self) -> AppResult<AIClient<P>> {
+    pub async fn build(self) -> AppResult<AIClient<P>> {
         let provider = self
             .provider
             .ok_or_else(|| AppError::from("Provider is required"))?;
@@ -258,13 +596,20 @@ impl<P: ModelProvider> AIClientBuilder<P> {
             .modules
             .unwrap_or(Arc::new(ModuleRegistry::empty_registry()));
 
-        config.validate()?;
+        config.validate().await?;
 
         let mut client = AIClient {
             provider,
             config,
             context: Context::new(self.max_context_history),
             registry: modules,
+            capabilities: None,
+            approval: self.approval,
+            pending_images: Vec::new(),
+            last_usage: Usage::default(),
+            total_usage: Usage::default(),
+            router: None,
+            routed_prompt: None,
         };
 
         if let Some(system_msg) = self.system_message {
@@ -1,10 +1,21 @@
 use super::{Message, MessageRole};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
 
+/// A message in the conversation tree, pointing back to the message it answers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MessageNode {
+    message: Message,
+    parent: Option<usize>,
+}
+
+/// Conversation history as a tree of messages. Regenerating an answer or
+/// editing a message adds a sibling instead of overwriting, so every branch
+/// stays reachable. `head` is the last message of the current branch, and
+/// message ids are stable indexes into the tree.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Context {
-    messages: VecDeque<Message>,
+    nodes: Vec<MessageNode>,
+    head: Option<usize>,
     max_history: usize,
 }
 
@@ -12,43 +23,55 @@ pub struct Context {
 impl Context {
     pub fn new(max_history: usize) -> Self {
         Self {
-            messages: VecDeque::new(),
+            nodes: Vec::new(),
+            head: None,
             max_history,
         }
     }
 
     pub fn add_messages(&mut self, messages: Vec<Message>) {
         for message in messages {
-            self.add_message(message.role, message.content);
-        }
-    }
-
-    fn trim(&mut self) {
-        while self.messages.len() > self.max_history {
-            self.messages.pop_front();
+            self.push(message);
         }
     }
 
     pub fn add_message(&mut self, role: MessageRole, content: String) -> &Message {
-        let message = Message {
+        self.push(Message {
             role,
             content,
             metadata: None,
-        };
-        self.messages.push_back(message);
-
-        // Add memory management support here
+            images: Vec::new(),
+        })
+    }
 
-        self.trim();
+    /// Appends a message to the current branch
+    fn push(&mut self, message: Message) -> &Message {
+        self.nodes.push(MessageNode {
+            message,
+            parent: self.head,
+        });
+        self.head = Some(self.nodes.len() - 1);
 
-        // Usually should never panic since we enforce max history to 0
-        self.messages.back().unwrap()
+        &self.nodes[self.nodes.len() - 1].message
     }
 
     pub fn add_user_message(&mut self, content: String) -> &Message {
         self.add_message(MessageRole::User, content)
     }
 
+    pub fn add_user_message_with_images(
+        &mut self,
+        content: String,
+        images: Vec<String>,
+    ) -> &Message {
+        self.push(Message {
+            role: MessageRole::User,
+            content,
+            metadata: None,
+            images,
+        })
+    }
+
     pub fn add_assistant_message(&mut self, content: String) -> &Message {
         self.add_message(MessageRole::Assistant, content)
     }
@@ -57,27 +80,217 @@ impl Context {
         self.add_message(MessageRole::System, content)
     }
 
+    /// Replaces the content of the system messages at the root of the tree,
+    /// keeping every branch and message id. Returns false when there is none.
+    pub fn replace_system_message(&mut self, content: &str) -> bool {
+        let mut replaced = false;
+        for node in &mut self.nodes {
+            if node.parent.is_none() && matches!(node.message.role, MessageRole::System) {
+                node.message.content = content.to_string();
+                replaced = true;
+            }
+        }
+
+        replaced
+    }
+
+    /// Ids of the messages from the root to `node`
+    fn path(&self, node: Option<usize>) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut current = node;
+        while let Some(id) = current {
+            path.push(id);
+            current = self.nodes[id].parent;
+        }
+
+        path.reverse();
+        path
+    }
+
+    /// Messages of the current branch with their ids
+    pub fn history(&self) -> Vec<(usize, &Message)> {
+        self.path(self.head)
+            .into_iter()
+            .map(|id| (id, &self.nodes[id].message))
+            .collect()
+    }
+
+    /// Messages of the current branch sent to the model, the most recent
+    /// `max_history` of them
     pub fn get_messages(&self) -> Vec<Message> {
-        self.messages.iter().cloned().collect()
+        let path = self.path(self.head);
+        let skip = path.len().saturating_sub(self.max_history);
+
+        path.into_iter()
+            .skip(skip)
+            .map(|id| self.nodes[id].message.clone())
+            .collect()
+    }
+
+    pub fn message(&self, id: usize) -> Option<&Message> {
+        self.nodes.get(id).map(|node| &node.message)
+    }
+
+    pub fn set_metadata(&mut self, id: usize, metadata: serde_json::Value) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.message.metadata = Some(metadata);
+        }
+    }
+
+    /// All messages of every branch
+    pub fn all_messages(&self) -> impl Iterator<Item = &Message> {
+        self.nodes.iter().map(|node| &node.message)
+    }
+
+    /// Id of the last message of the current branch
+    pub fn head(&self) -> Option<usize> {
+        self.head
+    }
+
+    /// Ends of all branches, i.e. messages nothing answers yet. The current
+    /// head is included when a new branch was just forked from it.
+    pub fn branches(&self) -> Vec<usize> {
+        let mut has_children = vec![false; self.nodes.len()];
+        for node in &self.nodes {
+            if let Some(parent) = node.parent {
+                has_children[parent] = true;
+            }
+        }
+
+        (0..self.nodes.len())
+            .filter(|id| !has_children[*id] || Some(*id) == self.head)
+            .collect()
+    }
+
+    /// Makes `id` the end of the current branch, the next message starts a
+    /// new branch from there if it already has answers
+    pub fn checkout(&mut self, id: usize) -> bool {
+        if id >= self.nodes.len() {
+            return false;
+        }
+
+        self.head = Some(id);
+        true
+    }
+
+    /// Moves the current branch back to just before `id`, returning the message
+    pub fn rewind_before(&mut self, id: usize) -> Option<Message> {
+        let node = self.nodes.get(id)?;
+        self.head = node.parent;
+
+        Some(node.message.clone())
+    }
+
+    /// Id of the last user message of the current branch
+    pub fn last_user_message(&self) -> Option<usize> {
+        self.history()
+            .into_iter()
+            .rev()
+            .find(|(_, msg)| matches!(msg.role, MessageRole::User))
+            .map(|(id, _)| id)
     }
 
     pub fn clear(&mut self) {
-        self.messages.clear();
+        self.nodes.clear();
+        self.head = None;
     }
 
+    /// Number of messages in the current branch
     pub fn len(&self) -> usize {
-        self.messages.len()
+        self.path(self.head).len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.messages.is_empty()
+        self.head.is_none()
     }
 
     pub fn get_last_user_prompt(&self) -> Option<String> {
-        self.messages
-            .iter()
-            .rev()
-            .find(|msg| matches!(msg.role, MessageRole::User))
-            .map(|msg| msg.content.clone())
+        self.last_user_message()
+            .map(|id| self.nodes[id].message.content.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(context: &Context) -> Vec<String> {
+        context
+            .get_messages()
+            .into_iter()
+            .map(|m| m.content)
+            .collect()
+    }
+
+    #[test]
+    fn test_rewind_keeps_old_branch() {
+        let mut context = Context::new(100);
+        context.add_system_message("system".to_string());
+        context.add_user_message("question".to_string());
+        context.add_assistant_message("first answer".to_string());
+
+        // Regenerate: answer the same question again
+        let question = context.last_user_message().unwrap();
+        assert!(context.checkout(question));
+        context.add_assistant_message("second answer".to_string());
+
+        assert_eq!(contents(&context), ["system", "question", "second answer"]);
+        assert_eq!(context.branches(), [2, 3]);
+
+        assert!(context.checkout(2));
+        assert_eq!(contents(&context), ["system", "question", "first answer"]);
+    }
+
+    #[test]
+    fn test_edit_creates_sibling() {
+        let mut context = Context::new(100);
+        context.add_user_message("hello".to_string());
+        context.add_assistant_message("hi".to_string());
+
+        let original = context.rewind_before(0).unwrap();
+        assert_eq!(original.content, "hello");
+        assert!(context.is_empty());
+
+        context.add_user_message("hello there".to_string());
+
+        assert_eq!(contents(&context), ["hello there"]);
+        assert_eq!(context.branches(), [1, 2]);
+        assert_eq!(context.len(), 1);
+    }
+
+    #[test]
+    fn test_replace_system_message_keeps_branches() {
+        let mut context = Context::new(100);
+        context.add_system_message("old persona".to_string());
+        context.add_user_message("question".to_string());
+        context.add_assistant_message("first answer".to_string());
+        assert!(context.checkout(1));
+        context.add_assistant_message("second answer".to_string());
+
+        assert!(context.replace_system_message("new persona"));
+
+        assert_eq!(
+            contents(&context),
+            ["new persona", "question", "second answer"]
+        );
+        assert_eq!(context.branches(), [2, 3]);
+        assert!(context.checkout(2));
+        assert_eq!(
+            contents(&context),
+            ["new persona", "question", "first answer"]
+        );
+
+        assert!(!Context::new(100).replace_system_message("persona"));
+    }
+
+    #[test]
+    fn test_get_messages_limits_history() {
+        let mut context = Context::new(2);
+        for i in 0..4 {
+            context.add_user_message(i.to_string());
+        }
+
+        assert_eq!(contents(&context), ["2", "3"]);
+        assert_eq!(context.history().len(), 4);
     }
 }
@@ -17,4 +17,7 @@ pub struct Message {
     pub role: MessageRole,
     pub content: String,
     pub metadata: Option<serde_json::Value>,
+    /// Base64 encoded images attached to the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
 }
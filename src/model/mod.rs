@@ -2,8 +2,10 @@ mod client;
 mod context;
 mod message;
 mod provider;
+mod usage;
 
-pub use client::AIClient;
+pub use client::{AIClient, ApprovalPolicy};
 pub use context::Context;
 pub use message::*;
 pub use provider::*;
+pub use usage::Usage;
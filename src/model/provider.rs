@@ -1,17 +1,73 @@
-use super::Message;
-use crate::{AppResult, modules::ToolCall, streaming::OutputStreamer};
+use super::{Message, Usage};
+use crate::{
+    AppError, AppResult,
+    modules::{Tool, ToolCall},
+    streaming::OutputStreamer,
+};
+
+/// Context window used when nothing else is known about the model
+pub const DEFAULT_CONTEXT_WINDOW: usize = 4096;
+
+/// Tokens kept free in the context window for the model's answer
+const RESPONSE_RESERVE_TOKENS: usize = 2048;
 
 #[derive(Debug, Clone)]
 pub struct GenerateResult {
     pub response: String,
     pub tool_calls: Option<Vec<ToolCall>>,
+    pub usage: Usage,
+}
+
+/// Capabilities of a specific model, discovered at runtime
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelCapabilities {
+    /// Model supports native tool calling
+    pub tools: bool,
+    /// Model accepts image inputs
+    pub vision: bool,
+    /// Maximum context length the model was trained with
+    pub context_length: Option<usize>,
+    /// Prompt template used by the model
+    pub template: Option<String>,
 }
 
+/// Picks a context window large enough for the messages plus an answer. The
+/// window grows in powers of two so the model isn't reloaded on every turn,
+/// never shrinks below `current` and never exceeds the model's `max` length.
+pub fn context_window_for(messages: &[Message], current: usize, max: Option<usize>) -> usize {
+    // Roughly four characters per token for most tokenizers
+    let estimated_tokens: usize = messages.iter().map(|m| m.content.len() / 4 + 4).sum();
+    let needed = (estimated_tokens + RESPONSE_RESERVE_TOKENS)
+        .next_power_of_two()
+        .max(current);
+
+    match max {
+        Some(max) => needed.min(max),
+        None => needed,
+    }
+}
+
+#[async_trait::async_trait]
 pub trait ModelConfig: Send + Sync + Clone {
     #[allow(dead_code)]
     fn model_name(&self) -> &str;
 
-    fn validate(&self) -> AppResult<()>;
+    /// Checks the configuration before the first request is sent
+    async fn validate(&self) -> AppResult<()>;
+
+    /// Adjusts the configuration to what the model can actually do
+    fn apply_capabilities(&mut self, capabilities: &ModelCapabilities);
+
+    /// Context window (in tokens) used for requests
+    fn context_window(&self) -> usize;
+
+    fn set_context_window(&mut self, tokens: usize);
+
+    /// Tools offered to the model
+    fn tools(&self) -> Option<Vec<Tool>>;
+
+    /// Replaces the tools offered to the model
+    fn set_tools(&mut self, tools: Option<Vec<Tool>>);
 }
 
 #[allow(dead_code)]
@@ -33,6 +89,19 @@ pub trait ModelProvider: Send + Sync {
         streamer: &mut dyn OutputStreamer,
     ) -> AppResult<GenerateResult>;
 
+    /// Embeds each text into a vector, in the same order as the input
+    async fn embed(&self, _texts: &[String], _config: &Self::Config) -> AppResult<Vec<Vec<f32>>> {
+        Err(AppError::from(&format!(
+            "Embeddings are not supported by {}",
+            self.provider_name()
+        )))
+    }
+
+    /// Capabilities of the configured model
+    async fn capabilities(&self, _config: &Self::Config) -> AppResult<ModelCapabilities> {
+        Ok(ModelCapabilities::default())
+    }
+
     fn provider_name(&self) -> &'static str;
 
     fn supports_streaming(&self) -> bool {
@@ -42,12 +111,37 @@ pub trait ModelProvider: Send + Sync {
     fn supports_system_messages(&self) -> bool {
         true
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::MessageRole;
+
+    fn message(len: usize) -> Message {
+        Message {
+            role: MessageRole::User,
+            content: "a".repeat(len),
+            metadata: None,
+            images: Vec::new(),
+        }
+    }
 
-    fn supports_tools(&self) -> bool {
-        true
+    #[test]
+    fn test_context_window_keeps_current_for_short_prompts() {
+        let window = context_window_for(&[message(100)], DEFAULT_CONTEXT_WINDOW, Some(131072));
+        assert_eq!(window, DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_context_window_grows_for_long_prompts() {
+        let window = context_window_for(&[message(40_000)], DEFAULT_CONTEXT_WINDOW, Some(131072));
+        assert_eq!(window, 16384);
     }
 
-    fn max_context_length(&self) -> Option<usize> {
-        None
+    #[test]
+    fn test_context_window_is_capped_by_model() {
+        let window = context_window_for(&[message(400_000)], DEFAULT_CONTEXT_WINDOW, Some(8192));
+        assert_eq!(window, 8192);
     }
 }
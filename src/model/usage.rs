@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::AddAssign};
+
+/// Token counts and timings of one or more generations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    /// Number of requests sent to the provider, e.g. one per tool iteration
+    pub requests: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Time spent reading the prompt, in milliseconds
+    pub prompt_ms: u64,
+    /// Time spent generating the completion, in milliseconds
+    pub completion_ms: u64,
+    /// Time of the whole requests, in milliseconds
+    pub total_ms: u64,
+}
+
+impl Usage {
+    /// Generation speed, `None` when there are no timings
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        (self.completion_ms > 0)
+            .then(|| self.completion_tokens as f64 * 1000.0 / self.completion_ms as f64)
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.prompt_ms += other.prompt_ms;
+        self.completion_ms += other.completion_ms;
+        self.total_ms += other.total_ms;
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} prompt + {} completion tokens",
+            self.prompt_tokens, self.completion_tokens
+        )?;
+
+        if let Some(speed) = self.tokens_per_second() {
+            write!(f, ", {:.1} tokens/s", speed)?;
+        }
+
+        write!(f, ", {:.2}s", self.total_ms as f64 / 1000.0)?;
+
+        if self.requests > 1 {
+            write!(f, " over {} requests", self.requests)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_aggregates() {
+        let mut usage = Usage {
+            requests: 1,
+            prompt_tokens: 100,
+            completion_tokens: 20,
+            prompt_ms: 50,
+            completion_ms: 500,
+            total_ms: 600,
+        };
+        usage += Usage {
+            requests: 1,
+            prompt_tokens: 150,
+            completion_tokens: 30,
+            prompt_ms: 50,
+            completion_ms: 500,
+            total_ms: 600,
+        };
+
+        assert_eq!(usage.tokens_per_second(), Some(50.0));
+        assert_eq!(
+            usage.to_string(),
+            "250 prompt + 50 completion tokens, 50.0 tokens/s, 1.20s over 2 requests"
+        );
+    }
+
+    #[test]
+    fn test_usage_without_timings() {
+        let usage = Usage {
+            requests: 1,
+            completion_tokens: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(usage.tokens_per_second(), None);
+        assert_eq!(usage.to_string(), "0 prompt + 10 completion tokens, 0.00s");
+    }
+}
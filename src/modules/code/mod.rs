@@ -0,0 +1,461 @@
+mod sandbox;
+
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use sandbox::{SandboxConfig, WorkDir};
+use serde_json::{Value, json};
+use std::{
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+/// Languages with their interpreter and the file the code is saved as
+const LANGUAGES: &[(&str, &str, &str)] = &[
+    ("python", "python3", "main.py"),
+    ("javascript", "node", "main.js"),
+    ("shell", "sh", "main.sh"),
+];
+
+/// Files returned after a run
+const MAX_FILES: usize = 20;
+
+/// Files longer than this are listed without their content
+const MAX_FILE_CHARS: usize = 4_000;
+
+/// Bytes read from a file the program wrote, enough for `MAX_FILE_CHARS`
+const MAX_FILE_BYTES: u64 = MAX_FILE_CHARS as u64 * 4;
+
+/// Runs short programs written by the model in a sandbox, with `--execute`
+pub struct Code {
+    config: SandboxConfig,
+}
+
+/// `py` as `python`, `bash` as `shell`, ...
+fn language(name: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    let name = match name.to_lowercase().as_str() {
+        "python" | "python3" | "py" => "python",
+        "javascript" | "js" | "node" | "nodejs" => "javascript",
+        "shell" | "sh" | "bash" => "shell",
+        _ => return None,
+    };
+    LANGUAGES.iter().find(|(n, _, _)| *n == name).copied()
+}
+
+/// A path inside the working directory, e.g. `data/input.csv`
+fn relative_path(name: &str) -> ModuleResult<PathBuf> {
+    let path = Path::new(name);
+    if name.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(ModuleError::InvalidFunctionInput(format!(
+            "Invalid file name '{}', use a relative path like 'data/input.csv'",
+            name
+        )));
+    }
+    Ok(path.to_path_buf())
+}
+
+/// Regular files under `dir`, relative to `base`, at most `MAX_FILES + 1`
+/// of them. Symlinks, FIFOs and devices are left out.
+fn list_files(base: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    entries.sort();
+    for path in entries {
+        if files.len() > MAX_FILES {
+            return;
+        }
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            list_files(base, &path, files);
+        } else if metadata.file_type().is_file()
+            && let Ok(relative) = path.strip_prefix(base)
+        {
+            files.push(relative.to_path_buf());
+        }
+    }
+}
+
+/// Size and first `MAX_FILE_BYTES` of a file the program wrote, `None` when
+/// it is not a regular file anymore. Opened without following symlinks or
+/// waiting on FIFOs, the program may have swapped it since it was listed.
+fn read_output(path: &Path) -> Option<(u64, Vec<u8>)> {
+    let mut options = fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK);
+    }
+
+    let file = options.open(path).ok()?;
+    let size = file.metadata().ok().filter(|m| m.is_file())?.len();
+    let mut bytes = Vec::new();
+    file.take(MAX_FILE_BYTES).read_to_end(&mut bytes).ok()?;
+    Some((size, bytes))
+}
+
+/// Why a program was stopped, from the signal that ended it
+fn signal_note(signal: i32) -> Option<&'static str> {
+    match signal {
+        9 => Some("killed"),
+        11 => Some("crashed (segmentation fault)"),
+        24 => Some("stopped at the CPU time limit"),
+        25 => Some("stopped at the file size limit"),
+        _ => None,
+    }
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: name.to_string(),
+            module: Code::name().to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+    }
+}
+
+impl Code {
+    /// Uses `code.toml`, the default limits when it is invalid
+    pub fn new() -> Code {
+        let config = SandboxConfig::load().unwrap_or_else(|e| {
+            log::error!("{}, using the default limits", e);
+            SandboxConfig::default()
+        });
+        Code { config }
+    }
+
+    #[cfg(test)]
+    fn with_config(config: SandboxConfig) -> Code {
+        Code { config }
+    }
+
+    pub fn name() -> &'static str {
+        "code"
+    }
+
+    fn required<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<&'a str> {
+        func.arguments
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!("Missing '{}' argument", name))
+            })
+    }
+
+    fn run_code(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let name = self.required(func, "language")?;
+        let (language, interpreter, script) = language(name).ok_or_else(|| {
+            ModuleError::InvalidFunctionInput(format!(
+                "Unsupported language {}, use python, javascript or shell",
+                name
+            ))
+        })?;
+        let code = self.required(func, "code")?;
+
+        let timeout = match func.arguments.get("timeout") {
+            None | Some(Value::Null) => self.config.timeout_secs,
+            Some(timeout) => timeout.as_u64().filter(|t| *t > 0).ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(
+                    "'timeout' must be a positive number of seconds".to_string(),
+                )
+            })?,
+        }
+        .min(self.config.timeout_secs);
+
+        let dir = WorkDir::new()?;
+        let write = |path: &Path, content: &str| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)
+        };
+        let write_error = |e: std::io::Error| {
+            ModuleError::ExecutionError(format!("Cannot write the files: {}", e))
+        };
+
+        let mut inputs = Vec::new();
+        if let Some(files) = func.arguments.get("files").and_then(|v| v.as_object()) {
+            for (name, content) in files {
+                let path = relative_path(name)?;
+                let content = content.as_str().ok_or_else(|| {
+                    ModuleError::InvalidFunctionInput(format!(
+                        "The content of {} must be a string",
+                        name
+                    ))
+                })?;
+                write(&dir.path.join(&path), content).map_err(write_error)?;
+                inputs.push((path, content));
+            }
+        }
+        write(&dir.path.join(script), code).map_err(write_error)?;
+
+        let outcome = sandbox::run(
+            &self.config,
+            &dir.path,
+            interpreter,
+            &[script],
+            Duration::from_secs(timeout),
+        )?;
+
+        // What the program wrote, given files only when it changed them
+        let mut paths = Vec::new();
+        list_files(&dir.path, &dir.path, &mut paths);
+        let mut files = Vec::new();
+        for path in paths.iter().take(MAX_FILES) {
+            if path == Path::new(script) {
+                continue;
+            }
+            let Some((size, bytes)) = read_output(&dir.path.join(path)) else {
+                continue;
+            };
+            let complete = size == bytes.len() as u64;
+            let unchanged = inputs
+                .iter()
+                .any(|(input, content)| input == path && complete && content.as_bytes() == bytes);
+            if unchanged {
+                continue;
+            }
+
+            let content = String::from_utf8(bytes)
+                .ok()
+                .filter(|text| complete && text.chars().count() <= MAX_FILE_CHARS);
+            files.push(json!({
+                "path": path.to_string_lossy(),
+                "bytes": size,
+                "content": content,
+            }));
+        }
+
+        let mut result = json!({
+            "language": language,
+            "exit_code": outcome.exit_code,
+            "stdout": outcome.stdout,
+            "stderr": outcome.stderr,
+            "duration_ms": outcome.duration.as_millis() as u64,
+            "files": files,
+        });
+        let note = if outcome.timed_out {
+            Some(format!("Stopped after the {} second timeout", timeout))
+        } else {
+            outcome.signal.map(|signal| match signal_note(signal) {
+                Some(note) => format!("The program was {}", note),
+                None => format!("The program was ended by signal {}", signal),
+            })
+        };
+        if let Some(note) = note {
+            result["note"] = json!(note);
+        }
+        if paths.len() > MAX_FILES {
+            result["files_note"] = json!(format!("Only the first {} files are listed", MAX_FILES));
+        }
+
+        Ok(result)
+    }
+}
+
+impl Module for Code {
+    fn name(&self) -> &'static str {
+        Code::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Runs short Python, JavaScript or shell programs in a sandbox, with --execute."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **code**: Runs short programs in a sandbox.
+  - **Rules**:
+    - Use `run` for computations the math tools can't do (simulations, big loops, text processing, dates over many rows...), rather than working them out yourself.
+    - Print the results, only stdout, stderr and the files written in the working directory are returned.
+    - Programs run in an empty temporary directory, the only place they can write, with a time limit and no network access. Pass the data they need in `files`.
+    - Prefer Python and its standard library, other packages may not be installed."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        match func.name.as_str() {
+            "run" => self.run_code(func),
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn mutating_tools(&self) -> &'static [&'static str] {
+        &["run"]
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        vec![tool(
+            "run",
+            "Runs a short program and returns its output and the files it wrote",
+            json!({
+                "type": "object",
+                "properties": {
+                    "language": { "type": "string", "enum": ["python", "javascript", "shell"] },
+                    "code": { "type": "string", "description": "The program, printing its results" },
+                    "files": { "type": "object", "description": "Files to create first, name to content, e.g. {\"data.csv\": \"a,b\\n1,2\"}" },
+                    "timeout": { "type": "integer", "description": "Seconds before the program is stopped" }
+                },
+                "required": ["language", "code"]
+            }),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(code: &Code, arguments: Value) -> ModuleResult<Value> {
+        code.run(&ToolCallFunction {
+            name: "run".to_string(),
+            module: Code::name().to_string(),
+            arguments,
+        })
+    }
+
+    /// The sandbox needs user and mount namespaces, which some machines
+    /// don't allow, the tests are skipped there
+    fn sandboxed(config: SandboxConfig) -> Option<Code> {
+        let code = Code::with_config(config);
+        match call(&code, json!({ "language": "shell", "code": "true" })) {
+            Ok(_) => Some(code),
+            Err(e) => {
+                eprintln!("Skipping, no sandbox on this machine: {}", e);
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let Some(code) = sandboxed(SandboxConfig::default()) else {
+            return;
+        };
+
+        let result = call(
+            &code,
+            json!({
+                "language": "python",
+                "code": "import os\nrows = open('data/in.csv').read().split()\nprint(sum(int(r) for r in rows))\nopen('out.txt', 'w').write('done')\nprint(os.getcwd() != '/')",
+                "files": { "data/in.csv": "1\n2\n3\n" }
+            }),
+        )
+        .unwrap();
+        assert_eq!(result["exit_code"], json!(0));
+        assert_eq!(result["stdout"], json!("6\nTrue\n"));
+        assert_eq!(
+            result["files"],
+            json!([{ "path": "out.txt", "bytes": 4, "content": "done" }])
+        );
+
+        let failed = call(
+            &code,
+            json!({ "language": "sh", "code": "echo oops >&2; exit 3" }),
+        )
+        .unwrap();
+        assert_eq!(failed["exit_code"], json!(3));
+        assert_eq!(failed["stderr"], json!("oops\n"));
+
+        assert!(call(&code, json!({ "language": "cobol", "code": "" })).is_err());
+        assert!(
+            call(
+                &code,
+                json!({ "language": "shell", "code": "true", "files": { "../x": "" } })
+            )
+            .is_err()
+        );
+        assert_eq!(code.mutating_tools(), &["run"]);
+    }
+
+    #[test]
+    fn test_isolation() {
+        let Some(code) = sandboxed(SandboxConfig::default()) else {
+            return;
+        };
+        let outside = std::env::temp_dir().join(format!("jarvis-escaped-{}", std::process::id()));
+
+        let result = call(
+            &code,
+            json!({
+                "language": "shell",
+                "code": format!("touch {} || echo refused; mount -o remount,rw / || echo refused", outside.display())
+            }),
+        )
+        .unwrap();
+        assert_eq!(result["stdout"], json!("refused\nrefused\n"));
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn test_special_output_files() {
+        let Some(code) = sandboxed(SandboxConfig::default()) else {
+            return;
+        };
+
+        // Neither blocks on the FIFO nor reads what the symlink points to
+        let result = call(
+            &code,
+            json!({
+                "language": "shell",
+                "code": "mkfifo fifo; ln -s /dev/zero zero; ln -s /etc/hostname host; head -c 20000 /dev/zero > big; echo ok > small"
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            result["files"],
+            json!([
+                { "path": "big", "bytes": 20000, "content": null },
+                { "path": "small", "bytes": 3, "content": "ok\n" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let Some(code) = sandboxed(SandboxConfig {
+            timeout_secs: 1,
+            ..SandboxConfig::default()
+        }) else {
+            return;
+        };
+
+        let slow = call(
+            &code,
+            json!({ "language": "shell", "code": "echo start; sleep 5 & sleep 5" }),
+        )
+        .unwrap();
+        assert_eq!(slow["stdout"], json!("start\n"));
+        assert!(slow["note"].as_str().unwrap().contains("timeout"));
+        assert!(slow["duration_ms"].as_u64().unwrap() < 3_000);
+
+        let loud = call(
+            &code,
+            json!({ "language": "shell", "code": "yes | head -c 50000" }),
+        )
+        .unwrap();
+        assert!(
+            loud["stdout"]
+                .as_str()
+                .unwrap()
+                .ends_with("more bytes omitted]")
+        );
+
+        let offline = call(
+            &code,
+            json!({
+                "language": "python",
+                "code": "import socket\ntry:\n    socket.create_connection(('1.1.1.1', 80), timeout=0.5)\n    print('online')\nexcept OSError:\n    print('offline')"
+            }),
+        )
+        .unwrap();
+        assert_eq!(offline["stdout"], json!("offline\n"));
+    }
+}
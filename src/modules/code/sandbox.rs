@@ -0,0 +1,391 @@
+use crate::{
+    AppError, AppResult,
+    modules::{ModuleError, ModuleResult},
+    utils::config_dir,
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Bytes of stdout and stderr kept, the rest is counted and dropped
+pub const MAX_OUTPUT_BYTES: usize = 10_000;
+
+/// Limits of the `code` sandbox, read from `code.toml` in the config
+/// directory
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SandboxConfig {
+    /// Programs can reach the network, they run in an empty network
+    /// namespace otherwise
+    pub network: bool,
+    /// Longest a program can run, the model can ask for less
+    pub timeout_secs: u64,
+    pub cpu_secs: u64,
+    /// Memory a program can allocate
+    pub memory_mb: u64,
+    /// Largest file a program can write
+    pub file_mb: u64,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            network: false,
+            timeout_secs: 10,
+            cpu_secs: 10,
+            memory_mb: 512,
+            file_mb: 16,
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// The user configuration, the defaults when there is none
+    pub fn load() -> AppResult<SandboxConfig> {
+        let path = config_dir().join("code.toml");
+        if !path.is_file() {
+            return Ok(SandboxConfig::default());
+        }
+
+        toml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| AppError::from(&format!("Invalid {}: {}", path.display(), e)))
+    }
+}
+
+/// How a program ended and what it printed
+#[derive(Debug)]
+pub struct Outcome {
+    pub exit_code: Option<i32>,
+    /// Signal that ended the program, e.g. SIGXCPU past its CPU time
+    pub signal: Option<i32>,
+    /// Killed for running longer than the timeout
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+/// Bytes kept from a pipe and how many were read in all
+type Output = Arc<Mutex<(Vec<u8>, usize)>>;
+
+/// Reads a pipe to the end, keeping the first `MAX_OUTPUT_BYTES`
+fn drain(mut pipe: impl Read + Send + 'static) -> (Output, thread::JoinHandle<()>) {
+    let output = Arc::new(Mutex::new((Vec::new(), 0)));
+    let shared = output.clone();
+
+    let handle = thread::spawn(move || {
+        let mut buffer = [0; 8192];
+        while let Ok(read) = pipe.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let mut output = shared.lock().unwrap();
+            let kept = read.min(MAX_OUTPUT_BYTES.saturating_sub(output.0.len()));
+            output.0.extend_from_slice(&buffer[..kept]);
+            output.1 += read;
+        }
+    });
+
+    (output, handle)
+}
+
+fn text(output: &Mutex<(Vec<u8>, usize)>) -> String {
+    let (bytes, total) = &*output.lock().unwrap();
+    let mut text = String::from_utf8_lossy(bytes).into_owned();
+    if *total > bytes.len() {
+        text.push_str(&format!(
+            "\n[... {} more bytes omitted]",
+            total - bytes.len()
+        ));
+    }
+    text
+}
+
+/// Runs `program` in `dir` with the limits of `config`: its own session so
+/// everything it starts is killed with it, a scrubbed environment, CPU,
+/// memory and file size limits, a filesystem that is read-only except for
+/// `dir`, and no network unless allowed. Fails when the program can't be
+/// isolated.
+#[cfg(unix)]
+pub fn run(
+    config: &SandboxConfig,
+    dir: &Path,
+    program: &str,
+    args: &[&str],
+    timeout: Duration,
+) -> ModuleResult<Outcome> {
+    use std::{
+        ffi::CString,
+        os::unix::{
+            ffi::OsStrExt,
+            process::{CommandExt, ExitStatusExt},
+        },
+    };
+
+    let limits = [
+        (libc::RLIMIT_CPU, config.cpu_secs),
+        (libc::RLIMIT_DATA, config.memory_mb * 1024 * 1024),
+        (libc::RLIMIT_FSIZE, config.file_mb * 1024 * 1024),
+        (libc::RLIMIT_CORE, 0),
+    ];
+    let network = config.network;
+    // Formatted before forking, the child can only make raw system calls.
+    // Root is nobody inside, a root program could undo the read-only mounts.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let inside = |id: u32| if id == 0 { 65534 } else { id };
+    let uid_map = format!("{} {} 1\0", inside(uid), uid);
+    let gid_map = format!("{} {} 1\0", inside(gid), gid);
+    let dir_path = CString::new(dir.as_os_str().as_bytes()).map_err(|_| {
+        ModuleError::ExecutionError(format!("Invalid working directory {}", dir.display()))
+    })?;
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", dir)
+        .env("TMPDIR", dir)
+        .env("LANG", "C.UTF-8")
+        .env("PYTHONDONTWRITEBYTECODE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setsid() < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            for (resource, limit) in limits {
+                let limit = libc::rlimit {
+                    rlim_cur: limit as libc::rlim_t,
+                    rlim_max: limit as libc::rlim_t,
+                };
+                if libc::setrlimit(resource, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            isolate(network, &uid_map, &gid_map, &dir_path)
+        });
+    }
+
+    let started = Instant::now();
+    let mut child = command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            ModuleError::ExecutionError(format!("{} is not installed", program))
+        }
+        _ => ModuleError::ExecutionError(format!(
+            "Cannot start {} in the sandbox ({}), it needs Linux user and mount namespaces",
+            program, e
+        )),
+    })?;
+
+    let (stdout, stdout_reader) = drain(child.stdout.take().expect("stdout is piped"));
+    let (stderr, stderr_reader) = drain(child.stderr.take().expect("stderr is piped"));
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| ModuleError::ExecutionError(e.to_string()))?
+        {
+            break status;
+        }
+        if started.elapsed() > timeout {
+            timed_out = true;
+            // The whole session, in case the program started others
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            break child
+                .wait()
+                .map_err(|e| ModuleError::ExecutionError(e.to_string()))?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let duration = started.elapsed();
+
+    // Processes that left the session may keep the pipes open, their
+    // output is not waited for long
+    let deadline = Instant::now() + Duration::from_millis(500);
+    while !(stdout_reader.is_finished() && stderr_reader.is_finished()) && Instant::now() < deadline
+    {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    Ok(Outcome {
+        exit_code: status.code(),
+        signal: status.signal(),
+        timed_out,
+        stdout: text(&stdout),
+        stderr: text(&stderr),
+        duration,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn run(_: &SandboxConfig, _: &Path, _: &str, _: &[&str], _: Duration) -> ModuleResult<Outcome> {
+    Err(ModuleError::ExecutionError(
+        "Running code is only supported on Unix".to_string(),
+    ))
+}
+
+/// Moves the calling process to its own user and mount namespaces, and an
+/// empty network namespace unless `network`, then makes every mount
+/// read-only except for `dir`. Only makes system calls, it runs between fork
+/// and exec.
+#[cfg(unix)]
+fn isolate(
+    network: bool,
+    uid_map: &str,
+    gid_map: &str,
+    dir: &std::ffi::CStr,
+) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        let check = |result: libc::c_long| {
+            if result < 0 {
+                Err(std::io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        };
+
+        let mut flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS;
+        if !network {
+            flags |= libc::CLONE_NEWNET;
+        }
+        check(libc::unshare(flags) as libc::c_long)?;
+
+        let write = |path: &[u8], content: &str| -> std::io::Result<()> {
+            let fd = libc::open(path.as_ptr() as *const libc::c_char, libc::O_WRONLY);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let length = content.len() - 1;
+            let written = libc::write(fd, content.as_ptr() as *const libc::c_void, length);
+            libc::close(fd);
+            if written != length as isize {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        };
+        // Same user and group inside, so the working directory stays theirs
+        write(b"/proc/self/setgroups\0", "deny\0")?;
+        write(b"/proc/self/uid_map\0", uid_map)?;
+        write(b"/proc/self/gid_map\0", gid_map)?;
+
+        let root = c"/".as_ptr();
+        let set_attr = |path: *const libc::c_char, flags: libc::c_int, attr: libc::mount_attr| {
+            check(libc::syscall(
+                libc::SYS_mount_setattr,
+                libc::AT_FDCWD,
+                path,
+                flags,
+                &attr as *const libc::mount_attr,
+                std::mem::size_of::<libc::mount_attr>(),
+            ))
+        };
+        let read_only = |set, clear| libc::mount_attr {
+            attr_set: set,
+            attr_clr: clear,
+            propagation: 0,
+            userns_fd: 0,
+        };
+
+        // Nothing mounted here shows outside
+        check(libc::mount(
+            std::ptr::null(),
+            root,
+            std::ptr::null(),
+            libc::MS_REC | libc::MS_PRIVATE,
+            std::ptr::null(),
+        ) as libc::c_long)?;
+        // The working directory as a mount of its own, made writable again
+        // once everything is read-only
+        check(libc::mount(
+            dir.as_ptr(),
+            dir.as_ptr(),
+            std::ptr::null(),
+            libc::MS_BIND | libc::MS_REC,
+            std::ptr::null(),
+        ) as libc::c_long)?;
+        set_attr(
+            root,
+            libc::AT_RECURSIVE,
+            read_only(libc::MOUNT_ATTR_RDONLY, 0),
+        )?;
+        set_attr(dir.as_ptr(), 0, read_only(0, libc::MOUNT_ATTR_RDONLY))?;
+
+        // The current directory is still the one under the read-only mount
+        check(libc::chdir(dir.as_ptr()) as libc::c_long)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (network, uid_map, gid_map, dir);
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "the sandbox needs Linux",
+        ))
+    }
+}
+
+/// A new empty directory for one run, removed when dropped
+pub struct WorkDir {
+    pub path: PathBuf,
+}
+
+impl WorkDir {
+    pub fn new() -> ModuleResult<WorkDir> {
+        let base = std::env::temp_dir();
+        for attempt in 0..100 {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.subsec_nanos())
+                .unwrap_or(0);
+            let path = base.join(format!(
+                "jarvis-code-{}-{}-{}",
+                std::process::id(),
+                nanos,
+                attempt
+            ));
+
+            // Fails when it exists, so no other run shares it
+            match fs::create_dir(&path) {
+                Ok(()) => {
+                    #[cfg(unix)]
+                    {
+                        use std::os::unix::fs::PermissionsExt;
+                        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o700));
+                    }
+                    return Ok(WorkDir { path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(ModuleError::ExecutionError(format!(
+                        "Cannot create a working directory: {}",
+                        e
+                    )));
+                }
+            }
+        }
+
+        Err(ModuleError::ExecutionError(
+            "Cannot create a working directory".to_string(),
+        ))
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
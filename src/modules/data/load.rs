@@ -0,0 +1,329 @@
+use crate::modules::{ModuleError, ModuleResult};
+use rusqlite::{Connection, types::Value as SqlValue};
+use serde_json::Value;
+use std::path::Path;
+
+/// Files larger than this are not loaded into memory
+pub const MAX_FILE_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Header of every SQLite database file
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// How a file is read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Sqlite,
+    Csv(u8),
+    Json,
+}
+
+impl Format {
+    /// From the first bytes of the file, then its extension
+    pub fn detect(path: &Path, head: &[u8]) -> Format {
+        if head.starts_with(SQLITE_HEADER) {
+            return Format::Sqlite;
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match extension.as_str() {
+            "tsv" | "tab" => Format::Csv(b'\t'),
+            "json" | "jsonl" | "ndjson" => Format::Json,
+            "csv" => Format::Csv(b','),
+            _ if head.trim_ascii_start().starts_with(b"[")
+                || head.trim_ascii_start().starts_with(b"{") =>
+            {
+                Format::Json
+            }
+            _ => Format::Csv(b','),
+        }
+    }
+}
+
+/// Rows read from a file, before they become a table
+#[derive(Debug, Default, PartialEq)]
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl Rows {
+    /// Position of a column, added when it is new
+    fn column(&mut self, name: &str) -> usize {
+        match self.columns.iter().position(|c| c == name) {
+            Some(index) => index,
+            None => {
+                self.columns.push(name.to_string());
+                self.columns.len() - 1
+            }
+        }
+    }
+
+    /// INTEGER, REAL or TEXT, whichever fits every value of the column
+    fn column_type(&self, index: usize) -> &'static str {
+        let values = || self.rows.iter().filter_map(|row| row.get(index));
+        if values().all(|v| matches!(v, SqlValue::Null | SqlValue::Integer(_))) {
+            "INTEGER"
+        } else if values()
+            .all(|v| matches!(v, SqlValue::Null | SqlValue::Integer(_) | SqlValue::Real(_)))
+        {
+            "REAL"
+        } else {
+            "TEXT"
+        }
+    }
+}
+
+/// `"name"` with its quotes doubled, for identifiers in SQL
+pub fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A table name from a file name, e.g. `Sales 2026.csv` as `sales_2026`
+pub fn table_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("data")
+        .to_lowercase();
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let name = name.trim_matches('_');
+
+    match name.chars().next() {
+        None => "data".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("t_{}", name),
+        Some(_) => name.to_string(),
+    }
+}
+
+/// A CSV field as a number when it reads as one. Zip codes and other
+/// numbers with leading zeros stay text.
+fn cell(field: &str) -> SqlValue {
+    let field = field.trim();
+    if field.is_empty() {
+        return SqlValue::Null;
+    }
+
+    let digits = field.trim_start_matches(['-', '+']);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    if !leading_zero {
+        if let Ok(integer) = field.parse::<i64>() {
+            return SqlValue::Integer(integer);
+        }
+        if let Ok(real) = field.parse::<f64>()
+            && real.is_finite()
+        {
+            return SqlValue::Real(real);
+        }
+    }
+
+    SqlValue::Text(field.to_string())
+}
+
+/// Reads CSV with a header line. Blank and repeated column names are made
+/// unique, short rows are padded with NULL.
+pub fn read_csv(content: &[u8], delimiter: u8) -> ModuleResult<Rows> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content);
+
+    let invalid = |e: csv::Error| ModuleError::ExecutionError(format!("Invalid CSV: {}", e));
+    let mut rows = Rows::default();
+    for (i, header) in reader.headers().map_err(invalid)?.iter().enumerate() {
+        let header = header.trim();
+        let mut name = if header.is_empty() {
+            format!("column_{}", i + 1)
+        } else {
+            header.to_string()
+        };
+        let mut n = 2;
+        while rows.columns.contains(&name) {
+            name = format!("{}_{}", header, n);
+            n += 1;
+        }
+        rows.columns.push(name);
+    }
+
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        let mut row: Vec<SqlValue> = record.iter().map(cell).collect();
+        row.resize(rows.columns.len(), SqlValue::Null);
+        rows.rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+fn json_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(integer) => SqlValue::Integer(integer),
+            None => n.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        // Nested values stay JSON, SQLite's json functions can read them
+        nested => SqlValue::Text(nested.to_string()),
+    }
+}
+
+/// Reads an array of objects, an object holding one (e.g. `{"data": [...]}`)
+/// or JSON lines. Keys become columns, missing ones are NULL.
+pub fn read_json(content: &[u8]) -> ModuleResult<Rows> {
+    let items = match serde_json::from_slice::<Value>(content) {
+        Ok(Value::Array(items)) => items,
+        Ok(Value::Object(object)) => match object
+            .values()
+            .find(|v| v.as_array().is_some_and(|a| a.iter().any(Value::is_object)))
+        {
+            Some(Value::Array(items)) => items.clone(),
+            _ => vec![Value::Object(object)],
+        },
+        Ok(_) => {
+            return Err(ModuleError::ExecutionError(
+                "The JSON is neither an array nor an object".to_string(),
+            ));
+        }
+        // One value per line
+        Err(e) => String::from_utf8_lossy(content)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<Value>, _>>()
+            .map_err(|_| ModuleError::ExecutionError(format!("Invalid JSON: {}", e)))?,
+    };
+
+    let mut rows = Rows::default();
+    for item in items {
+        let mut row = vec![SqlValue::Null; rows.columns.len()];
+        match item {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    let index = rows.column(&key);
+                    row.resize(rows.columns.len(), SqlValue::Null);
+                    row[index] = json_value(&value);
+                }
+            }
+            value => {
+                let index = rows.column("value");
+                row.resize(rows.columns.len(), SqlValue::Null);
+                row[index] = json_value(&value);
+            }
+        }
+        rows.rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Creates `table` with the rows, replacing any table of the same name
+pub fn create_table(connection: &mut Connection, table: &str, rows: &Rows) -> ModuleResult<()> {
+    if rows.columns.is_empty() {
+        return Err(ModuleError::ExecutionError(
+            "The file has no columns".to_string(),
+        ));
+    }
+
+    let sql_error = |e: rusqlite::Error| ModuleError::ExecutionError(e.to_string());
+    let columns: Vec<String> = rows
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("{} {}", quote(name), rows.column_type(i)))
+        .collect();
+    let placeholders = vec!["?"; rows.columns.len()].join(", ");
+
+    let transaction = connection.transaction().map_err(sql_error)?;
+    transaction
+        .execute_batch(&format!(
+            "DROP TABLE IF EXISTS main.{table}; CREATE TABLE main.{table} ({});",
+            columns.join(", "),
+            table = quote(table)
+        ))
+        .map_err(sql_error)?;
+    {
+        let mut insert = transaction
+            .prepare(&format!(
+                "INSERT INTO main.{} VALUES ({})",
+                quote(table),
+                placeholders
+            ))
+            .map_err(sql_error)?;
+        for row in &rows.rows {
+            // Rows of JSON lines seen before a key appeared are shorter
+            let padded = row
+                .iter()
+                .cloned()
+                .chain(std::iter::repeat(SqlValue::Null))
+                .take(rows.columns.len());
+            insert
+                .execute(rusqlite::params_from_iter(padded))
+                .map_err(sql_error)?;
+        }
+    }
+    transaction.commit().map_err(sql_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_csv() {
+        let rows = read_csv(
+            b"name,zip,amount,,amount\nAda,01234,12.5,x,3\nBob,75001,-2\n",
+            b',',
+        )
+        .unwrap();
+
+        assert_eq!(
+            rows.columns,
+            vec!["name", "zip", "amount", "column_4", "amount_2"]
+        );
+        assert_eq!(
+            rows.rows[0],
+            vec![
+                SqlValue::Text("Ada".to_string()),
+                SqlValue::Text("01234".to_string()),
+                SqlValue::Real(12.5),
+                SqlValue::Text("x".to_string()),
+                SqlValue::Integer(3),
+            ]
+        );
+        assert_eq!(rows.rows[1][4], SqlValue::Null);
+        assert_eq!(rows.column_type(1), "TEXT");
+        assert_eq!(rows.column_type(2), "REAL");
+        assert_eq!(rows.column_type(4), "INTEGER");
+    }
+
+    #[test]
+    fn test_read_json() {
+        let rows =
+            read_json(br#"{"data": [{"a": 1, "b": "x"}, {"b": "y", "c": [1, 2], "d": true}]}"#)
+                .unwrap();
+        assert_eq!(rows.columns, vec!["a", "b", "c", "d"]);
+        assert_eq!(rows.rows[1][2], SqlValue::Text("[1,2]".to_string()));
+        assert_eq!(rows.rows[1][3], SqlValue::Integer(1));
+
+        let lines = read_json(b"{\"a\": 1}\n\n{\"a\": 2.5}\n").unwrap();
+        assert_eq!(lines.rows.len(), 2);
+        assert_eq!(lines.column_type(0), "REAL");
+
+        assert!(read_json(b"{\"a\": ").is_err());
+    }
+
+    #[test]
+    fn test_table_name() {
+        assert_eq!(table_name(Path::new("data/Sales 2026.csv")), "sales_2026");
+        assert_eq!(table_name(Path::new("2026.json")), "t_2026");
+        assert_eq!(table_name(Path::new("-.csv")), "data");
+    }
+}
@@ -0,0 +1,688 @@
+mod load;
+
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use load::{Format, MAX_FILE_BYTES, create_table, quote, read_csv, read_json, table_name};
+use rusqlite::{
+    Connection, OpenFlags,
+    hooks::{AuthAction, AuthContext, Authorization},
+    types::ValueRef,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_ROW_LIMIT: u64 = 50;
+const MAX_ROW_LIMIT: u64 = 500;
+
+/// Longer values are cut in results
+const MAX_CELL_CHARS: usize = 200;
+
+/// Results are cut after this many characters, whatever the row limit
+const MAX_TABLE_CHARS: usize = 12_000;
+
+/// Queries running longer are interrupted
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Rows shown by `describe`
+const SAMPLE_ROWS: u64 = 3;
+
+/// A file opened as a table, or as a database for SQLite files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Source {
+    path: PathBuf,
+    name: String,
+}
+
+struct Database {
+    /// In memory, CSV and JSON files are tables of it and SQLite files are
+    /// attached read-only
+    connection: Connection,
+    sources: Vec<Source>,
+}
+
+/// Read-only SQL over local data: SQLite databases, CSV and JSON files
+pub struct Data {
+    database: Mutex<Database>,
+}
+
+fn sql_error(error: rusqlite::Error) -> ModuleError {
+    ModuleError::ExecutionError(error.to_string())
+}
+
+fn connect() -> Connection {
+    // URIs let SQLite files be attached read-only (`file:...?mode=ro`)
+    Connection::open_in_memory_with_flags(
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_CREATE
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .expect("SQLite can't open an in-memory database")
+}
+
+/// A read-only `file:` URI for ATTACH
+fn read_only_uri(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    format!("file:{}?mode=ro", path)
+}
+
+/// A value as a table cell, on one line and cut at `MAX_CELL_CHARS`
+fn cell(value: ValueRef) -> String {
+    let text = match value {
+        ValueRef::Null => return "NULL".to_string(),
+        ValueRef::Integer(integer) => return integer.to_string(),
+        ValueRef::Real(real) => return real.to_string(),
+        ValueRef::Blob(blob) => return format!("<{} bytes>", blob.len()),
+        ValueRef::Text(text) => String::from_utf8_lossy(text),
+    };
+
+    let text = text.replace(['\n', '\r'], " ").replace('|', "\\|");
+    match text.char_indices().nth(MAX_CELL_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+/// Results of a query as a markdown table, with the rows shown and whether
+/// some were left out
+fn select(connection: &Connection, sql: &str, limit: u64) -> ModuleResult<(String, u64, bool)> {
+    let mut statement = connection.prepare(sql).map_err(sql_error)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(|name| name.replace('|', "\\|"))
+        .collect();
+
+    let mut table = format!(
+        "| {} |\n|{}\n",
+        columns.join(" | "),
+        " --- |".repeat(columns.len())
+    );
+    let mut shown = 0;
+    let mut truncated = false;
+
+    let mut rows = statement.query([]).map_err(sql_error)?;
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        if shown == limit {
+            truncated = true;
+            break;
+        }
+
+        let cells = (0..columns.len())
+            .map(|i| row.get_ref(i).map(cell))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+        let line = format!("| {} |\n", cells.join(" | "));
+        if table.len() + line.len() > MAX_TABLE_CHARS {
+            truncated = true;
+            break;
+        }
+
+        table.push_str(&line);
+        shown += 1;
+    }
+
+    Ok((table.trim_end().to_string(), shown, truncated))
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: name.to_string(),
+            module: Data::name().to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+    }
+}
+
+impl Database {
+    /// Schemas holding data: `main` for the loaded files, then the attached
+    /// databases
+    fn schemas(&self) -> ModuleResult<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("PRAGMA database_list")
+            .map_err(sql_error)?;
+        let schemas = statement
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(sql_error)?
+            .filter_map(|name| name.ok())
+            .filter(|name| name != "temp")
+            .collect();
+        Ok(schemas)
+    }
+
+    /// `schema` and `table` of a name like `shop.orders` or `sales`
+    fn split<'a>(&self, name: &'a str) -> ModuleResult<(String, &'a str)> {
+        if let Some((schema, table)) = name.split_once('.')
+            && self.schemas()?.iter().any(|s| s == schema)
+        {
+            return Ok((schema.to_string(), table));
+        }
+        Ok(("main".to_string(), name))
+    }
+
+    /// Columns of a table as `name TYPE`, empty when there is no such table
+    fn columns(&self, schema: &str, table: &str) -> ModuleResult<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "PRAGMA {}.table_info({})",
+                quote(schema),
+                quote(table)
+            ))
+            .map_err(sql_error)?;
+        let columns = statement
+            .query_map([], |row| {
+                let name: String = row.get(1)?;
+                let kind: String = row.get(2)?;
+                Ok(format!("{} {}", name, kind).trim().to_string())
+            })
+            .map_err(sql_error)?
+            .collect::<Result<_, _>>()
+            .map_err(sql_error)?;
+        Ok(columns)
+    }
+
+    fn row_count(&self, schema: &str, table: &str) -> ModuleResult<i64> {
+        self.connection
+            .query_row(
+                &format!("SELECT count(*) FROM {}.{}", quote(schema), quote(table)),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(sql_error)
+    }
+
+    /// Tables and views of a schema, named as queries refer to them
+    fn tables(&self, schema: &str) -> ModuleResult<Vec<(String, String)>> {
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "SELECT name FROM {}.sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+                quote(schema)
+            ))
+            .map_err(sql_error)?;
+        let names: Vec<String> = statement
+            .query_map([], |row| row.get(0))
+            .map_err(sql_error)?
+            .collect::<Result<_, _>>()
+            .map_err(sql_error)?;
+
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let qualified = match schema {
+                    "main" => name.clone(),
+                    schema => format!("{}.{}", schema, name),
+                };
+                (qualified, name)
+            })
+            .collect())
+    }
+
+    fn summary(&self, schema: &str) -> ModuleResult<Vec<Value>> {
+        self.tables(schema)?
+            .into_iter()
+            .map(|(qualified, table)| {
+                Ok(json!({
+                    "table": qualified,
+                    "rows": self.row_count(schema, &table)?,
+                    "columns": self.columns(schema, &table)?.join(", "),
+                }))
+            })
+            .collect()
+    }
+
+    fn open(&mut self, path: &Path, name: Option<&str>) -> ModuleResult<Value> {
+        let metadata = fs::metadata(path).map_err(|e| {
+            ModuleError::ExecutionError(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        if !metadata.is_file() {
+            return Err(ModuleError::InvalidFunctionInput(format!(
+                "{} is not a file",
+                path.display()
+            )));
+        }
+        if metadata.len() > MAX_FILE_BYTES {
+            return Err(ModuleError::ExecutionError(format!(
+                "{} is larger than {} MB",
+                path.display(),
+                MAX_FILE_BYTES / 1024 / 1024
+            )));
+        }
+
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => table_name(path),
+        };
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(ModuleError::InvalidFunctionInput(format!(
+                "Invalid name '{}', use letters, digits and underscores",
+                name
+            )));
+        }
+
+        let read_error = |e: std::io::Error| {
+            ModuleError::ExecutionError(format!("Cannot read {}: {}", path.display(), e))
+        };
+        let mut head = Vec::new();
+        fs::File::open(path)
+            .map_err(read_error)?
+            .take(16)
+            .read_to_end(&mut head)
+            .map_err(read_error)?;
+        let path = path.canonicalize().map_err(read_error)?;
+
+        let result = match Format::detect(&path, &head) {
+            Format::Sqlite => {
+                if matches!(name.as_str(), "main" | "temp") {
+                    return Err(ModuleError::InvalidFunctionInput(format!(
+                        "'{}' is reserved, choose another name",
+                        name
+                    )));
+                }
+                if self.schemas()?.contains(&name) {
+                    self.connection
+                        .execute(&format!("DETACH DATABASE {}", quote(&name)), [])
+                        .map_err(sql_error)?;
+                }
+                self.connection
+                    .execute(
+                        &format!("ATTACH DATABASE ?1 AS {}", quote(&name)),
+                        [read_only_uri(&path)],
+                    )
+                    .map_err(sql_error)?;
+
+                json!({ "database": name, "tables": self.summary(&name)? })
+            }
+            format => {
+                let content = fs::read(&path).map_err(read_error)?;
+                let rows = match format {
+                    Format::Csv(delimiter) => read_csv(&content, delimiter)?,
+                    _ => read_json(&content)?,
+                };
+                create_table(&mut self.connection, &name, &rows)?;
+
+                json!({
+                    "table": name,
+                    "rows": rows.rows.len(),
+                    "columns": self.columns("main", &name)?.join(", "),
+                })
+            }
+        };
+
+        self.sources.retain(|source| source.name != name);
+        self.sources.push(Source { path, name });
+        Ok(result)
+    }
+
+    fn query(&self, sql: &str, limit: u64) -> ModuleResult<Value> {
+        // Attaching could create files, everything else is refused unless
+        // it is read-only
+        self.connection
+            .authorizer(Some(|context: AuthContext<'_>| match context.action {
+                AuthAction::Attach { .. } | AuthAction::Detach { .. } => Authorization::Deny,
+                _ => Authorization::Allow,
+            }));
+        let prepared = self.connection.prepare(sql).map(|s| s.readonly());
+        self.connection
+            .authorizer(None::<fn(AuthContext<'_>) -> Authorization>);
+
+        if !prepared.map_err(sql_error)? {
+            return Err(ModuleError::InvalidFunctionInput(
+                "Only read-only queries are allowed (SELECT, WITH, EXPLAIN, ...)".to_string(),
+            ));
+        }
+
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        self.connection
+            .progress_handler(10_000, Some(move || Instant::now() > deadline));
+        let selected = select(&self.connection, sql, limit);
+        self.connection.progress_handler(0, None::<fn() -> bool>);
+
+        let (table, rows, truncated) = selected.map_err(|e| match Instant::now() > deadline {
+            true => ModuleError::ExecutionError(format!(
+                "The query was stopped after {} seconds",
+                QUERY_TIMEOUT.as_secs()
+            )),
+            false => e,
+        })?;
+
+        Ok(json!({ "table": table, "rows": rows, "truncated": truncated }))
+    }
+}
+
+impl Data {
+    pub fn new() -> Data {
+        Data {
+            database: Mutex::new(Database {
+                connection: connect(),
+                sources: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn name() -> &'static str {
+        "data"
+    }
+
+    fn string<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<Option<&'a str>> {
+        match func.arguments.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_str()
+                .map(|v| Some(v.trim()).filter(|v| !v.is_empty()))
+                .ok_or_else(|| {
+                    ModuleError::InvalidFunctionInput(format!("Expected a string for '{}'", name))
+                }),
+        }
+    }
+
+    fn required<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<&'a str> {
+        self.string(func, name)?.ok_or_else(|| {
+            ModuleError::InvalidFunctionInput(format!("Missing '{}' argument", name))
+        })
+    }
+
+    fn open(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let path = Path::new(self.required(func, "path")?);
+        let name = self.string(func, "name")?;
+        self.database.lock().unwrap().open(path, name)
+    }
+
+    fn describe(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let database = self.database.lock().unwrap();
+
+        let Some(name) = self.string(func, "table")? else {
+            let mut tables = Vec::new();
+            for schema in database.schemas()? {
+                tables.extend(database.summary(&schema)?);
+            }
+            if tables.is_empty() {
+                return Ok(json!({ "tables": [], "note": "No data is loaded, use open first" }));
+            }
+            return Ok(json!({ "tables": tables }));
+        };
+
+        let (schema, table) = database.split(name)?;
+        let columns = database.columns(&schema, table)?;
+        if columns.is_empty() {
+            return Err(ModuleError::InvalidFunctionInput(format!(
+                "Unknown table {}, call describe without a table to list them",
+                name
+            )));
+        }
+
+        let (sample, _, _) = select(
+            &database.connection,
+            &format!("SELECT * FROM {}.{}", quote(&schema), quote(table)),
+            SAMPLE_ROWS,
+        )?;
+        Ok(json!({
+            "table": name,
+            "rows": database.row_count(&schema, table)?,
+            "columns": columns,
+            "sample": sample,
+        }))
+    }
+
+    fn query(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let sql = self.required(func, "sql")?;
+        let limit = match func.arguments.get("limit") {
+            None | Some(Value::Null) => DEFAULT_ROW_LIMIT,
+            Some(limit) => limit.as_u64().filter(|l| *l > 0).ok_or_else(|| {
+                ModuleError::InvalidFunctionInput("'limit' must be a positive integer".to_string())
+            })?,
+        };
+
+        self.database
+            .lock()
+            .unwrap()
+            .query(sql, limit.min(MAX_ROW_LIMIT))
+    }
+}
+
+impl Module for Data {
+    fn name(&self) -> &'static str {
+        Data::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Runs read-only SQL on SQLite databases, CSV and JSON files."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **data**: Read-only SQL on local data files.
+  - **Rules**:
+    - Use `open` to load a CSV, TSV, JSON or SQLite file, then `describe` to see its tables, columns and a few rows.
+    - Answer questions about the data with `query` (SQLite SQL), never by guessing or reading the file yourself.
+    - Let SQL do the work (`COUNT`, `SUM`, `GROUP BY`, `ORDER BY ... LIMIT`) rather than fetching every row.
+    - Tables of SQLite files are named `database.table`, e.g. `shop.orders`.
+    - Results are capped, say so when `truncated` is true."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        match func.name.as_str() {
+            "open" => self.open(func),
+            "describe" => self.describe(func),
+            "query" => self.query(func),
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn state(&self) -> Option<Value> {
+        let database = self.database.lock().unwrap();
+        (!database.sources.is_empty()).then(|| json!({ "sources": database.sources }))
+    }
+
+    fn restore_state(&self, state: Option<&Value>) -> ModuleResult<()> {
+        let sources: Vec<Source> = match state.and_then(|s| s.get("sources")) {
+            Some(sources) => serde_json::from_value(sources.clone()).map_err(|e| {
+                ModuleError::InvalidFunctionInput(format!("Invalid data state: {}", e))
+            })?,
+            None => Vec::new(),
+        };
+
+        let mut database = self.database.lock().unwrap();
+        *database = Database {
+            connection: connect(),
+            sources: Vec::new(),
+        };
+
+        // Files may have moved since the session was saved
+        for source in sources {
+            if let Err(e) = database.open(&source.path, Some(&source.name)) {
+                log::warn!("Cannot reopen {}: {}", source.path.display(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        vec![
+            tool(
+                "open",
+                "Loads a CSV, TSV, JSON or JSON lines file as a table, or attaches a SQLite database",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Path of the file" },
+                        "name": { "type": "string", "description": "Name of the table or database, from the file name by default" }
+                    },
+                    "required": ["path"]
+                }),
+            ),
+            tool(
+                "describe",
+                "Lists the loaded tables, or gives the columns, row count and first rows of one table",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "table": { "type": "string", "description": "The table, e.g. 'sales' or 'shop.orders'. All tables when missing" }
+                    }
+                }),
+            ),
+            tool(
+                "query",
+                "Runs a read-only SQLite query and returns the result as a table",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "sql": { "type": "string", "description": "The query, e.g. 'SELECT region, SUM(amount) FROM sales GROUP BY region'" },
+                        "limit": { "type": "integer", "description": "Most rows returned, 50 by default and 500 at most" }
+                    },
+                    "required": ["sql"]
+                }),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of data files, removed when dropped
+    struct TempDir {
+        dir: PathBuf,
+    }
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir =
+                std::env::temp_dir().join(format!("jarvis-data-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir { dir }
+        }
+
+        fn write(&self, name: &str, content: &str) -> String {
+            let path = self.dir.join(name);
+            fs::write(&path, content).unwrap();
+            path.display().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn call(data: &Data, name: &str, arguments: Value) -> ModuleResult<Value> {
+        data.run(&ToolCallFunction {
+            name: name.to_string(),
+            module: Data::name().to_string(),
+            arguments,
+        })
+    }
+
+    #[test]
+    fn test_csv_and_json() {
+        let dir = TempDir::new("files");
+        let sales = dir.write("Sales.csv", "region,amount\nEU,10.5\nUS,3\nEU,1.5\nAsia,\n");
+        let users = dir.write("users.json", r#"[{"id": 1, "name": "Ada"}, {"id": 2}]"#);
+        let data = Data::new();
+
+        let opened = call(&data, "open", json!({ "path": sales })).unwrap();
+        assert_eq!(opened["table"], json!("sales"));
+        assert_eq!(opened["rows"], json!(4));
+        assert_eq!(opened["columns"], json!("region TEXT, amount REAL"));
+        call(&data, "open", json!({ "path": users, "name": "people" })).unwrap();
+
+        let result = call(
+            &data,
+            "query",
+            json!({ "sql": "SELECT region, SUM(amount) AS total FROM sales GROUP BY region ORDER BY total DESC" }),
+        )
+        .unwrap();
+        assert_eq!(
+            result["table"],
+            json!("| region | total |\n| --- | --- |\n| EU | 12 |\n| US | 3 |\n| Asia | NULL |")
+        );
+        assert_eq!(result["truncated"], json!(false));
+
+        let limited = call(
+            &data,
+            "query",
+            json!({ "sql": "SELECT * FROM sales", "limit": 2 }),
+        )
+        .unwrap();
+        assert_eq!(limited["rows"], json!(2));
+        assert_eq!(limited["truncated"], json!(true));
+
+        let tables = call(&data, "describe", json!({})).unwrap();
+        assert_eq!(tables["tables"].as_array().unwrap().len(), 2);
+        let people = call(&data, "describe", json!({ "table": "people" })).unwrap();
+        assert_eq!(people["columns"], json!(["id INTEGER", "name TEXT"]));
+        assert_eq!(people["rows"], json!(2));
+        assert!(call(&data, "describe", json!({ "table": "missing" })).is_err());
+    }
+
+    #[test]
+    fn test_queries_are_read_only() {
+        let dir = TempDir::new("readonly");
+        let path = dir.dir.join("shop.db");
+        let shop = Connection::open(&path).unwrap();
+        shop.execute_batch(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL); INSERT INTO orders (total) VALUES (5), (7);",
+        )
+        .unwrap();
+        drop(shop);
+
+        let data = Data::new();
+        let opened = call(&data, "open", json!({ "path": path })).unwrap();
+        assert_eq!(opened["database"], json!("shop"));
+        assert_eq!(opened["tables"][0]["table"], json!("shop.orders"));
+
+        let total = call(
+            &data,
+            "query",
+            json!({ "sql": "SELECT SUM(total) FROM shop.orders" }),
+        )
+        .unwrap();
+        assert!(total["table"].as_str().unwrap().ends_with("| 12 |"));
+
+        for sql in [
+            "DELETE FROM shop.orders",
+            "CREATE TABLE main.x (a)",
+            "ATTACH DATABASE 'other.db' AS other",
+            "SELECT 1; DELETE FROM shop.orders",
+        ] {
+            assert!(
+                call(&data, "query", json!({ "sql": sql })).is_err(),
+                "{}",
+                sql
+            );
+        }
+
+        // The session keeps the opened files
+        let state = data.state().unwrap();
+        let restored = Data::new();
+        restored.restore_state(Some(&state)).unwrap();
+        let count = call(
+            &restored,
+            "query",
+            json!({ "sql": "SELECT COUNT(*) FROM shop.orders" }),
+        )
+        .unwrap();
+        assert!(count["table"].as_str().unwrap().ends_with("| 2 |"));
+    }
+}
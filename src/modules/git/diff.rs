@@ -0,0 +1,202 @@
+/// Files whose changes are generated, only their size is shown
+const GENERATED_FILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "go.sum",
+];
+
+/// Changes to one file: its `diff --git` header and its hunks
+struct FileDiff<'a> {
+    path: &'a str,
+    header: String,
+    hunks: Vec<String>,
+}
+
+impl FileDiff<'_> {
+    fn body_len(&self) -> usize {
+        self.hunks.iter().map(String::len).sum()
+    }
+
+    fn is_generated(&self) -> bool {
+        let name = self.path.rsplit('/').next().unwrap_or(self.path);
+        GENERATED_FILES.contains(&name) || name.ends_with(".min.js")
+    }
+}
+
+/// Lines before the first file (e.g. the commit of `git show`) and the files
+fn parse(diff: &str) -> (String, Vec<FileDiff<'_>>) {
+    let mut preamble = String::new();
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    for line in diff.split_inclusive('\n') {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let path = paths
+                .rsplit_once(" b/")
+                .map(|(_, path)| path)
+                .unwrap_or(paths)
+                .trim_end();
+            files.push(FileDiff {
+                path,
+                header: line.to_string(),
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+
+        match files.last_mut() {
+            None => preamble.push_str(line),
+            Some(file) if line.starts_with("@@") => file.hunks.push(line.to_string()),
+            Some(file) => match file.hunks.last_mut() {
+                Some(hunk) => hunk.push_str(line),
+                None => file.header.push_str(line),
+            },
+        }
+    }
+
+    (preamble, files)
+}
+
+/// Lines of the hunks, for the omission notes
+fn line_count(hunks: &[String]) -> usize {
+    hunks.iter().map(|h| h.lines().count()).sum()
+}
+
+/// Shortens a diff to about `max_chars`. Every file keeps its header, and
+/// the space left is shared fairly so one huge file doesn't hide the others:
+/// small files are shown whole, large ones keep their first hunks. Changes
+/// to lock files and other generated files are left out.
+/// Returns the diff and whether it was truncated.
+pub fn truncate_diff(diff: &str, max_chars: usize) -> (String, bool) {
+    if diff.len() <= max_chars {
+        return (diff.to_string(), false);
+    }
+
+    let (preamble, files) = parse(diff);
+    let headers: usize = files.iter().map(|f| f.header.len()).sum();
+    let mut remaining = max_chars.saturating_sub(preamble.len() + headers);
+
+    // Smallest files first, so what they don't use goes to the larger ones
+    let mut budgets = vec![0; files.len()];
+    let mut order: Vec<usize> = (0..files.len())
+        .filter(|i| !files[*i].is_generated())
+        .collect();
+    order.sort_by_key(|i| files[*i].body_len());
+    for (n, i) in order.iter().enumerate() {
+        let share = remaining / (order.len() - n);
+        budgets[*i] = files[*i].body_len().min(share);
+        remaining -= budgets[*i];
+    }
+
+    let mut text = preamble;
+    for (file, budget) in files.iter().zip(budgets) {
+        text.push_str(&file.header);
+
+        if file.is_generated() {
+            text.push_str(&format!(
+                "[{} lines of changes to a generated file omitted]\n",
+                line_count(&file.hunks)
+            ));
+            continue;
+        }
+
+        let mut used = 0;
+        let mut shown = 0;
+        for hunk in &file.hunks {
+            if used + hunk.len() > budget {
+                break;
+            }
+            text.push_str(hunk);
+            used += hunk.len();
+            shown += 1;
+        }
+
+        // Not even the first hunk fits, show its beginning
+        if shown == 0
+            && let Some(hunk) = file.hunks.first()
+            && budget > 0
+        {
+            let mut cut = 0;
+            for line in hunk.split_inclusive('\n') {
+                if cut + line.len() > budget {
+                    break;
+                }
+                text.push_str(line);
+                cut += line.len();
+            }
+            let omitted = hunk[cut..].lines().count() + line_count(&file.hunks[1..]);
+            text.push_str(&format!("[... {} more lines omitted]\n", omitted));
+            continue;
+        }
+
+        let omitted = &file.hunks[shown..];
+        if !omitted.is_empty() {
+            text.push_str(&format!(
+                "[... {} more hunks ({} lines) omitted]\n",
+                omitted.len(),
+                line_count(omitted)
+            ));
+        }
+    }
+
+    (text, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_diff(path: &str, hunks: usize, lines: usize) -> String {
+        let mut diff = format!(
+            "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n",
+            path = path
+        );
+        for h in 0..hunks {
+            diff.push_str(&format!("@@ -{0},{1} +{0},{1} @@\n", h * 100 + 1, lines));
+            for l in 0..lines {
+                diff.push_str(&format!("+line {} of hunk {}\n", l, h));
+            }
+        }
+        diff
+    }
+
+    #[test]
+    fn test_small_diff_is_kept() {
+        let diff = file_diff("src/main.rs", 1, 3);
+        assert_eq!(truncate_diff(&diff, 10_000), (diff, false));
+    }
+
+    #[test]
+    fn test_large_file_does_not_hide_others() {
+        let diff = [
+            file_diff("src/big.rs", 20, 50),
+            file_diff("src/small.rs", 1, 3),
+            file_diff("Cargo.lock", 3, 40),
+        ]
+        .concat();
+
+        let (text, truncated) = truncate_diff(&diff, 3_000);
+        assert!(truncated);
+        assert!(text.len() < 3_500);
+
+        // The small file is complete, the big one keeps its first hunk
+        assert!(text.contains(&file_diff("src/small.rs", 1, 3)));
+        assert!(text.contains("+line 49 of hunk 0\n"));
+        assert!(text.contains("more hunks"));
+        assert!(text.contains("diff --git a/Cargo.lock b/Cargo.lock\n"));
+        assert!(text.contains("[123 lines of changes to a generated file omitted]"));
+    }
+
+    #[test]
+    fn test_huge_hunk_is_cut() {
+        let diff = file_diff("src/main.rs", 1, 500);
+        let (text, truncated) = truncate_diff(&diff, 1_000);
+
+        assert!(truncated);
+        assert!(text.starts_with("diff --git a/src/main.rs b/src/main.rs\n"));
+        assert!(text.contains("+line 0 of hunk 0\n"));
+        assert!(text.ends_with("more lines omitted]\n"));
+    }
+}
@@ -0,0 +1,805 @@
+mod diff;
+
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use chrono::DateTime;
+use diff::truncate_diff;
+use serde_json::{Value, json};
+use std::{path::PathBuf, process::Command};
+
+/// Diffs longer than this are truncated, keeping every file visible
+const MAX_DIFF_CHARS: usize = 12_000;
+
+/// Files shown by `show` are cut after this many characters
+const MAX_FILE_CHARS: usize = 20_000;
+
+const DEFAULT_LOG_LIMIT: u64 = 10;
+const MAX_LOG_LIMIT: u64 = 100;
+
+/// Most lines returned by `blame`
+const MAX_BLAME_LINES: usize = 200;
+
+/// Field separator of the `--format` strings
+const SEPARATOR: char = '\u{1f}';
+
+/// Read-only access to the repository Jarvis runs in. Committing, creating
+/// branches and stashing need `--execute`.
+pub struct Git {
+    dir: PathBuf,
+}
+
+/// Change of a file in `git status`, e.g. `M` as `modified`
+fn status_name(code: char) -> &'static str {
+    match code {
+        'M' => "modified",
+        'A' => "added",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        'T' => "type changed",
+        'U' => "conflict",
+        _ => "changed",
+    }
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: name.to_string(),
+            module: Git::name().to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+    }
+}
+
+impl Git {
+    pub fn new() -> Git {
+        Git {
+            dir: PathBuf::from("."),
+        }
+    }
+
+    #[cfg(test)]
+    fn in_dir(dir: PathBuf) -> Git {
+        Git { dir }
+    }
+
+    pub fn name() -> &'static str {
+        "git"
+    }
+
+    /// Runs git in the repository and returns its output
+    fn git(&self, args: &[&str]) -> ModuleResult<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(["-c", "core.quotepath=off", "-c", "color.ui=never"])
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            // Reading must not take the index lock of a repository in use
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .output()
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot run git: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(ModuleError::ExecutionError(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// A trimmed string argument, `None` when missing or empty. Values
+    /// starting with `-` are refused so they can't be taken for git options,
+    /// revisions are also passed after `--end-of-options`.
+    fn optional<'a>(
+        &self,
+        func: &'a ToolCallFunction,
+        name: &str,
+    ) -> ModuleResult<Option<&'a str>> {
+        let value = match func.arguments.get(name) {
+            None | Some(Value::Null) => return Ok(None),
+            Some(value) => value.as_str().ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!("Expected a string for '{}'", name))
+            })?,
+        }
+        .trim();
+
+        if value.starts_with('-') {
+            return Err(ModuleError::InvalidFunctionInput(format!(
+                "'{}' can't start with '-'",
+                name
+            )));
+        }
+
+        Ok(Some(value).filter(|v| !v.is_empty()))
+    }
+
+    fn required<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<&'a str> {
+        self.optional(func, name)?.ok_or_else(|| {
+            ModuleError::InvalidFunctionInput(format!("Missing '{}' argument", name))
+        })
+    }
+
+    fn status(&self) -> ModuleResult<Value> {
+        let output = self.git(&["status", "--porcelain=v1", "--branch"])?;
+
+        let mut branch = json!(null);
+        let mut upstream = json!(null);
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
+        let mut untracked = Vec::new();
+        for line in output.lines() {
+            // `## main...origin/main [ahead 1]`
+            if let Some(head) = line.strip_prefix("## ") {
+                match head.split_once("...") {
+                    Some((name, tracking)) => {
+                        branch = json!(name);
+                        upstream = json!(tracking);
+                    }
+                    None => branch = json!(head),
+                }
+                continue;
+            }
+
+            let mut codes = line.chars();
+            let (Some(index), Some(worktree)) = (codes.next(), codes.next()) else {
+                continue;
+            };
+            let path = line.get(3..).unwrap_or_default();
+
+            if index == '?' {
+                untracked.push(json!(path));
+                continue;
+            }
+            if index != ' ' {
+                staged.push(json!({ "path": path, "change": status_name(index) }));
+            }
+            if worktree != ' ' {
+                // Renames only apply to the index, the worktree has the new path
+                let path = path.rsplit(" -> ").next().unwrap_or(path);
+                unstaged.push(json!({ "path": path, "change": status_name(worktree) }));
+            }
+        }
+
+        Ok(json!({
+            "branch": branch,
+            "upstream": upstream,
+            "clean": staged.is_empty() && unstaged.is_empty() && untracked.is_empty(),
+            "staged": staged,
+            "unstaged": unstaged,
+            "untracked": untracked,
+        }))
+    }
+
+    /// Changed files with their added and removed lines, for `git diff` or
+    /// `git show` arguments
+    fn changed_files(&self, args: &[&str]) -> ModuleResult<Vec<Value>> {
+        let mut numstat = args.to_vec();
+        numstat.insert(1, "--numstat");
+
+        Ok(self
+            .git(&numstat)?
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let (added, removed, path) = (fields.next()?, fields.next()?, fields.next()?);
+                // Binary files have `-` instead of line counts
+                Some(json!({
+                    "path": path,
+                    "added": added.parse::<u64>().ok(),
+                    "removed": removed.parse::<u64>().ok(),
+                }))
+            })
+            .collect())
+    }
+
+    fn diff(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let mut args = vec!["diff", "--no-ext-diff"];
+        if func
+            .arguments
+            .get("staged")
+            .and_then(|s| s.as_bool())
+            .unwrap_or(false)
+        {
+            args.push("--cached");
+        }
+        if let Some(rev) = self.optional(func, "rev")? {
+            args.extend(["--end-of-options", rev]);
+        }
+        args.push("--");
+        if let Some(path) = self.optional(func, "path")? {
+            args.push(path);
+        }
+
+        let files = self.changed_files(&args)?;
+        let (diff, truncated) = truncate_diff(&self.git(&args)?, MAX_DIFF_CHARS);
+
+        Ok(json!({ "files": files, "diff": diff, "truncated": truncated }))
+    }
+
+    fn log(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let limit = func
+            .arguments
+            .get("limit")
+            .and_then(|l| l.as_u64())
+            .unwrap_or(DEFAULT_LOG_LIMIT)
+            .clamp(1, MAX_LOG_LIMIT)
+            .to_string();
+
+        let mut args = vec!["log", "--format=%h%x1f%an%x1f%aI%x1f%s", "-n", &limit];
+        if let Some(rev) = self.optional(func, "rev")? {
+            args.extend(["--end-of-options", rev]);
+        }
+        args.push("--");
+        if let Some(path) = self.optional(func, "path")? {
+            args.push(path);
+        }
+
+        let commits: Vec<Value> = self
+            .git(&args)?
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.splitn(4, SEPARATOR).collect();
+                let [commit, author, date, subject] = fields[..] else {
+                    return None;
+                };
+                Some(
+                    json!({ "commit": commit, "author": author, "date": date, "subject": subject }),
+                )
+            })
+            .collect();
+
+        Ok(json!({ "commits": commits }))
+    }
+
+    fn show(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let rev = self.optional(func, "rev")?.unwrap_or("HEAD");
+
+        // A file as it was in that commit
+        if let Some(path) = self.optional(func, "path")? {
+            let content = self.git(&["show", &format!("{}:{}", rev, path)])?;
+            let truncated = content.len() > MAX_FILE_CHARS;
+            let content: String = if truncated {
+                let mut end = MAX_FILE_CHARS;
+                while !content.is_char_boundary(end) {
+                    end -= 1;
+                }
+                content[..end].to_string()
+            } else {
+                content
+            };
+
+            return Ok(
+                json!({ "rev": rev, "path": path, "content": content, "truncated": truncated }),
+            );
+        }
+
+        let header = self.git(&[
+            "show",
+            "-s",
+            "--format=%H%x1f%an%x1f%aI%x1f%B",
+            "--end-of-options",
+            rev,
+            "--",
+        ])?;
+        let fields: Vec<&str> = header.splitn(4, SEPARATOR).collect();
+        let [commit, author, date, message] = fields[..] else {
+            return Err(ModuleError::ExecutionError(format!(
+                "Unexpected output of git show for {}",
+                rev
+            )));
+        };
+
+        let args = [
+            "show",
+            "--format=",
+            "--no-ext-diff",
+            "--end-of-options",
+            rev,
+            "--",
+        ];
+        let files = self.changed_files(&args)?;
+        let (diff, truncated) = truncate_diff(&self.git(&args)?, MAX_DIFF_CHARS);
+
+        Ok(json!({
+            "commit": commit,
+            "author": author,
+            "date": date,
+            "message": message.trim(),
+            "files": files,
+            "diff": diff,
+            "truncated": truncated,
+        }))
+    }
+
+    fn blame(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let path = self.required(func, "path")?;
+        let start = func.arguments.get("start_line").and_then(|l| l.as_u64());
+        let end = func.arguments.get("end_line").and_then(|l| l.as_u64());
+
+        let range = match (start, end) {
+            (Some(start), Some(end)) => Some(format!("{},{}", start, end)),
+            (Some(start), None) => Some(format!("{},", start)),
+            (None, Some(end)) => Some(format!("1,{}", end)),
+            (None, None) => None,
+        };
+
+        let mut args = vec!["blame", "--line-porcelain"];
+        if let Some(range) = &range {
+            args.extend(["-L", range]);
+        }
+        args.extend(["--", path]);
+
+        let mut lines = Vec::new();
+        let (mut commit, mut number, mut author, mut date) = ("", "", "", String::new());
+        for line in self.git(&args)?.lines() {
+            if let Some(content) = line.strip_prefix('\t') {
+                lines.push(json!({
+                    "line": number.parse::<u64>().ok(),
+                    "commit": commit.get(..7).unwrap_or(commit),
+                    "author": author,
+                    "date": date,
+                    "content": content,
+                }));
+            } else if let Some(name) = line.strip_prefix("author ") {
+                author = name;
+            } else if let Some(time) = line.strip_prefix("author-time ") {
+                date = time
+                    .parse()
+                    .ok()
+                    .and_then(|t| DateTime::from_timestamp(t, 0))
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+            } else {
+                // `<commit> <original line> <final line> [<lines in group>]`
+                let fields: Vec<&str> = line.split(' ').collect();
+                if fields.len() >= 3
+                    && fields[0].len() >= 40
+                    && fields[0].chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    commit = fields[0];
+                    number = fields[2];
+                }
+            }
+        }
+
+        let truncated = lines.len() > MAX_BLAME_LINES;
+        lines.truncate(MAX_BLAME_LINES);
+
+        Ok(json!({ "path": path, "lines": lines, "truncated": truncated }))
+    }
+
+    fn branch_list(&self) -> ModuleResult<Value> {
+        let output = self.git(&[
+            "for-each-ref",
+            "--format=%(HEAD)%1f%(refname)%1f%(objectname:short)%1f%(committerdate:short)%1f%(subject)",
+            "refs/heads",
+            "refs/remotes",
+        ])?;
+
+        let mut current = json!(null);
+        let mut branches = Vec::new();
+        for line in output.lines() {
+            let fields: Vec<&str> = line.splitn(5, SEPARATOR).collect();
+            let [head, refname, commit, date, subject] = fields[..] else {
+                continue;
+            };
+
+            let (name, remote) = match refname.strip_prefix("refs/heads/") {
+                Some(name) => (name, false),
+                None => (refname.trim_start_matches("refs/remotes/"), true),
+            };
+            // `origin/HEAD` only points to another remote branch
+            if remote && name.ends_with("/HEAD") {
+                continue;
+            }
+            if head == "*" {
+                current = json!(name);
+            }
+
+            branches.push(json!({
+                "name": name,
+                "remote": remote,
+                "commit": commit,
+                "date": date,
+                "subject": subject,
+            }));
+        }
+
+        Ok(json!({ "current": current, "branches": branches }))
+    }
+
+    fn commit(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let message = func
+            .arguments
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput("Missing 'message' argument".into())
+            })?;
+
+        let paths: Vec<&str> = func
+            .arguments
+            .get("paths")
+            .and_then(|p| p.as_array())
+            .map(|paths| paths.iter().filter_map(|p| p.as_str()).collect())
+            .unwrap_or_default();
+        if !paths.is_empty() {
+            let mut args = vec!["add", "--"];
+            args.extend(&paths);
+            self.git(&args)?;
+        }
+
+        let mut args = vec!["commit", "-m", message];
+        if func
+            .arguments
+            .get("all")
+            .and_then(|a| a.as_bool())
+            .unwrap_or(false)
+        {
+            args.push("--all");
+        }
+        self.git(&args)?;
+
+        let head = self.git(&["log", "-1", "--format=%h%x1f%s"])?;
+        let (commit, subject) = head.trim().split_once(SEPARATOR).unwrap_or_default();
+        Ok(json!({ "commit": commit, "subject": subject }))
+    }
+
+    fn create_branch(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let name = self.required(func, "name")?;
+        self.git(&["check-ref-format", "--branch", name])
+            .map_err(|_| {
+                ModuleError::InvalidFunctionInput(format!("Invalid branch name {}", name))
+            })?;
+
+        let mut args = vec!["checkout", "-b", name];
+        if let Some(start) = self.optional(func, "start")? {
+            args.push(start);
+        }
+        self.git(&args)?;
+
+        Ok(json!({ "branch": name }))
+    }
+
+    fn stash(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        match self.optional(func, "action")?.unwrap_or("push") {
+            "push" => {
+                let mut args = vec!["stash", "push", "--include-untracked"];
+                if let Some(message) = self.optional(func, "message")? {
+                    args.extend(["-m", message]);
+                }
+                let output = self.git(&args)?;
+                Ok(
+                    json!({ "stashed": !output.contains("No local changes"), "output": output.trim() }),
+                )
+            }
+            "pop" => {
+                let output = self.git(&["stash", "pop"])?;
+                Ok(json!({ "output": output.trim() }))
+            }
+            "list" => {
+                let output = self.git(&["stash", "list"])?;
+                Ok(json!({ "stashes": output.lines().collect::<Vec<_>>() }))
+            }
+            action => Err(ModuleError::InvalidFunctionInput(format!(
+                "Unknown stash action {}, expected push, pop or list",
+                action
+            ))),
+        }
+    }
+}
+
+impl Module for Git {
+    fn name(&self) -> &'static str {
+        Git::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Reads the status, diffs, history and blame of the current git repository, and commits, branches or stashes with --execute."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **git**: Access to the git repository of the current directory.
+  - **Rules**:
+    - Use `status` and `diff` to see what changed before reviewing changes or drafting a commit message. Use `staged` for what is about to be committed.
+    - Use `log`, `show` and `blame` to find when and why code changed. `show` with a `path` returns a file as it was in a commit.
+    - Use `branch_list` to see the local and remote branches.
+    - Large diffs are truncated, call `diff` again with a `path` to see all the changes of one file.
+    - `commit`, `create_branch` and `stash` change the repository, they are only available when the user runs with `--execute`. Only use them when asked to."#
+    }
+
+    fn mutating_tools(&self) -> &'static [&'static str] {
+        &["commit", "create_branch", "stash"]
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        match func.name.as_str() {
+            "status" => self.status(),
+            "diff" => self.diff(func),
+            "log" => self.log(func),
+            "show" => self.show(func),
+            "blame" => self.blame(func),
+            "branch_list" => self.branch_list(),
+            "commit" => self.commit(func),
+            "create_branch" => self.create_branch(func),
+            "stash" => self.stash(func),
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        let path = json!({ "type": "string", "description": "Limit to this file or directory" });
+
+        vec![
+            tool(
+                "status",
+                "Shows the current branch and the staged, unstaged and untracked files",
+                json!({ "type": "object", "properties": {} }),
+            ),
+            tool(
+                "diff",
+                "Shows the changes of the working tree, the staged changes, or the changes since a commit",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "staged": { "type": "boolean", "description": "Show the staged changes instead of the unstaged ones" },
+                        "rev": { "type": "string", "description": "Compare with this commit or branch (e.g. 'main' or 'HEAD~3')" },
+                        "path": path
+                    }
+                }),
+            ),
+            tool(
+                "log",
+                "Lists the latest commits",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "limit": { "type": "integer", "description": "Number of commits, 10 by default" },
+                        "rev": { "type": "string", "description": "Branch or range to list (e.g. 'main..HEAD')" },
+                        "path": path
+                    }
+                }),
+            ),
+            tool(
+                "show",
+                "Shows a commit with its message and changes, or a file as it was in a commit",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "rev": { "type": "string", "description": "Commit to show, HEAD by default" },
+                        "path": { "type": "string", "description": "File to show as it was in the commit" }
+                    }
+                }),
+            ),
+            tool(
+                "blame",
+                "Shows the commit, author and date of the last change of each line of a file",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "The file" },
+                        "start_line": { "type": "integer", "description": "First line" },
+                        "end_line": { "type": "integer", "description": "Last line" }
+                    },
+                    "required": ["path"]
+                }),
+            ),
+            tool(
+                "branch_list",
+                "Lists the local and remote branches with their last commit",
+                json!({ "type": "object", "properties": {} }),
+            ),
+            tool(
+                "commit",
+                "Commits the staged changes",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "message": { "type": "string", "description": "The commit message" },
+                        "paths": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Files to stage before committing"
+                        },
+                        "all": { "type": "boolean", "description": "Stage every modified file first (git commit -a)" }
+                    },
+                    "required": ["message"]
+                }),
+            ),
+            tool(
+                "create_branch",
+                "Creates a branch and switches to it (git checkout -b)",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "description": "Name of the new branch" },
+                        "start": { "type": "string", "description": "Commit or branch to start from, HEAD by default" }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            tool(
+                "stash",
+                "Stashes the uncommitted changes, restores the last stash or lists the stashes",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "action": { "type": "string", "enum": ["push", "pop", "list"], "description": "push by default" },
+                        "message": { "type": "string", "description": "Description of the stash" }
+                    }
+                }),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::Path};
+
+    /// A repository with one commit of `notes.txt`, removed when dropped
+    struct TempRepo {
+        dir: PathBuf,
+    }
+
+    impl TempRepo {
+        fn new(name: &str) -> TempRepo {
+            let dir =
+                std::env::temp_dir().join(format!("jarvis-git-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+
+            let repo = TempRepo { dir };
+            repo.git(&["init", "-q", "-b", "main"]);
+            repo.git(&["config", "user.name", "Ada"]);
+            repo.git(&["config", "user.email", "ada@example.com"]);
+            repo.git(&["config", "commit.gpgsign", "false"]);
+            repo.write("notes.txt", "first\nsecond\n");
+            repo.git(&["add", "notes.txt"]);
+            repo.git(&["commit", "-q", "-m", "Add notes"]);
+            repo
+        }
+
+        fn git(&self, args: &[&str]) {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&self.dir)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        }
+
+        fn write(&self, path: &str, content: &str) {
+            fs::write(self.dir.join(path), content).unwrap();
+        }
+
+        fn path(&self) -> &Path {
+            &self.dir
+        }
+    }
+
+    impl Drop for TempRepo {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn call(git: &Git, name: &str, arguments: Value) -> ModuleResult<Value> {
+        git.run(&ToolCallFunction {
+            name: name.to_string(),
+            module: Git::name().to_string(),
+            arguments,
+        })
+    }
+
+    #[test]
+    fn test_read_only_tools() {
+        let repo = TempRepo::new("read");
+        let git = Git::in_dir(repo.path().to_path_buf());
+
+        repo.write("notes.txt", "first\nchanged\n");
+        repo.write("todo.txt", "new\n");
+
+        let status = call(&git, "status", json!({})).unwrap();
+        assert_eq!(status["branch"], json!("main"));
+        assert_eq!(
+            status["unstaged"],
+            json!([{ "path": "notes.txt", "change": "modified" }])
+        );
+        assert_eq!(status["untracked"], json!(["todo.txt"]));
+        assert_eq!(status["clean"], json!(false));
+
+        let diff = call(&git, "diff", json!({})).unwrap();
+        assert_eq!(
+            diff["files"],
+            json!([{ "path": "notes.txt", "added": 1, "removed": 1 }])
+        );
+        assert!(diff["diff"].as_str().unwrap().contains("+changed"));
+        assert_eq!(diff["truncated"], json!(false));
+
+        let log = call(&git, "log", json!({ "limit": 5 })).unwrap();
+        assert_eq!(log["commits"][0]["subject"], json!("Add notes"));
+        assert_eq!(log["commits"][0]["author"], json!("Ada"));
+
+        let show = call(&git, "show", json!({})).unwrap();
+        assert_eq!(show["message"], json!("Add notes"));
+        assert!(show["diff"].as_str().unwrap().contains("+second"));
+
+        let file = call(&git, "show", json!({ "path": "notes.txt" })).unwrap();
+        assert_eq!(file["content"], json!("first\nsecond\n"));
+
+        let blame = call(
+            &git,
+            "blame",
+            json!({ "path": "notes.txt", "start_line": 1, "end_line": 1 }),
+        )
+        .unwrap();
+        assert_eq!(blame["lines"][0]["line"], json!(1));
+        assert_eq!(blame["lines"][0]["content"], json!("first"));
+        assert_eq!(blame["lines"][0]["author"], json!("Ada"));
+        assert_eq!(blame["lines"].as_array().unwrap().len(), 1);
+
+        let branches = call(&git, "branch_list", json!({})).unwrap();
+        assert_eq!(branches["current"], json!("main"));
+        assert_eq!(branches["branches"][0]["subject"], json!("Add notes"));
+
+        // Arguments can't smuggle git options
+        assert!(call(&git, "diff", json!({ "rev": "--output=/tmp/x" })).is_err());
+        for tool in ["diff", "log", "show"] {
+            let output = repo.path().join("written");
+            let rev = format!(" --output={}", output.display());
+            assert!(call(&git, tool, json!({ "rev": rev })).is_err());
+            assert!(!output.exists());
+        }
+        assert!(call(&git, "blame", json!({})).is_err());
+    }
+
+    #[test]
+    fn test_mutating_tools() {
+        let repo = TempRepo::new("write");
+        let git = Git::in_dir(repo.path().to_path_buf());
+
+        assert!(call(&git, "create_branch", json!({ "name": "bad..name" })).is_err());
+        let branch = call(&git, "create_branch", json!({ "name": "feature" })).unwrap();
+        assert_eq!(branch["branch"], json!("feature"));
+
+        repo.write("todo.txt", "new\n");
+        let commit = call(
+            &git,
+            "commit",
+            json!({ "message": "Add todo", "paths": ["todo.txt"] }),
+        )
+        .unwrap();
+        assert_eq!(commit["subject"], json!("Add todo"));
+
+        repo.write("notes.txt", "stashed\n");
+        let stash = call(&git, "stash", json!({ "message": "wip" })).unwrap();
+        assert_eq!(stash["stashed"], json!(true));
+        assert_eq!(
+            call(&git, "status", json!({})).unwrap()["clean"],
+            json!(true)
+        );
+
+        let list = call(&git, "stash", json!({ "action": "list" })).unwrap();
+        assert_eq!(list["stashes"].as_array().unwrap().len(), 1);
+        call(&git, "stash", json!({ "action": "pop" })).unwrap();
+        assert_eq!(
+            call(&git, "status", json!({})).unwrap()["clean"],
+            json!(false)
+        );
+
+        // Nothing staged
+        assert!(call(&git, "commit", json!({ "message": "Empty" })).is_err());
+    }
+}
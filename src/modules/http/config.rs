@@ -0,0 +1,229 @@
+use crate::{AppError, AppResult, utils::config_dir};
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
+
+/// Outbound access of the `http` module, read from `http.toml` in the config
+/// directory
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// No request leaves the machine when false
+    pub enabled: bool,
+    /// Only these domains and their subdomains can be reached, any domain
+    /// when empty
+    pub allowed_domains: Vec<String>,
+    /// Loopback and private network addresses (e.g. `localhost`,
+    /// `192.168.1.1`) can be reached
+    pub allow_private: bool,
+    pub max_redirects: usize,
+    /// Responses are cut after this many bytes
+    pub max_bytes: u64,
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_domains: Vec::new(),
+            allow_private: false,
+            max_redirects: 5,
+            max_bytes: 2_000_000,
+            timeout_secs: 20,
+        }
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    config_dir().join("http.toml")
+}
+
+fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)
+        }
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                || ip
+                    .to_ipv4_mapped()
+                    .is_some_and(|v4| is_private_ip(v4.into()))
+        }
+    }
+}
+
+/// Resolves host names, refusing the ones pointing at loopback or private
+/// network addresses, so a public name can't lead to a local service
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+
+            if let Some(addr) = addrs.iter().find(|addr| is_private_ip(addr.ip())) {
+                return Err(format!(
+                    "{} resolves to the local or private network address {}",
+                    host,
+                    addr.ip()
+                )
+                .into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl HttpConfig {
+    /// The user configuration, the defaults when there is none
+    pub fn load() -> AppResult<HttpConfig> {
+        let path = config_path();
+        if !path.is_file() {
+            return Ok(HttpConfig::default());
+        }
+
+        toml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| AppError::from(&format!("Invalid {}: {}", path.display(), e)))
+    }
+
+    /// Configuration refusing every request, used when the user one is invalid
+    pub fn disabled() -> HttpConfig {
+        HttpConfig {
+            enabled: false,
+            ..HttpConfig::default()
+        }
+    }
+
+    /// Why `url` can't be reached, `None` when it can
+    pub fn refusal(&self, url: &Url) -> Option<String> {
+        if !self.enabled {
+            return Some(format!(
+                "Outbound access is disabled in {}",
+                config_path().display()
+            ));
+        }
+        if !matches!(url.scheme(), "http" | "https") {
+            return Some(format!(
+                "Only http and https URLs are supported, not {}",
+                url
+            ));
+        }
+
+        let Some(host) = url.host_str() else {
+            return Some(format!("{} has no host", url));
+        };
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_lowercase();
+
+        if !self.allow_private {
+            let private = match host.parse::<IpAddr>() {
+                Ok(ip) => is_private_ip(ip),
+                Err(_) => host == "localhost" || host.ends_with(".localhost"),
+            };
+            if private {
+                return Some(format!("{} is a local or private network address", host));
+            }
+        }
+
+        let allowed = self.allowed_domains.is_empty()
+            || self.allowed_domains.iter().any(|domain| {
+                let domain = domain.trim_start_matches("*.").to_lowercase();
+                host == domain || host.ends_with(&format!(".{}", domain))
+            });
+        if !allowed {
+            return Some(format!(
+                "{} is not in the allowed domains of {}",
+                host,
+                config_path().display()
+            ));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refused(config: &HttpConfig, url: &str) -> bool {
+        config.refusal(&Url::parse(url).unwrap()).is_some()
+    }
+
+    #[test]
+    fn test_refusal() {
+        let config = HttpConfig::default();
+        assert!(!refused(&config, "https://example.com/page"));
+        assert!(refused(&config, "ftp://example.com/file"));
+        assert!(refused(&config, "http://localhost:8080/"));
+        assert!(refused(&config, "http://127.0.0.1/"));
+        assert!(refused(&config, "http://192.168.1.1/"));
+        assert!(refused(&config, "http://[::1]/"));
+        assert!(refused(&config, "http://[::ffff:10.0.0.1]/"));
+
+        let config = HttpConfig {
+            allowed_domains: vec!["docs.rs".to_string(), "*.wikipedia.org".to_string()],
+            ..HttpConfig::default()
+        };
+        assert!(!refused(&config, "https://docs.rs/serde"));
+        assert!(!refused(&config, "https://en.wikipedia.org/wiki/Rust"));
+        assert!(refused(&config, "https://notdocs.rs/"));
+        assert!(refused(&config, "https://example.com/"));
+
+        assert!(refused(&HttpConfig::disabled(), "https://docs.rs/"));
+    }
+
+    #[test]
+    fn test_public_resolver() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let resolve = |host: &str| runtime.block_on(PublicResolver.resolve(host.parse().unwrap()));
+
+        // Names are checked by what they resolve to, not how they are spelled
+        let error = resolve("localhost").err().unwrap().to_string();
+        assert!(
+            error.contains("local or private network address"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: HttpConfig = toml::from_str(
+            r#"
+                allowed_domains = ["example.com"]
+                max_redirects = 2
+            "#,
+        )
+        .unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.allowed_domains, vec!["example.com".to_string()]);
+        assert_eq!(config.max_redirects, 2);
+        assert_eq!(config.max_bytes, HttpConfig::default().max_bytes);
+
+        assert!(toml::from_str::<HttpConfig>("enable = false").is_err());
+    }
+}
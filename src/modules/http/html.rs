@@ -0,0 +1,438 @@
+use reqwest::Url;
+
+/// Elements whose content is never shown
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "svg", "template", "iframe", "head", "nav",
+];
+
+/// Elements shown as their own paragraph
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "footer",
+    "aside",
+    "table",
+    "form",
+    "blockquote",
+    "figure",
+    "figcaption",
+    "dl",
+    "dt",
+    "dd",
+    "address",
+    "details",
+    "summary",
+];
+
+/// A web page as markdown
+#[derive(Debug, PartialEq)]
+pub struct Page {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+/// Replaces the common character references (`&amp;`, `&#39;`, `&#x2014;`, ...)
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "copy" => Some('©'),
+            _ => {
+                let code = entity.strip_prefix('#')?;
+                let code = match code.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => code.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Position of the `>` ending the tag starting at `start`, quotes included
+fn tag_end(html: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(start + i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Value of an attribute in the inside of a tag (`a href="/docs" class=x`)
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag.split_once(char::is_whitespace)?.1;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            None => "",
+            Some(value) => {
+                let value = value.trim_start();
+                let (value, next) = match value.chars().next() {
+                    Some(q @ ('"' | '\'')) => match value[1..].find(q) {
+                        Some(end) => (&value[1..end + 1], &value[end + 2..]),
+                        None => (&value[1..], ""),
+                    },
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                rest = next;
+                value
+            }
+        };
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode_entities(value));
+        }
+    }
+}
+
+/// Builds the markdown, collapsing whitespace outside `<pre>`
+#[derive(Default)]
+struct Writer {
+    out: String,
+    /// Whitespace was seen since the last character
+    space: bool,
+    pre: usize,
+    /// Open lists, with the next number of ordered ones
+    lists: Vec<Option<usize>>,
+    /// Start of the open links in `out`, with their target
+    links: Vec<(usize, Option<String>)>,
+}
+
+impl Writer {
+    fn text(&mut self, text: &str) {
+        if self.pre > 0 {
+            self.out.push_str(text);
+            return;
+        }
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+            } else {
+                self.flush_space();
+                self.out.push(c);
+            }
+        }
+    }
+
+    /// Writes the pending space, unless at the start of a line
+    fn flush_space(&mut self) {
+        if self.space && !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+            self.out.push(' ');
+        }
+        self.space = false;
+    }
+
+    /// Ends the current line, leaving `count` line breaks
+    fn newlines(&mut self, count: usize) {
+        self.space = false;
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if self.out.is_empty() {
+            return;
+        }
+
+        let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+        for _ in existing..count {
+            self.out.push('\n');
+        }
+    }
+
+    fn open(&mut self, name: &str, tag: &str, base: Option<&Url>) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.newlines(2);
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&format!("{} ", "#".repeat(level)));
+            }
+            "br" => self.newlines(1),
+            "hr" => {
+                self.newlines(2);
+                self.out.push_str("---");
+                self.newlines(2);
+            }
+            "pre" => {
+                self.newlines(2);
+                self.out.push_str("```\n");
+                self.pre += 1;
+            }
+            "code" if self.pre == 0 => {
+                self.flush_space();
+                self.out.push('`');
+            }
+            "strong" | "b" => {
+                self.flush_space();
+                self.out.push_str("**");
+            }
+            "em" | "i" => {
+                self.flush_space();
+                self.out.push('*');
+            }
+            "ul" | "ol" => {
+                self.newlines(if self.lists.is_empty() { 2 } else { 1 });
+                self.lists.push((name == "ol").then_some(1));
+            }
+            "li" => {
+                self.newlines(1);
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        self.out.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            "tr" => self.newlines(1),
+            "td" | "th" => {
+                if !self.out.is_empty() && !self.out.ends_with('\n') {
+                    self.out.push_str(" | ");
+                }
+                self.space = false;
+            }
+            "a" => {
+                self.flush_space();
+                let target = attribute(tag, "href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"))
+                    .and_then(|href| match base {
+                        Some(base) => base.join(&href).ok().map(|url| url.to_string()),
+                        None => Some(href),
+                    });
+                self.links.push((self.out.len(), target));
+            }
+            _ if BLOCKS.contains(&name) => self.newlines(2),
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.newlines(2),
+            "pre" if self.pre > 0 => {
+                self.pre -= 1;
+                if !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.out.push_str("```");
+                self.newlines(2);
+            }
+            "code" if self.pre == 0 => self.out.push('`'),
+            "strong" | "b" => self.out.push_str("**"),
+            "em" | "i" => self.out.push('*'),
+            "ul" | "ol" => {
+                self.lists.pop();
+                self.newlines(if self.lists.is_empty() { 2 } else { 1 });
+            }
+            "tr" => self.newlines(1),
+            "a" => {
+                let Some((start, target)) = self.links.pop() else {
+                    return;
+                };
+                let text = self.out[start..]
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if let Some(target) = target
+                    && !text.is_empty()
+                {
+                    self.out.truncate(start);
+                    self.out.push_str(&format!("[{}]({})", text, target));
+                }
+            }
+            _ if BLOCKS.contains(&name) => self.newlines(2),
+            _ => {}
+        }
+    }
+}
+
+fn title(html: &str, lower: &str) -> Option<String> {
+    let start = lower.find("<title")?;
+    let start = tag_end(html, start)? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = decode_entities(&html[start..end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(title).filter(|t| !t.is_empty())
+}
+
+/// Converts a page to markdown: headings, paragraphs, lists, links (made
+/// absolute against `base`), emphasis and code are kept, scripts, styles
+/// and navigation are left out
+pub fn html_to_markdown(html: &str, base: Option<&Url>) -> Page {
+    // Same byte offsets as `html`, for case insensitive searches
+    let lower = html.to_ascii_lowercase();
+    let mut writer = Writer::default();
+    let mut i = 0;
+
+    while let Some(offset) = html[i..].find('<') {
+        let start = i + offset;
+        writer.text(&decode_entities(&html[i..start]));
+        i = start + 1;
+
+        if lower[start..].starts_with("<!--") {
+            i = lower[start..]
+                .find("-->")
+                .map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+
+        let next = html[start + 1..].chars().next();
+        if !next.is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) {
+            writer.text("<");
+            continue;
+        }
+
+        let Some(end) = tag_end(html, start) else {
+            break;
+        };
+        i = end + 1;
+        if matches!(next, Some('!' | '?')) {
+            continue;
+        }
+
+        let tag = html[start + 1..end].trim_end_matches('/').trim();
+        let closing = tag.starts_with('/');
+        let name = lower[start + 1..end]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_string();
+
+        if closing {
+            writer.close(&name);
+        } else if SKIPPED.contains(&name.as_str()) && !html[start..end].ends_with('/') {
+            i = lower[i..]
+                .find(&format!("</{}", name))
+                .and_then(|close| tag_end(html, i + close))
+                .map_or(html.len(), |close| close + 1);
+        } else {
+            writer.open(&name, tag, base);
+        }
+    }
+    writer.text(&decode_entities(&html[i.min(html.len())..]));
+
+    let mut text = String::new();
+    let mut blank = 0;
+    for line in writer.out.lines() {
+        let line = line.trim_end();
+        blank = if line.is_empty() { blank + 1 } else { 0 };
+        if blank < 2 {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+
+    Page {
+        title: title(html, &lower),
+        text: text.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &amp; b &lt;c&gt; &#39;d&#x27; &mdash; &unknown; & e"),
+            "a & b <c> 'd' — &unknown; & e"
+        );
+    }
+
+    #[test]
+    fn test_html_to_markdown() {
+        let html = r##"<!DOCTYPE html>
+            <html><head><title>Rust &amp; Jarvis</title><style>body { color: red }</style></head>
+            <body>
+              <nav><a href="/">Home</a></nav>
+              <script>var x = "<p>hidden</p>";</script>
+              <h1>Getting   started</h1>
+              <p>Install it with <code>cargo install</code>, see the
+                 <a href="/docs/guide.html">guide</a> or <a href="#top">top</a>.</p>
+              <!-- a comment -->
+              <ul><li>First <b>point</b></li><li>Second<ol><li>Nested</li></ol></li></ul>
+              <pre>fn main() {
+    println!("hi");
+}</pre>
+              <p>1 &lt; 2<br>end</p>
+            </body></html>"##;
+
+        let base = Url::parse("https://example.com/docs/index.html").unwrap();
+        let page = html_to_markdown(html, Some(&base));
+
+        assert_eq!(page.title, Some("Rust & Jarvis".to_string()));
+        assert_eq!(
+            page.text,
+            "# Getting started\n\n\
+             Install it with `cargo install`, see the [guide](https://example.com/docs/guide.html) or top.\n\n\
+             - First **point**\n\
+             - Second\n  1. Nested\n\n\
+             ```\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+             1 < 2\nend"
+        );
+    }
+}
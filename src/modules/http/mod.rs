@@ -0,0 +1,621 @@
+mod config;
+mod html;
+
+pub use config::HttpConfig;
+
+use config::PublicResolver;
+
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use html::html_to_markdown;
+use reqwest::{
+    Method, Url,
+    blocking::Client,
+    header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue},
+    redirect::Policy,
+};
+use serde_json::{Map, Value, json};
+use std::{error::Error, io::Read, sync::Arc, thread, time::Duration};
+
+/// Page content and response bodies are cut after this many characters,
+/// the rest can be read with `start`
+const MAX_CONTENT_CHARS: usize = 20_000;
+
+/// Reads web pages and calls web APIs, within the limits of `http.toml`.
+/// Requests other than `fetch` can change things and need `--execute`.
+pub struct Http {
+    config: HttpConfig,
+}
+
+/// A request to send, owned so it can move to the request thread
+struct Request {
+    method: Method,
+    url: Url,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+}
+
+/// A response, its body cut at `max_bytes`
+struct Response {
+    url: Url,
+    status: u16,
+    headers: HeaderMap,
+    body: Vec<u8>,
+    truncated: bool,
+}
+
+impl Response {
+    fn content_type(&self) -> String {
+        self.headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_lowercase()
+    }
+}
+
+/// The error with its causes, reqwest only shows the outermost one
+fn request_error(error: reqwest::Error) -> ModuleError {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    ModuleError::ExecutionError(message)
+}
+
+/// Characters of `text` from `start`, at most `MAX_CONTENT_CHARS` of them,
+/// with where to continue when there is more
+fn page(text: &str, start: usize) -> (String, Option<usize>) {
+    let mut chars = text.chars().skip(start);
+    let content: String = chars.by_ref().take(MAX_CONTENT_CHARS).collect();
+    let next = chars.next().map(|_| start + MAX_CONTENT_CHARS);
+    (content, next)
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: name.to_string(),
+            module: Http::name().to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+    }
+}
+
+impl Http {
+    /// Uses `http.toml`, an invalid one disables outbound access
+    pub fn new() -> Http {
+        let config = HttpConfig::load().unwrap_or_else(|e| {
+            log::error!("{}, outbound access is disabled", e);
+            HttpConfig::disabled()
+        });
+        Http { config }
+    }
+
+    #[cfg(test)]
+    fn with_config(config: HttpConfig) -> Http {
+        Http { config }
+    }
+
+    pub fn name() -> &'static str {
+        "http"
+    }
+
+    fn string<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<Option<&'a str>> {
+        match func.arguments.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value.as_str().map(|v| Some(v.trim())).ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!("Expected a string for '{}'", name))
+            }),
+        }
+    }
+
+    fn url(&self, func: &ToolCallFunction) -> ModuleResult<Url> {
+        let url = self.string(func, "url")?.ok_or_else(|| {
+            ModuleError::InvalidFunctionInput("Missing 'url' argument".to_string())
+        })?;
+
+        // `example.com/page` is taken as https
+        let parsed = if url.contains("://") {
+            Url::parse(url)
+        } else {
+            Url::parse(&format!("https://{}", url))
+        };
+        let url = parsed.map_err(|e| {
+            ModuleError::InvalidFunctionInput(format!("Invalid URL {}: {}", url, e))
+        })?;
+
+        match self.config.refusal(&url) {
+            Some(reason) => Err(ModuleError::ExecutionError(reason)),
+            None => Ok(url),
+        }
+    }
+
+    fn start(&self, func: &ToolCallFunction) -> ModuleResult<usize> {
+        match func.arguments.get("start") {
+            None | Some(Value::Null) => Ok(0),
+            Some(value) => value.as_u64().map(|v| v as usize).ok_or_else(|| {
+                ModuleError::InvalidFunctionInput("'start' must be a positive integer".to_string())
+            }),
+        }
+    }
+
+    /// Sends a request on its own thread, the blocking client can't run
+    /// on the async runtime
+    fn send(&self, request: Request) -> ModuleResult<Response> {
+        let config = self.config.clone();
+
+        thread::spawn(move || {
+            let redirects = config.clone();
+            let mut client = Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .user_agent(concat!("jarvis/", env!("CARGO_PKG_VERSION")))
+                .redirect(Policy::custom(move |attempt| {
+                    if attempt.previous().len() > redirects.max_redirects {
+                        let error = format!("more than {} redirects", redirects.max_redirects);
+                        return attempt.error(error);
+                    }
+                    match redirects.refusal(attempt.url()) {
+                        Some(reason) => attempt.error(reason),
+                        None => attempt.follow(),
+                    }
+                }));
+            // Every hop is checked by the addresses its host resolves to as well
+            if !config.allow_private {
+                client = client.dns_resolver(Arc::new(PublicResolver));
+            }
+            let client = client.build().map_err(request_error)?;
+
+            let mut builder = client
+                .request(request.method, request.url)
+                .headers(request.headers);
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let response = builder.send().map_err(request_error)?;
+
+            let url = response.url().clone();
+            let status = response.status().as_u16();
+            let headers = response.headers().clone();
+            let mut body = Vec::new();
+            response
+                .take(config.max_bytes + 1)
+                .read_to_end(&mut body)
+                .map_err(|e| ModuleError::ExecutionError(format!("Cannot read {}: {}", url, e)))?;
+
+            let truncated = body.len() as u64 > config.max_bytes;
+            body.truncate(config.max_bytes as usize);
+
+            Ok(Response {
+                url,
+                status,
+                headers,
+                body,
+                truncated,
+            })
+        })
+        .join()
+        .map_err(|_| ModuleError::ExecutionError("The request failed unexpectedly".to_string()))?
+    }
+
+    fn fetch(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let start = self.start(func)?;
+        let response = self.send(Request {
+            method: Method::GET,
+            url: self.url(func)?,
+            headers: HeaderMap::new(),
+            body: None,
+        })?;
+
+        let content_type = response.content_type();
+        let body = String::from_utf8_lossy(&response.body);
+        let mut title = None;
+        let text = if content_type.contains("html")
+            || (content_type.is_empty() && body.trim_start().starts_with('<'))
+        {
+            let page = html_to_markdown(&body, Some(&response.url));
+            title = page.title;
+            page.text
+        } else if content_type.contains("json") {
+            serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|v| serde_json::to_string_pretty(&v).ok())
+                .unwrap_or_else(|| body.into_owned())
+        } else if content_type.is_empty()
+            || content_type.starts_with("text/")
+            || content_type.contains("xml")
+        {
+            body.into_owned()
+        } else {
+            return Err(ModuleError::ExecutionError(format!(
+                "{} is {}, not a text document",
+                response.url, content_type
+            )));
+        };
+
+        let (content, next) = page(&text, start);
+        let mut result = json!({
+            "url": response.url.as_str(),
+            "status": response.status,
+            "content_type": content_type,
+            "title": title,
+            "content": content,
+        });
+        if let Some(next) = next {
+            result["next_start"] = json!(next);
+        }
+        if response.truncated {
+            result["note"] = json!(format!(
+                "Only the first {} bytes of the page were downloaded",
+                self.config.max_bytes
+            ));
+        }
+
+        Ok(result)
+    }
+
+    fn request(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let method = self.string(func, "method")?.unwrap_or("GET").to_uppercase();
+        let method = match method.as_str() {
+            "GET" | "HEAD" | "POST" | "PUT" | "PATCH" | "DELETE" | "OPTIONS" => {
+                Method::from_bytes(method.as_bytes()).unwrap_or(Method::GET)
+            }
+            _ => {
+                return Err(ModuleError::InvalidFunctionInput(format!(
+                    "Unsupported method {}",
+                    method
+                )));
+            }
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Some(given) = func.arguments.get("headers").and_then(|v| v.as_object()) {
+            for (name, value) in given {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                    ModuleError::InvalidFunctionInput(format!("Invalid header name {}", name))
+                })?;
+                let value = HeaderValue::from_str(&value).map_err(|_| {
+                    ModuleError::InvalidFunctionInput(format!("Invalid value for header {}", name))
+                })?;
+                headers.insert(name, value);
+            }
+        }
+
+        // Objects and arrays are sent as JSON, strings as they are
+        let body = match func.arguments.get("body") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(body)) => Some(body.clone().into_bytes()),
+            Some(body) => {
+                if !headers.contains_key(CONTENT_TYPE) {
+                    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                }
+                Some(body.to_string().into_bytes())
+            }
+        };
+
+        let start = self.start(func)?;
+        let response = self.send(Request {
+            method,
+            url: self.url(func)?,
+            headers,
+            body,
+        })?;
+
+        let response_headers: Map<String, Value> = response
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), json!(value.to_str().ok()?))))
+            .collect();
+        let text = String::from_utf8_lossy(&response.body);
+        let (content, next) = page(&text, start);
+
+        // JSON bodies are returned as JSON when they are complete
+        let body = match serde_json::from_str::<Value>(&text) {
+            Ok(value)
+                if response.content_type().contains("json") && next.is_none() && start == 0 =>
+            {
+                value
+            }
+            _ => json!(content),
+        };
+
+        let mut result = json!({
+            "url": response.url.as_str(),
+            "status": response.status,
+            "headers": response_headers,
+            "body": body,
+        });
+        if let Some(next) = next {
+            result["next_start"] = json!(next);
+        }
+        if response.truncated {
+            result["note"] = json!(format!(
+                "Only the first {} bytes of the response were downloaded",
+                self.config.max_bytes
+            ));
+        }
+
+        Ok(result)
+    }
+}
+
+impl Module for Http {
+    fn name(&self) -> &'static str {
+        Http::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Reads web pages as markdown and calls web APIs."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **http**: Reads web pages and calls web APIs.
+  - **Rules**:
+    - Use `fetch` to read a web page, it is returned as markdown. Only fetch URLs the user gave or that you found in a page, never make them up.
+    - Long pages are cut, call `fetch` again with the returned `next_start` to read more.
+    - Use `http_request` for JSON APIs, with `method`, `headers` and `body`.
+    - Say so when a URL can't be reached, don't guess its content."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        match func.name.as_str() {
+            "fetch" => self.fetch(func),
+            "http_request" => self.request(func),
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn mutating_tools(&self) -> &'static [&'static str] {
+        &["http_request"]
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        // Nothing to offer without outbound access
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let start = json!({ "type": "integer", "description": "Character to start from, to read past the end of a long result" });
+
+        vec![
+            tool(
+                "fetch",
+                "Reads a web page or text document and returns it as markdown",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string", "description": "The URL, e.g. 'https://docs.rs/serde'" },
+                        "start": start
+                    },
+                    "required": ["url"]
+                }),
+            ),
+            tool(
+                "http_request",
+                "Sends an HTTP request, e.g. to a JSON API, and returns the status, headers and body",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "method": { "type": "string", "enum": ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"], "description": "GET by default" },
+                        "url": { "type": "string", "description": "The URL" },
+                        "headers": { "type": "object", "description": "Request headers, e.g. {\"Accept\": \"application/json\"}" },
+                        "body": { "description": "Request body, objects and arrays are sent as JSON" },
+                        "start": start
+                    },
+                    "required": ["url"]
+                }),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    /// Answers requests on a local port, one connection per response, and
+    /// sends back the requests it received
+    fn serve(responses: Vec<String>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8_lossy(&body));
+                requests.push(request);
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        (address, handle)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            headers
+                .iter()
+                .map(|h| format!("{}\r\n", h))
+                .collect::<String>(),
+            body.len(),
+            body
+        )
+    }
+
+    fn local_config() -> HttpConfig {
+        HttpConfig {
+            allow_private: true,
+            ..HttpConfig::default()
+        }
+    }
+
+    fn call(http: &Http, name: &str, arguments: Value) -> ModuleResult<Value> {
+        http.run(&ToolCallFunction {
+            name: name.to_string(),
+            module: Http::name().to_string(),
+            arguments,
+        })
+    }
+
+    #[test]
+    fn test_fetch() {
+        let (address, server) = serve(vec![
+            response("302 Found", &["Location: /page"], ""),
+            response(
+                "200 OK",
+                &["Content-Type: text/html; charset=utf-8"],
+                "<html><head><title>Hello</title></head><body><h1>Hi</h1><p>See <a href=\"/more\">more</a></p></body></html>",
+            ),
+        ]);
+        let http = Http::with_config(local_config());
+
+        let page = call(
+            &http,
+            "fetch",
+            json!({ "url": format!("{}/start", address) }),
+        )
+        .unwrap();
+        assert_eq!(page["url"], json!(format!("{}/page", address)));
+        assert_eq!(page["status"], json!(200));
+        assert_eq!(page["title"], json!("Hello"));
+        assert_eq!(
+            page["content"],
+            json!(format!("# Hi\n\nSee [more]({}/more)", address))
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /start HTTP/1.1"));
+        assert!(requests[1].starts_with("GET /page HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_fetch_limits() {
+        let long = "x".repeat(MAX_CONTENT_CHARS + 10);
+        let (address, server) = serve(vec![
+            response("200 OK", &["Content-Type: text/plain"], &long),
+            response("200 OK", &["Content-Type: text/plain"], &long),
+            response("301 Moved", &["Location: /loop"], ""),
+            response("301 Moved", &["Location: /loop"], ""),
+        ]);
+        let http = Http::with_config(HttpConfig {
+            max_redirects: 1,
+            ..local_config()
+        });
+
+        let first = call(&http, "fetch", json!({ "url": &address })).unwrap();
+        assert_eq!(first["next_start"], json!(MAX_CONTENT_CHARS));
+        let rest = call(
+            &http,
+            "fetch",
+            json!({ "url": &address, "start": MAX_CONTENT_CHARS }),
+        )
+        .unwrap();
+        assert_eq!(rest["content"], json!("x".repeat(10)));
+        assert!(rest.get("next_start").is_none());
+
+        let error = call(
+            &http,
+            "fetch",
+            json!({ "url": format!("{}/loop", address) }),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("more than 1 redirects"), "{}", error);
+        server.join().unwrap();
+
+        // Local addresses, domains outside the allow-list and disabled access
+        let default = Http::with_config(HttpConfig::default());
+        assert!(call(&default, "fetch", json!({ "url": &address })).is_err());
+        let listed = Http::with_config(HttpConfig {
+            allowed_domains: vec!["example.com".to_string()],
+            ..local_config()
+        });
+        assert!(call(&listed, "fetch", json!({ "url": &address })).is_err());
+        let disabled = Http::with_config(HttpConfig::disabled());
+        assert!(disabled.tools().is_empty());
+        assert!(call(&disabled, "fetch", json!({ "url": &address })).is_err());
+    }
+
+    #[test]
+    fn test_http_request() {
+        let (address, server) = serve(vec![response(
+            "201 Created",
+            &["Content-Type: application/json"],
+            r#"{"id": 7, "name": "Ada"}"#,
+        )]);
+        let http = Http::with_config(local_config());
+
+        let result = call(
+            &http,
+            "http_request",
+            json!({
+                "method": "post",
+                "url": format!("{}/users", address),
+                "headers": { "Authorization": "Bearer token" },
+                "body": { "name": "Ada" }
+            }),
+        )
+        .unwrap();
+        assert_eq!(result["status"], json!(201));
+        assert_eq!(result["body"], json!({ "id": 7, "name": "Ada" }));
+        assert_eq!(result["headers"]["content-type"], json!("application/json"));
+
+        let request = server.join().unwrap().remove(0);
+        assert!(request.starts_with("POST /users HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer token"));
+        assert!(request.contains("content-type: application/json"));
+        assert!(request.ends_with(r#"{"name":"Ada"}"#));
+
+        assert!(
+            call(
+                &http,
+                "http_request",
+                json!({ "method": "TRACE", "url": &address })
+            )
+            .is_err()
+        );
+        assert!(
+            call(
+                &http,
+                "http_request",
+                json!({ "url": "file:///etc/passwd" })
+            )
+            .is_err()
+        );
+        assert_eq!(http.mutating_tools(), &["http_request"]);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::modules::{ModuleError, ModuleResult};
+use num::{BigInt, BigRational, One, Signed, ToPrimitive, Zero};
+use serde_json::json;
+
+/// Largest exponent accepted by `^`, bigger powers would take forever to print
+const MAX_EXPONENT: u32 = 10_000;
+
+/// Largest number accepted by `!`
+const MAX_FACTORIAL: u32 = 2_000;
+
+/// Largest result of `^` in bits, about 30,000 digits
+const MAX_RESULT_BITS: u64 = 100_000;
+
+pub type Rational = BigRational;
+
+/// Parses a decimal or a fraction exactly, e.g. `2`, `-0.125`, `1.5e3` or `3/4`.
+/// Scientific exponents are limited to `MAX_EXPONENT`.
+pub fn parse_rational(text: &str) -> Option<Rational> {
+    let text = text.trim();
+
+    if let Some((numer, denom)) = text.split_once('/') {
+        let denom = parse_rational(denom)?;
+        if denom.is_zero() {
+            return None;
+        }
+        return Some(parse_rational(numer)? / denom);
+    }
+
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (
+            mantissa,
+            exponent
+                .parse::<i32>()
+                .ok()
+                .filter(|e| e.unsigned_abs() <= MAX_EXPONENT)?,
+        ),
+        None => (text, 0),
+    };
+
+    let (sign, digits) = match mantissa.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let numer: BigInt = format!("{}{}", whole, fraction).parse().ok()?;
+    let scale = exponent
+        .checked_sub(i32::try_from(fraction.len()).ok()?)
+        .filter(|s| s.unsigned_abs() <= 2 * MAX_EXPONENT)?;
+    let ten = Rational::from_integer(BigInt::from(10));
+
+    Some(Rational::from_integer(numer * sign) * pow(&ten, scale))
+}
+
+/// Parses a JSON number or a numeric string exactly
+pub fn rational_from_json(value: &serde_json::Value) -> ModuleResult<Rational> {
+    let text = match value {
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        _ => {
+            return Err(ModuleError::InvalidFunctionInput(format!(
+                "Expected a number, got {}",
+                value
+            )));
+        }
+    };
+
+    parse_rational(&text)
+        .ok_or_else(|| ModuleError::InvalidFunctionInput(format!("Invalid number {}", text)))
+}
+
+fn pow(base: &Rational, exponent: i32) -> Rational {
+    let result = num::pow(base.clone(), exponent.unsigned_abs() as usize);
+    if exponent < 0 { result.recip() } else { result }
+}
+
+pub fn to_f64(value: &Rational) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Decimal expansion with at most `digits` fractional digits, ending with `...`
+/// when it had to be cut
+pub fn format_decimal(value: &Rational, digits: usize) -> String {
+    let sign = if value.is_negative() { "-" } else { "" };
+    let value = value.abs();
+
+    let whole = value.to_integer();
+    let denom = value.denom().clone();
+    let mut remainder = value.numer() - &whole * &denom;
+
+    let mut fraction = String::new();
+    while !remainder.is_zero() && fraction.len() < digits {
+        remainder *= 10;
+        let digit = &remainder / &denom;
+        remainder -= &digit * &denom;
+        fraction.push_str(&digit.to_string());
+    }
+
+    match (fraction.is_empty(), remainder.is_zero()) {
+        (true, true) => format!("{}{}", sign, whole),
+        (_, true) => format!("{}{}.{}", sign, whole, fraction),
+        (_, false) => format!("{}{}.{}...", sign, whole, fraction),
+    }
+}
+
+/// Exact and decimal forms of a number, e.g. `{"exact": "1/3", "decimal": 0.333...}`
+pub fn number_json(value: &Rational) -> serde_json::Value {
+    json!({ "exact": value.to_string(), "decimal": to_f64(value) })
+}
+
+/// Evaluates an arithmetic expression with exact rational numbers. Supports
+/// `+ - * /`, `^` with an integer exponent, `!`, and parentheses.
+pub fn evaluate(expression: &str) -> ModuleResult<Rational> {
+    let mut parser = Parser {
+        chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        position: 0,
+    };
+
+    let value = parser.expression()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(parser.error(&format!("Unexpected '{}'", c))),
+    }
+}
+
+/// Recursive descent parser, evaluating while it parses
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> ModuleError {
+        ModuleError::InvalidFunctionInput(format!("{} at position {}", message, self.position + 1))
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> ModuleResult<Rational> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> ModuleResult<Rational> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor.is_zero() {
+                    return Err(ModuleError::ExecutionError("Division by zero".into()));
+                }
+                value /= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> ModuleResult<Rational> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    // power := postfix ('^' unary)?
+    fn power(&mut self) -> ModuleResult<Rational> {
+        let base = self.postfix()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+
+        let exponent = self.unary()?;
+        let exponent = exponent
+            .to_integer()
+            .to_i32()
+            .filter(|e| exponent.is_integer() && e.unsigned_abs() <= MAX_EXPONENT)
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!(
+                    "Exponents must be integers up to {}, use eval for {}",
+                    MAX_EXPONENT, exponent
+                ))
+            })?;
+
+        if base.is_zero() && exponent < 0 {
+            return Err(ModuleError::ExecutionError("Division by zero".into()));
+        }
+
+        let bits = base.numer().bits().max(base.denom().bits());
+        if bits.saturating_mul(exponent.unsigned_abs() as u64) > MAX_RESULT_BITS {
+            return Err(ModuleError::InvalidFunctionInput(format!(
+                "The result of ^ would have more than {} bits",
+                MAX_RESULT_BITS
+            )));
+        }
+
+        Ok(pow(&base, exponent))
+    }
+
+    // postfix := atom '!'*
+    fn postfix(&mut self) -> ModuleResult<Rational> {
+        let mut value = self.atom()?;
+        while self.eat('!') {
+            let n = value
+                .to_integer()
+                .to_u32()
+                .filter(|n| value.is_integer() && *n <= MAX_FACTORIAL)
+                .ok_or_else(|| {
+                    ModuleError::InvalidFunctionInput(format!(
+                        "Factorials need an integer between 0 and {}",
+                        MAX_FACTORIAL
+                    ))
+                })?;
+
+            let factorial = (1..=n).fold(BigInt::one(), |acc, i| acc * i);
+            value = Rational::from_integer(factorial);
+        }
+        Ok(value)
+    }
+
+    // atom := number | '(' expression ')'
+    fn atom(&mut self) -> ModuleResult<Rational> {
+        if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err(self.error("Missing ')'"));
+            }
+            return Ok(value);
+        }
+
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            let exponent_sign = matches!(c, '+' | '-')
+                && matches!(
+                    self.chars.get(self.position.wrapping_sub(1)),
+                    Some('e' | 'E')
+                );
+            if c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E') || exponent_sign {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        let literal: String = self.chars[start..self.position].iter().collect();
+        if literal.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => self.error(&format!("Unexpected '{}'", c)),
+                None => self.error("Unexpected end of expression"),
+            });
+        }
+
+        parse_rational(&literal).ok_or_else(|| {
+            self.position = start;
+            self.error(&format!("Invalid number {}", literal))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exact(expression: &str) -> String {
+        evaluate(expression).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_rational() {
+        assert_eq!(parse_rational("0.125").unwrap().to_string(), "1/8");
+        assert_eq!(parse_rational("-1.5e3").unwrap().to_string(), "-1500");
+        assert_eq!(parse_rational("3/4").unwrap().to_string(), "3/4");
+        assert!(parse_rational("1/0").is_none());
+        assert!(parse_rational("abc").is_none());
+        // Huge exponents are refused instead of overflowing or hanging
+        assert!(parse_rational("1.5e-2147483648").is_none());
+        assert!(parse_rational("1e99999999").is_none());
+        assert!(rational_from_json(&json!("1e999999999")).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_exact() {
+        assert_eq!(exact("2 * 5 / 4"), "5/2");
+        assert_eq!(exact("0.1 + 0.2"), "3/10");
+        assert_eq!(exact("-2^2 + (1/3)^-2"), "5");
+        assert_eq!(exact("2^100"), "1267650600228229401496703205376");
+        assert_eq!(exact("20!/18!"), "380");
+        assert_eq!(exact("1e-3 * 4"), "1/250");
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert!(evaluate("1 / (2 - 2)").is_err());
+        assert!(evaluate("2 ^ 0.5").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("1 + x").is_err());
+        assert!(evaluate("1.5e-2147483648").is_err());
+        assert!(evaluate("1e99999999").is_err());
+        assert!(evaluate("((2^10000)^10000)^10000").is_err());
+        assert!(evaluate("2^10000").is_ok());
+    }
+
+    #[test]
+    fn test_format_decimal() {
+        assert_eq!(format_decimal(&parse_rational("1/8").unwrap(), 20), "0.125");
+        assert_eq!(
+            format_decimal(&parse_rational("-2/3").unwrap(), 5),
+            "-0.66666..."
+        );
+        assert_eq!(format_decimal(&parse_rational("42").unwrap(), 5), "42");
+    }
+}
@@ -0,0 +1,288 @@
+use super::exact::{Rational, number_json, rational_from_json};
+use crate::modules::{ModuleError, ModuleResult};
+use num::{One, Zero};
+
+/// Largest number of rows or columns accepted
+const MAX_SIZE: usize = 64;
+
+pub type Matrix = Vec<Vec<Rational>>;
+
+/// Parses a JSON array of rows, e.g. `[[1, 2], [3, "1/2"]]`
+pub fn matrix_from_json(value: &serde_json::Value) -> ModuleResult<Matrix> {
+    let invalid = || {
+        ModuleError::InvalidFunctionInput("Expected a matrix as an array of rows of numbers".into())
+    };
+
+    let rows = value.as_array().ok_or_else(invalid)?;
+    let matrix = rows
+        .iter()
+        .map(|row| {
+            row.as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(rational_from_json)
+                .collect::<ModuleResult<Vec<_>>>()
+        })
+        .collect::<ModuleResult<Matrix>>()?;
+
+    let columns = matrix.first().map(Vec::len).unwrap_or_default();
+    if columns == 0 || matrix.iter().any(|row| row.len() != columns) {
+        return Err(ModuleError::InvalidFunctionInput(
+            "Matrix rows must be non-empty and of the same length".into(),
+        ));
+    }
+
+    if matrix.len() > MAX_SIZE || columns > MAX_SIZE {
+        return Err(ModuleError::InvalidFunctionInput(format!(
+            "Matrices are limited to {}x{}",
+            MAX_SIZE, MAX_SIZE
+        )));
+    }
+
+    Ok(matrix)
+}
+
+/// Exact form of a matrix, rows of strings like `"1/2"`
+pub fn exact_json(matrix: &Matrix) -> serde_json::Value {
+    matrix
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Decimal form of a matrix
+pub fn decimal_json(matrix: &Matrix) -> serde_json::Value {
+    matrix
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|v| number_json(v)["decimal"].clone())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn size(matrix: &Matrix) -> (usize, usize) {
+    (matrix.len(), matrix[0].len())
+}
+
+fn size_error(operation: &str, a: &Matrix, b: &Matrix) -> ModuleError {
+    let (ar, ac) = size(a);
+    let (br, bc) = size(b);
+    ModuleError::InvalidFunctionInput(format!(
+        "Cannot {} a {}x{} and a {}x{} matrix",
+        operation, ar, ac, br, bc
+    ))
+}
+
+fn require_square(matrix: &Matrix) -> ModuleResult<()> {
+    let (rows, columns) = size(matrix);
+    if rows != columns {
+        return Err(ModuleError::InvalidFunctionInput(format!(
+            "Expected a square matrix, got {}x{}",
+            rows, columns
+        )));
+    }
+    Ok(())
+}
+
+pub fn add(a: &Matrix, b: &Matrix) -> ModuleResult<Matrix> {
+    if size(a) != size(b) {
+        return Err(size_error("add", a, b));
+    }
+
+    Ok(a.iter()
+        .zip(b)
+        .map(|(x, y)| x.iter().zip(y).map(|(x, y)| x + y).collect())
+        .collect())
+}
+
+pub fn subtract(a: &Matrix, b: &Matrix) -> ModuleResult<Matrix> {
+    if size(a) != size(b) {
+        return Err(size_error("subtract", a, b));
+    }
+
+    Ok(a.iter()
+        .zip(b)
+        .map(|(x, y)| x.iter().zip(y).map(|(x, y)| x - y).collect())
+        .collect())
+}
+
+pub fn multiply(a: &Matrix, b: &Matrix) -> ModuleResult<Matrix> {
+    let (rows, inner) = size(a);
+    let (b_rows, columns) = size(b);
+    if inner != b_rows {
+        return Err(size_error("multiply", a, b));
+    }
+
+    Ok((0..rows)
+        .map(|i| {
+            (0..columns)
+                .map(|j| (0..inner).map(|k| &a[i][k] * &b[k][j]).sum())
+                .collect()
+        })
+        .collect())
+}
+
+pub fn transpose(a: &Matrix) -> Matrix {
+    let (rows, columns) = size(a);
+    (0..columns)
+        .map(|j| (0..rows).map(|i| a[i][j].clone()).collect())
+        .collect()
+}
+
+/// Reduces the augmented matrix `[a | b]` to reduced row echelon form with
+/// Gauss-Jordan elimination. Returns the determinant of `a`, zero when it is
+/// singular, in which case `b` is left partially reduced.
+fn gauss_jordan(a: &mut Matrix, b: &mut Matrix) -> Rational {
+    let n = a.len();
+    let mut determinant = Rational::one();
+
+    for column in 0..n {
+        let Some(pivot) = (column..n).find(|&row| !a[row][column].is_zero()) else {
+            return Rational::zero();
+        };
+
+        if pivot != column {
+            a.swap(pivot, column);
+            b.swap(pivot, column);
+            determinant = -determinant;
+        }
+
+        let factor = a[column][column].clone();
+        determinant *= &factor;
+        for value in a[column].iter_mut().chain(b[column].iter_mut()) {
+            *value /= &factor;
+        }
+
+        for row in 0..n {
+            if row == column || a[row][column].is_zero() {
+                continue;
+            }
+
+            let factor = a[row][column].clone();
+            let (pivot_a, pivot_b) = (a[column].clone(), b[column].clone());
+            for (value, pivot) in a[row].iter_mut().zip(&pivot_a) {
+                *value -= &factor * pivot;
+            }
+            for (value, pivot) in b[row].iter_mut().zip(&pivot_b) {
+                *value -= &factor * pivot;
+            }
+        }
+    }
+
+    determinant
+}
+
+fn identity(n: usize) -> Matrix {
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    if i == j {
+                        Rational::one()
+                    } else {
+                        Rational::zero()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn determinant(a: &Matrix) -> ModuleResult<Rational> {
+    require_square(a)?;
+
+    let mut empty = vec![Vec::new(); a.len()];
+    Ok(gauss_jordan(&mut a.clone(), &mut empty))
+}
+
+pub fn inverse(a: &Matrix) -> ModuleResult<Matrix> {
+    require_square(a)?;
+
+    let mut inverse = identity(a.len());
+    if gauss_jordan(&mut a.clone(), &mut inverse).is_zero() {
+        return Err(ModuleError::ExecutionError(
+            "The matrix is singular and has no inverse".into(),
+        ));
+    }
+
+    Ok(inverse)
+}
+
+/// Solves `a x = b` for `x`, given the coefficients and the constants
+pub fn solve(a: &Matrix, b: &[Rational]) -> ModuleResult<Vec<Rational>> {
+    require_square(a)?;
+    if a.len() != b.len() {
+        return Err(ModuleError::InvalidFunctionInput(format!(
+            "Expected {} constants, one per equation, got {}",
+            a.len(),
+            b.len()
+        )));
+    }
+
+    let mut constants: Matrix = b.iter().map(|v| vec![v.clone()]).collect();
+    if gauss_jordan(&mut a.clone(), &mut constants).is_zero() {
+        return Err(ModuleError::ExecutionError(
+            "The system has no unique solution".into(),
+        ));
+    }
+
+    Ok(constants.into_iter().map(|mut row| row.remove(0)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn matrix(value: serde_json::Value) -> Matrix {
+        matrix_from_json(&value).unwrap()
+    }
+
+    #[test]
+    fn test_operations() {
+        let a = matrix(json!([[1, 2], [3, 4]]));
+        let b = matrix(json!([[0, 1], [1, 0]]));
+
+        assert_eq!(
+            exact_json(&multiply(&a, &b).unwrap()),
+            json!([["2", "1"], ["4", "3"]])
+        );
+        assert_eq!(
+            exact_json(&add(&a, &b).unwrap()),
+            json!([["1", "3"], ["4", "4"]])
+        );
+        assert_eq!(exact_json(&transpose(&a)), json!([["1", "3"], ["2", "4"]]));
+        assert_eq!(determinant(&a).unwrap().to_string(), "-2");
+        assert_eq!(
+            exact_json(&inverse(&a).unwrap()),
+            json!([["-2", "1"], ["3/2", "-1/2"]])
+        );
+        assert!(multiply(&a, &matrix(json!([[1, 2, 3]]))).is_err());
+    }
+
+    #[test]
+    fn test_solve() {
+        // 2x + y = 5, x - 3y = "1/2"
+        let a = matrix(json!([[2, 1], [1, -3]]));
+        let b = [Rational::from_integer(5.into()), "1/2".parse().unwrap()];
+
+        let x = solve(&a, &b).unwrap();
+        assert_eq!(x[0].to_string(), "31/14");
+        assert_eq!(x[1].to_string(), "4/7");
+
+        let singular = matrix(json!([[1, 2], [2, 4]]));
+        assert!(solve(&singular, &b).is_err());
+        assert!(inverse(&singular).is_err());
+    }
+
+    #[test]
+    fn test_invalid_matrix() {
+        assert!(matrix_from_json(&json!([[1, 2], [3]])).is_err());
+        assert!(matrix_from_json(&json!([])).is_err());
+        assert!(matrix_from_json(&json!([["x"]])).is_err());
+    }
+}
@@ -0,0 +1,769 @@
+mod exact;
+mod matrix;
+mod stats;
+mod units;
+mod variables;
+
+use super::{Module, ModuleError, ModuleResult, ToolCallFunction};
+use evalexpr::Value;
+use exact::{Rational, format_decimal, number_json, rational_from_json, to_f64};
+use serde_json::json;
+use std::sync::Mutex;
+use variables::MathState;
+
+/// Default number of fractional digits in the decimal form of `exact`
+const DEFAULT_PRECISION: usize = 30;
+
+/// Largest number of fractional digits `exact` will print
+const MAX_PRECISION: usize = 1_000;
+
+fn value_to_json(val: Value) -> serde_json::Value {
+    match val {
+        Value::Int(i) => json!(i),
+        Value::Float(f) => json!(f),
+        Value::String(s) => json!(s),
+        Value::Boolean(b) => json!(b),
+        Value::Tuple(t) => serde_json::Value::Array(t.into_iter().map(value_to_json).collect()),
+        Value::Empty => serde_json::Value::Null,
+    }
+}
+
+fn json_to_value(json: &serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(s) => Value::String(s.clone()),
+        serde_json::Value::Bool(b) => Value::Boolean(*b),
+        serde_json::Value::Array(a) => Value::Tuple(a.iter().map(json_to_value).collect()),
+        serde_json::Value::Null | serde_json::Value::Object(_) => Value::Empty,
+    }
+}
+
+pub struct Math {
+    /// Variables and functions of the session
+    state: Mutex<MathState>,
+}
+
+impl Math {
+    pub fn new() -> Math {
+        Math {
+            state: Mutex::new(MathState::default()),
+        }
+    }
+
+    pub fn name() -> &'static str {
+        "math"
+    }
+
+    fn eval(&self, expression: &str) -> ModuleResult<serde_json::Value> {
+        let result = self.state.lock().unwrap().eval(expression)?;
+
+        let json_result = value_to_json(result);
+        Ok(json_result)
+    }
+
+    fn pow(&self, base: f64, exponent: f64) -> ModuleResult<serde_json::Value> {
+        let result = base.powf(exponent);
+        self.state.lock().unwrap().set_answer(result)?;
+        Ok(json!(result))
+    }
+
+    fn sqrt(&self, value: f64) -> ModuleResult<serde_json::Value> {
+        let result = value.sqrt();
+        self.state.lock().unwrap().set_answer(result)?;
+        Ok(json!(result))
+    }
+
+    fn set_variable(
+        &self,
+        name: &str,
+        value: &serde_json::Value,
+    ) -> ModuleResult<serde_json::Value> {
+        let mut state = self.state.lock().unwrap();
+        let value = match value {
+            serde_json::Value::String(expression) => state.value_of(expression)?,
+            serde_json::Value::Number(_) | serde_json::Value::Bool(_) => json_to_value(value),
+            _ => {
+                return Err(ModuleError::InvalidFunctionInput(
+                    "Expected a number or an expression for 'value'".into(),
+                ));
+            }
+        };
+
+        state.set_variable(name, value.clone())?;
+        Ok(json!({ "name": name, "value": value_to_json(value) }))
+    }
+
+    fn define_function(
+        &self,
+        name: &str,
+        parameters: &[String],
+        body: &str,
+    ) -> ModuleResult<serde_json::Value> {
+        let function = self
+            .state
+            .lock()
+            .unwrap()
+            .define_function(name, parameters, body)?;
+        Ok(json!({ "defined": function.signature() }))
+    }
+
+    fn list_variables(&self) -> ModuleResult<serde_json::Value> {
+        let state = self.state.lock().unwrap();
+        let variables: serde_json::Map<String, serde_json::Value> = state
+            .variables()
+            .into_iter()
+            .map(|(name, value)| (name, value_to_json(value)))
+            .collect();
+        let functions: Vec<String> = state.functions().iter().map(|f| f.signature()).collect();
+
+        Ok(json!({ "variables": variables, "functions": functions }))
+    }
+
+    fn exact(&self, expression: &str, precision: usize) -> ModuleResult<serde_json::Value> {
+        let result = exact::evaluate(expression)?;
+        self.state.lock().unwrap().set_answer(to_f64(&result))?;
+        Ok(json!({
+            "exact": result.to_string(),
+            "decimal": format_decimal(&result, precision.min(MAX_PRECISION)),
+        }))
+    }
+
+    fn convert(&self, value: &Rational, from: &str, to: &str) -> ModuleResult<serde_json::Value> {
+        let result = units::convert(value, from, to)?;
+        Ok(json!({ "value": number_json(&result), "unit": to }))
+    }
+
+    fn matrix(
+        &self,
+        operation: &str,
+        a: &matrix::Matrix,
+        b: Option<&matrix::Matrix>,
+    ) -> ModuleResult<serde_json::Value> {
+        let other = || {
+            b.ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!(
+                    "'{}' needs a second matrix 'b'",
+                    operation
+                ))
+            })
+        };
+
+        let result = match operation {
+            "add" => matrix::add(a, other()?)?,
+            "subtract" => matrix::subtract(a, other()?)?,
+            "multiply" => matrix::multiply(a, other()?)?,
+            "transpose" => matrix::transpose(a),
+            "inverse" => matrix::inverse(a)?,
+            "determinant" => return Ok(number_json(&matrix::determinant(a)?)),
+            _ => {
+                return Err(ModuleError::InvalidFunctionInput(format!(
+                    "Unknown matrix operation {}",
+                    operation
+                )));
+            }
+        };
+
+        Ok(json!({
+            "exact": matrix::exact_json(&result),
+            "decimal": matrix::decimal_json(&result),
+        }))
+    }
+
+    fn solve_linear(&self, a: &matrix::Matrix, b: &[Rational]) -> ModuleResult<serde_json::Value> {
+        let solution = matrix::solve(a, b)?;
+        Ok(json!({ "solution": solution.iter().map(number_json).collect::<Vec<_>>() }))
+    }
+
+    fn argument<'a>(
+        &self,
+        func: &'a ToolCallFunction,
+        name: &str,
+    ) -> ModuleResult<&'a serde_json::Value> {
+        func.arguments.get(name).ok_or_else(|| {
+            ModuleError::InvalidFunctionInput(format!("Missing '{}' argument", name))
+        })
+    }
+
+    fn string_argument<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<&'a str> {
+        self.argument(func, name)?.as_str().ok_or_else(|| {
+            ModuleError::InvalidFunctionInput(format!("Expected a string for '{}'", name))
+        })
+    }
+
+    fn numbers_argument(&self, func: &ToolCallFunction, name: &str) -> ModuleResult<Vec<Rational>> {
+        self.argument(func, name)?
+            .as_array()
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!(
+                    "Expected an array of numbers for '{}'",
+                    name
+                ))
+            })?
+            .iter()
+            .map(rational_from_json)
+            .collect()
+    }
+
+    fn parse_float(&self, value_json: &serde_json::Value) -> ModuleResult<f64> {
+        match value_json {
+            serde_json::Value::Number(n) => n.as_f64().ok_or_else(|| {
+                ModuleError::InvalidFunctionInput("Value is not a valid number".into())
+            }),
+            serde_json::Value::String(s) => s.parse().map_err(|_| {
+                ModuleError::InvalidFunctionInput("Value string is not a valid number".into())
+            }),
+            _ => Err(ModuleError::InvalidFunctionInput(
+                "Expected a number or a string for 'value'".into(),
+            )),
+        }
+    }
+}
+
+impl Module for Math {
+    fn name(&self) -> &'static str {
+        Math::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Allows you to perform mathematical operations, including exact arithmetic, unit conversion, statistics and matrices."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **math**: Allows you to perform mathematical operations, including exact arithmetic, unit conversion, statistics and matrices.
+  - **Rules**:
+    - Never do arithmetic yourself, always use a tool and report its result.
+    - Use the `exact` function for arithmetic with fractions, large numbers or factorials (e.g., `1/3 + 2^100`, `20!/18!`). It returns an exact fraction and its decimal expansion.
+    - Use the `eval` function for expressions with functions such as `math::sin(1.2)` or non-integer exponents. It can assign variables (e.g., `price = 12.5; price * 3`).
+    - `ans` always holds the last result, use it to continue a calculation (e.g., `ans * 1.2`).
+    - Use `set_variable` and `define_function` (e.g., `area(w, h) = w * h`) for values and formulas you will reuse, they are kept for the whole session. Use `list_variables` to see them.
+    - Use the `pow` function for exponents (e.g., `pow(5, 3)`).
+    - Use the `sqrt` function for square roots (e.g., `sqrt(81)`).
+    - Use the `convert` function to convert between units (e.g., 3 `mi` to `km`, 70 `F` to `C`).
+    - Use the `statistics` function for the mean, median, mode, variance and standard deviation of a list of numbers.
+    - Use the `matrix` function to add, subtract, multiply, transpose, invert or get the determinant of matrices.
+    - Use the `solve_linear` function to solve systems of linear equations.
+    - Numbers may be given as strings to keep them exact (e.g., `"0.1"` or `"1/3"`)."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<serde_json::Value> {
+        // In `run` function
+        match func.name.as_str() {
+            "eval" => {
+                let expression = func
+                    .arguments
+                    .get("expression")
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Missing 'expression' argument".into())
+                    })?
+                    .as_str()
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Expected string expression".into())
+                    })?;
+
+                self.eval(expression)
+            }
+            "pow" => {
+                let base_json = func
+                    .arguments
+                    .get("base")
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Missing 'base' argument".into())
+                    })?
+                    .clone();
+
+                let exponent_json = func
+                    .arguments
+                    .get("exponent")
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Missing 'exponent' argument".into())
+                    })?
+                    .clone();
+
+                let base: f64 = self.parse_float(&base_json)?;
+                let exponent: f64 = self.parse_float(&exponent_json)?;
+
+                self.pow(base, exponent)
+            }
+            "sqrt" => {
+                let value_json = func
+                    .arguments
+                    .get("value")
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Missing 'value' argument".into())
+                    })?
+                    .clone();
+
+                let value: f64 = self.parse_float(&value_json)?;
+
+                self.sqrt(value)
+            }
+            "exact" => {
+                let expression = self.string_argument(func, "expression")?;
+                let precision = func
+                    .arguments
+                    .get("precision")
+                    .and_then(|v| v.as_u64())
+                    .map(|p| p as usize)
+                    .unwrap_or(DEFAULT_PRECISION);
+
+                self.exact(expression, precision)
+            }
+            "convert" => {
+                let value = rational_from_json(self.argument(func, "value")?)?;
+                let from = self.string_argument(func, "from")?;
+                let to = self.string_argument(func, "to")?;
+
+                self.convert(&value, from, to)
+            }
+            "statistics" => {
+                let values = self.numbers_argument(func, "values")?;
+
+                stats::statistics(&values)
+            }
+            "matrix" => {
+                let operation = self.string_argument(func, "operation")?;
+                let a = matrix::matrix_from_json(self.argument(func, "a")?)?;
+                let b = func
+                    .arguments
+                    .get("b")
+                    .map(matrix::matrix_from_json)
+                    .transpose()?;
+
+                self.matrix(operation, &a, b.as_ref())
+            }
+            "solve_linear" => {
+                let a = matrix::matrix_from_json(self.argument(func, "coefficients")?)?;
+                let b = self.numbers_argument(func, "constants")?;
+
+                self.solve_linear(&a, &b)
+            }
+            "set_variable" => {
+                let name = self.string_argument(func, "name")?;
+                let value = self.argument(func, "value")?;
+
+                self.set_variable(name, value)
+            }
+            "define_function" => {
+                let name = self.string_argument(func, "name")?;
+                let body = self.string_argument(func, "body")?;
+                let parameters: Vec<String> = func
+                    .arguments
+                    .get("parameters")
+                    .and_then(|v| v.as_array())
+                    .map(|p| {
+                        p.iter()
+                            .filter_map(|p| p.as_str())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                self.define_function(name, &parameters, body)
+            }
+            "list_variables" => self.list_variables(),
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn state(&self) -> Option<serde_json::Value> {
+        let state = self.state.lock().unwrap();
+        (!state.is_empty()).then(|| state.to_json())
+    }
+
+    fn restore_state(&self, state: Option<&serde_json::Value>) -> ModuleResult<()> {
+        let restored = match state {
+            Some(state) => MathState::from_json(state)?,
+            None => MathState::default(),
+        };
+
+        *self.state.lock().unwrap() = restored;
+        Ok(())
+    }
+
+    fn tools(&self) -> Vec<super::Tool> {
+        vec![
+            // Eval function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "eval".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Evaluate a mathematical expression".to_string(),
+                    parameters: serde_json::json!({
+                      "type": "object",
+                      "properties": {
+                        "expression": {
+                            "type": "string",
+                            "description": "Mathematical expression to evaluate (e.g., '2.0 + 2.0', '5^3')"
+                        }
+                      },
+                      "required": ["expression"]
+                    }),
+                },
+            },
+            // Pow function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "pow".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Raises a base to the power of an exponent".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "base": { "type": "number", "description": "The base number" },
+                            "exponent": { "type": "number", "description": "The exponent" }
+                        },
+                        "required": ["base", "exponent"]
+                    }),
+                },
+            },
+            // Sqrt function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "sqrt".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Calculates the square root of a number".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "value": { "type": "number", "description": "The number to find the square root of" }
+                        },
+                        "required": ["value"]
+                    }),
+                },
+            },
+            // Exact function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "exact".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Evaluates an arithmetic expression exactly, with fractions and arbitrary precision".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "expression": {
+                                "type": "string",
+                                "description": "Expression with + - * /, integer ^, ! and parentheses (e.g., '1/3 + 0.1', '2^200', '25!')"
+                            },
+                            "precision": { "type": "integer", "description": "Number of decimal digits to show, 30 by default" }
+                        },
+                        "required": ["expression"]
+                    }),
+                },
+            },
+            // Convert function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "convert".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Converts a value between units of length, mass, time, volume, area, speed, energy, pressure, data or temperature".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "value": { "type": ["number", "string"], "description": "The value to convert" },
+                            "from": { "type": "string", "description": "Unit of the value (e.g., 'mi', 'kg', 'F', 'GiB')" },
+                            "to": { "type": "string", "description": "Unit to convert to (e.g., 'km', 'lb', 'C', 'MB')" }
+                        },
+                        "required": ["value", "from", "to"]
+                    }),
+                },
+            },
+            // Statistics function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "statistics".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Computes descriptive statistics of a list of numbers".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "values": { "type": "array", "items": { "type": ["number", "string"] }, "description": "The numbers" }
+                        },
+                        "required": ["values"]
+                    }),
+                },
+            },
+            // Matrix function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "matrix".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Performs an exact matrix operation".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "operation": {
+                                "type": "string",
+                                "enum": ["add", "subtract", "multiply", "transpose", "determinant", "inverse"],
+                                "description": "The operation to perform"
+                            },
+                            "a": { "type": "array", "items": { "type": "array", "items": { "type": ["number", "string"] } }, "description": "The matrix as an array of rows" },
+                            "b": { "type": "array", "items": { "type": "array", "items": { "type": ["number", "string"] } }, "description": "The second matrix, for add, subtract and multiply" }
+                        },
+                        "required": ["operation", "a"]
+                    }),
+                },
+            },
+            // Solve linear function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "solve_linear".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Solves a system of linear equations exactly".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "coefficients": {
+                                "type": "array",
+                                "items": { "type": "array", "items": { "type": ["number", "string"] } },
+                                "description": "Coefficients of each equation (e.g., [[2, 1], [1, -3]] for 2x + y and x - 3y)"
+                            },
+                            "constants": { "type": "array", "items": { "type": ["number", "string"] }, "description": "Right-hand side of each equation (e.g., [5, 0.5])" }
+                        },
+                        "required": ["coefficients", "constants"]
+                    }),
+                },
+            },
+            // Set variable function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "set_variable".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Stores a value in a variable usable in later eval calls".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "description": "Name of the variable (e.g., 'rate')" },
+                            "value": { "type": ["number", "string"], "description": "A number, or an expression to evaluate (e.g., 'ans * 2')" }
+                        },
+                        "required": ["name", "value"]
+                    }),
+                },
+            },
+            // Define function function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "define_function".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Defines a function usable in later eval calls".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "description": "Name of the function (e.g., 'area')" },
+                            "parameters": { "type": "array", "items": { "type": "string" }, "description": "Names of the parameters (e.g., ['w', 'h'])" },
+                            "body": { "type": "string", "description": "Expression computing the result (e.g., 'w * h')" }
+                        },
+                        "required": ["name", "body"]
+                    }),
+                },
+            },
+            // List variables function
+            super::Tool {
+                tool_type: "function".to_string(),
+                function: super::ToolFunction {
+                    name: "list_variables".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Lists the variables and functions defined in this session".to_string(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {}
+                    }),
+                },
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "eval".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "expression": "2000.0 * 2122.0" }),
+            })
+            .unwrap();
+        assert_eq!(result, json!(4244000.0));
+    }
+
+    #[test]
+    fn test_eval_with_division() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "eval".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "expression": "(2000.0 * 2122.0) / (22124.0 * 900.0)" }),
+            })
+            .unwrap();
+        let expected_float = (2000.0 * 2122.0) / (22124.0 * 900.0);
+
+        let actual_float: f64 = serde_json::from_value(result).unwrap();
+
+        println!("Expected: {}, Actual: {}", expected_float, actual_float);
+        let epsilon = 1e-9;
+        assert!((actual_float - expected_float).abs() < epsilon);
+    }
+
+    #[test]
+    fn test_pow_number() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "pow".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "base": 5, "exponent": 3 }),
+            })
+            .unwrap();
+        assert_eq!(result, json!(125.0));
+    }
+
+    #[test]
+    fn test_pow_string() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "pow".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "base": "5", "exponent": "3" }),
+            })
+            .unwrap();
+        assert_eq!(result, json!(125.0));
+    }
+
+    #[test]
+    fn test_sqrt_number() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "sqrt".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "value": 81 }),
+            })
+            .unwrap();
+        assert_eq!(result, json!(9.0));
+    }
+
+    #[test]
+    fn test_sqrt_string() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "sqrt".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "value": "81" }),
+            })
+            .unwrap();
+        assert_eq!(result, json!(9.0));
+    }
+
+    #[test]
+    fn test_exact() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "exact".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "expression": "2 * 5 / 3", "precision": 4 }),
+            })
+            .unwrap();
+        assert_eq!(result, json!({ "exact": "10/3", "decimal": "3.3333..." }));
+    }
+
+    #[test]
+    fn test_convert() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "convert".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "value": "0.5", "from": "kg", "to": "g" }),
+            })
+            .unwrap();
+        assert_eq!(result["value"], json!({ "exact": "500", "decimal": 500.0 }));
+    }
+
+    #[test]
+    fn test_matrix_needs_second_matrix() {
+        let math = Math::new();
+        let result = math.run(&ToolCallFunction {
+            name: "matrix".to_string(),
+            module: Math::name().to_string(),
+            arguments: json!({ "operation": "multiply", "a": [[1, 2], [3, 4]] }),
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_variables_persist_between_calls() {
+        let math = Math::new();
+        let call = |name: &str, arguments: serde_json::Value| {
+            math.run(&ToolCallFunction {
+                name: name.to_string(),
+                module: Math::name().to_string(),
+                arguments,
+            })
+        };
+
+        call("set_variable", json!({ "name": "price", "value": 12.5 })).unwrap();
+        call(
+            "define_function",
+            json!({ "name": "with_tax", "parameters": ["x"], "body": "x * 1.2" }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            call("eval", json!({ "expression": "with_tax(price * 2)" })).unwrap(),
+            json!(30.0)
+        );
+        assert_eq!(
+            call("eval", json!({ "expression": "ans + 1" })).unwrap(),
+            json!(31.0)
+        );
+
+        let state = math.state().unwrap();
+        let restored = Math::new();
+        restored.restore_state(Some(&state)).unwrap();
+        let listed = restored
+            .run(&ToolCallFunction {
+                name: "list_variables".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({}),
+            })
+            .unwrap();
+        assert_eq!(listed["variables"], json!({ "ans": 31.0, "price": 12.5 }));
+        assert_eq!(listed["functions"], json!(["with_tax(x) = x * 1.2"]));
+    }
+
+    #[test]
+    fn test_solve_linear() {
+        let math = Math::new();
+        let result = math
+            .run(&ToolCallFunction {
+                name: "solve_linear".to_string(),
+                module: Math::name().to_string(),
+                arguments: json!({ "coefficients": [[1, 1], [1, -1]], "constants": [3, "0.5"] }),
+            })
+            .unwrap();
+        assert_eq!(result["solution"][0]["exact"], "7/4");
+        assert_eq!(result["solution"][1]["exact"], "5/4");
+    }
+}
@@ -0,0 +1,109 @@
+use super::exact::{Rational, number_json, to_f64};
+use crate::modules::{ModuleError, ModuleResult};
+use serde_json::json;
+
+/// Descriptive statistics of a list of numbers. Everything but the standard
+/// deviations is exact.
+pub fn statistics(values: &[Rational]) -> ModuleResult<serde_json::Value> {
+    if values.is_empty() {
+        return Err(ModuleError::InvalidFunctionInput(
+            "Statistics need at least one value".into(),
+        ));
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort();
+
+    let count = Rational::from_integer(values.len().into());
+    let sum: Rational = values.iter().sum();
+    let mean = &sum / &count;
+
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (&sorted[middle - 1] + &sorted[middle]) / Rational::from_integer(2.into())
+    } else {
+        sorted[middle].clone()
+    };
+
+    let squares: Rational = values.iter().map(|v| (v - &mean) * (v - &mean)).sum();
+    let variance = &squares / &count;
+    let sample_variance =
+        (values.len() > 1).then(|| &squares / (&count - Rational::from_integer(1.into())));
+
+    let min = &sorted[0];
+    let max = &sorted[sorted.len() - 1];
+
+    Ok(json!({
+        "count": values.len(),
+        "sum": number_json(&sum),
+        "mean": number_json(&mean),
+        "median": number_json(&median),
+        "mode": mode(&sorted).iter().map(number_json).collect::<Vec<_>>(),
+        "min": number_json(min),
+        "max": number_json(max),
+        "range": number_json(&(max - min)),
+        "variance": number_json(&variance),
+        "std_dev": to_f64(&variance).sqrt(),
+        "sample_variance": sample_variance.as_ref().map(number_json),
+        "sample_std_dev": sample_variance.as_ref().map(|v| to_f64(v).sqrt()),
+    }))
+}
+
+/// Most frequent values of a sorted list, empty when every value is unique
+fn mode(sorted: &[Rational]) -> Vec<Rational> {
+    let mut runs: Vec<(&Rational, usize)> = Vec::new();
+    for value in sorted {
+        match runs.last_mut() {
+            Some((last, count)) if *last == value => *count += 1,
+            _ => runs.push((value, 1)),
+        }
+    }
+
+    let best = runs
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or_default();
+    if best < 2 {
+        return Vec::new();
+    }
+
+    runs.into_iter()
+        .filter(|(_, count)| *count == best)
+        .map(|(value, _)| value.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::math::exact::parse_rational;
+
+    fn values(values: &[&str]) -> Vec<Rational> {
+        values.iter().map(|v| parse_rational(v).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_statistics() {
+        let stats = statistics(&values(&["2", "4", "4", "4", "5", "5", "7", "9"])).unwrap();
+
+        assert_eq!(stats["count"], 8);
+        assert_eq!(stats["mean"]["exact"], "5");
+        assert_eq!(stats["median"]["exact"], "9/2");
+        assert_eq!(stats["mode"][0]["exact"], "4");
+        assert_eq!(stats["range"]["exact"], "7");
+        assert_eq!(stats["variance"]["exact"], "4");
+        assert_eq!(stats["std_dev"], 2.0);
+        assert_eq!(stats["sample_variance"]["exact"], "32/7");
+    }
+
+    #[test]
+    fn test_statistics_single_value() {
+        let stats = statistics(&values(&["0.1"])).unwrap();
+
+        assert_eq!(stats["mean"]["exact"], "1/10");
+        assert!(stats["mode"].as_array().unwrap().is_empty());
+        assert!(stats["sample_variance"].is_null());
+        assert!(statistics(&[]).is_err());
+    }
+}
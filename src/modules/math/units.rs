@@ -0,0 +1,258 @@
+use super::exact::{Rational, parse_rational};
+use crate::modules::{ModuleError, ModuleResult};
+
+/// A unit, converted to its dimension's base unit with `value * factor + offset`
+struct Unit {
+    names: &'static [&'static str],
+    dimension: &'static str,
+    factor: &'static str,
+    offset: &'static str,
+}
+
+const fn unit(
+    names: &'static [&'static str],
+    dimension: &'static str,
+    factor: &'static str,
+) -> Unit {
+    Unit {
+        names,
+        dimension,
+        factor,
+        offset: "0",
+    }
+}
+
+/// Supported units. Factors are exact where the unit is defined exactly.
+const UNITS: &[Unit] = &[
+    // Length, in meters
+    unit(&["m", "meter", "meters", "metre", "metres"], "length", "1"),
+    unit(
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        "length",
+        "1000",
+    ),
+    unit(
+        &[
+            "cm",
+            "centimeter",
+            "centimeters",
+            "centimetre",
+            "centimetres",
+        ],
+        "length",
+        "0.01",
+    ),
+    unit(
+        &[
+            "mm",
+            "millimeter",
+            "millimeters",
+            "millimetre",
+            "millimetres",
+        ],
+        "length",
+        "0.001",
+    ),
+    unit(
+        &["um", "µm", "micrometer", "micrometers", "micron", "microns"],
+        "length",
+        "1e-6",
+    ),
+    unit(&["nm", "nanometer", "nanometers"], "length", "1e-9"),
+    unit(&["in", "inch", "inches"], "length", "0.0254"),
+    unit(&["ft", "foot", "feet"], "length", "0.3048"),
+    unit(&["yd", "yard", "yards"], "length", "0.9144"),
+    unit(&["mi", "mile", "miles"], "length", "1609.344"),
+    unit(
+        &["nmi", "nautical mile", "nautical miles"],
+        "length",
+        "1852",
+    ),
+    // Mass, in kilograms
+    unit(&["kg", "kilogram", "kilograms"], "mass", "1"),
+    unit(&["g", "gram", "grams"], "mass", "0.001"),
+    unit(&["mg", "milligram", "milligrams"], "mass", "1e-6"),
+    unit(&["t", "tonne", "tonnes", "metric ton"], "mass", "1000"),
+    unit(&["lb", "lbs", "pound", "pounds"], "mass", "0.45359237"),
+    unit(&["oz", "ounce", "ounces"], "mass", "0.028349523125"),
+    unit(&["st", "stone", "stones"], "mass", "6.35029318"),
+    // Time, in seconds
+    unit(&["s", "sec", "second", "seconds"], "time", "1"),
+    unit(&["ms", "millisecond", "milliseconds"], "time", "0.001"),
+    unit(&["us", "µs", "microsecond", "microseconds"], "time", "1e-6"),
+    unit(&["ns", "nanosecond", "nanoseconds"], "time", "1e-9"),
+    unit(&["min", "minute", "minutes"], "time", "60"),
+    unit(&["h", "hr", "hour", "hours"], "time", "3600"),
+    unit(&["d", "day", "days"], "time", "86400"),
+    unit(&["wk", "week", "weeks"], "time", "604800"),
+    unit(&["yr", "year", "years"], "time", "31557600"),
+    // Volume, in liters
+    unit(
+        &["l", "L", "liter", "liters", "litre", "litres"],
+        "volume",
+        "1",
+    ),
+    unit(
+        &[
+            "ml",
+            "mL",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+        ],
+        "volume",
+        "0.001",
+    ),
+    unit(
+        &["m3", "m^3", "cubic meter", "cubic meters"],
+        "volume",
+        "1000",
+    ),
+    unit(&["gal", "gallon", "gallons"], "volume", "3.785411784"),
+    unit(&["qt", "quart", "quarts"], "volume", "0.946352946"),
+    unit(&["pt", "pint", "pints"], "volume", "0.473176473"),
+    unit(&["cup", "cups"], "volume", "0.2365882365"),
+    unit(
+        &["floz", "fl oz", "fluid ounce", "fluid ounces"],
+        "volume",
+        "0.0295735295625",
+    ),
+    // Area, in square meters
+    unit(&["m2", "m^2", "square meter", "square meters"], "area", "1"),
+    unit(
+        &["km2", "km^2", "square kilometer", "square kilometers"],
+        "area",
+        "1000000",
+    ),
+    unit(
+        &["ft2", "ft^2", "square foot", "square feet"],
+        "area",
+        "0.09290304",
+    ),
+    unit(&["ha", "hectare", "hectares"], "area", "10000"),
+    unit(&["acre", "acres"], "area", "4046.8564224"),
+    // Speed, in meters per second
+    unit(&["m/s", "mps"], "speed", "1"),
+    unit(&["km/h", "kmh", "kph"], "speed", "5/18"),
+    unit(&["mph", "mi/h"], "speed", "0.44704"),
+    unit(&["kn", "knot", "knots"], "speed", "1852/3600"),
+    // Energy, in joules
+    unit(&["J", "joule", "joules"], "energy", "1"),
+    unit(&["kJ", "kilojoule", "kilojoules"], "energy", "1000"),
+    unit(&["cal", "calorie", "calories"], "energy", "4.184"),
+    unit(&["kcal", "kilocalorie", "kilocalories"], "energy", "4184"),
+    unit(&["Wh", "watt hour", "watt hours"], "energy", "3600"),
+    unit(
+        &["kWh", "kilowatt hour", "kilowatt hours"],
+        "energy",
+        "3600000",
+    ),
+    // Pressure, in pascals
+    unit(&["Pa", "pascal", "pascals"], "pressure", "1"),
+    unit(&["kPa", "kilopascal", "kilopascals"], "pressure", "1000"),
+    unit(&["bar", "bars"], "pressure", "100000"),
+    unit(&["atm", "atmosphere", "atmospheres"], "pressure", "101325"),
+    unit(&["psi"], "pressure", "6894.757293168361"),
+    // Data, in bytes
+    unit(&["B", "byte", "bytes"], "data", "1"),
+    unit(&["bit", "bits"], "data", "1/8"),
+    unit(&["KB", "kB", "kilobyte", "kilobytes"], "data", "1000"),
+    unit(&["MB", "megabyte", "megabytes"], "data", "1000000"),
+    unit(&["GB", "gigabyte", "gigabytes"], "data", "1e9"),
+    unit(&["TB", "terabyte", "terabytes"], "data", "1e12"),
+    unit(&["KiB", "kibibyte", "kibibytes"], "data", "1024"),
+    unit(&["MiB", "mebibyte", "mebibytes"], "data", "1048576"),
+    unit(&["GiB", "gibibyte", "gibibytes"], "data", "1073741824"),
+    unit(&["TiB", "tebibyte", "tebibytes"], "data", "1099511627776"),
+    // Temperature, in kelvins
+    Unit {
+        names: &["K", "kelvin", "kelvins"],
+        dimension: "temperature",
+        factor: "1",
+        offset: "0",
+    },
+    Unit {
+        names: &["C", "°C", "celsius"],
+        dimension: "temperature",
+        factor: "1",
+        offset: "273.15",
+    },
+    Unit {
+        names: &["F", "°F", "fahrenheit"],
+        dimension: "temperature",
+        factor: "5/9",
+        offset: "45967/180",
+    },
+];
+
+/// Finds a unit by name, ignoring case when nothing matches exactly
+fn find_unit(name: &str) -> ModuleResult<&'static Unit> {
+    let name = name.trim();
+
+    UNITS
+        .iter()
+        .find(|u| u.names.contains(&name))
+        .or_else(|| {
+            UNITS
+                .iter()
+                .find(|u| u.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        })
+        .ok_or_else(|| ModuleError::InvalidFunctionInput(format!("Unknown unit {}", name)))
+}
+
+fn constant(text: &str) -> Rational {
+    parse_rational(text).expect("unit constants are valid numbers")
+}
+
+/// Converts a value between two units of the same dimension, exactly
+pub fn convert(value: &Rational, from: &str, to: &str) -> ModuleResult<Rational> {
+    let from_unit = find_unit(from)?;
+    let to_unit = find_unit(to)?;
+
+    if from_unit.dimension != to_unit.dimension {
+        return Err(ModuleError::InvalidFunctionInput(format!(
+            "Cannot convert {} ({}) to {} ({})",
+            from, from_unit.dimension, to, to_unit.dimension
+        )));
+    }
+
+    let base = value * constant(from_unit.factor) + constant(from_unit.offset);
+    Ok((base - constant(to_unit.offset)) / constant(to_unit.factor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converted(value: &str, from: &str, to: &str) -> String {
+        convert(&parse_rational(value).unwrap(), from, to)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(converted("1", "mi", "km"), "25146/15625");
+        assert_eq!(converted("100", "C", "F"), "212");
+        assert_eq!(converted("-40", "fahrenheit", "celsius"), "-40");
+        assert_eq!(converted("90", "km/h", "m/s"), "25");
+        assert_eq!(converted("1", "GiB", "MB"), "16777216/15625");
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        let one = parse_rational("1").unwrap();
+
+        assert!(convert(&one, "kg", "m").is_err());
+        assert!(convert(&one, "parsec", "m").is_err());
+    }
+
+    #[test]
+    fn test_unit_names() {
+        assert_eq!(find_unit("Kilometers").unwrap().factor, "1000");
+        assert_eq!(find_unit("MB").unwrap().factor, "1000000");
+        assert_eq!(find_unit("mb").unwrap().factor, "1000000");
+        assert_eq!(find_unit("mL").unwrap().factor, "0.001");
+    }
+}
@@ -0,0 +1,322 @@
+use super::{json_to_value, value_to_json};
+use crate::modules::{ModuleError, ModuleResult};
+use evalexpr::{
+    Context, ContextWithMutableFunctions, ContextWithMutableVariables, EvalexprError,
+    EvalexprResult, Function, HashMapContext, IterateVariablesContext, Node, Value,
+    build_operator_tree, eval_with_context, eval_with_context_mut,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+/// Variable holding the last result
+pub const ANSWER: &str = "ans";
+
+/// A function defined with `define_function`, e.g. `area(w, h) = w * h`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserFunction {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: String,
+}
+
+impl UserFunction {
+    pub fn signature(&self) -> String {
+        format!(
+            "{}({}) = {}",
+            self.name,
+            self.parameters.join(", "),
+            self.body
+        )
+    }
+}
+
+/// Variables and functions of a session, kept between evaluations
+#[derive(Debug, Default)]
+pub struct MathState {
+    context: HashMapContext,
+    /// Definitions of the functions in `context`, in the order they were defined
+    functions: Vec<UserFunction>,
+}
+
+/// What is saved of a `MathState`, functions can't be serialized once compiled
+#[derive(Serialize, Deserialize)]
+struct SavedState {
+    variables: BTreeMap<String, serde_json::Value>,
+    functions: Vec<UserFunction>,
+}
+
+fn validate_identifier(name: &str) -> ModuleResult<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !valid {
+        return Err(ModuleError::InvalidFunctionInput(format!(
+            "Invalid name {}, use letters, digits and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn math_error(e: EvalexprError) -> ModuleError {
+    ModuleError::ExecutionError(format!("Math error: {}", e))
+}
+
+/// Context of a function call, the arguments shadow the session variables
+struct FunctionScope<'a> {
+    arguments: HashMap<&'a str, Value>,
+    context: &'a HashMapContext,
+}
+
+impl Context for FunctionScope<'_> {
+    type NumericTypes = evalexpr::DefaultNumericTypes;
+
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        self.arguments
+            .get(identifier)
+            .or_else(|| self.context.get_value(identifier))
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        self.context.call_function(identifier, argument)
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        false
+    }
+
+    fn set_builtin_functions_disabled(&mut self, _disabled: bool) -> EvalexprResult<()> {
+        Err(EvalexprError::ContextNotMutable)
+    }
+}
+
+/// Turns a definition into an evalexpr function, evaluated in `context`
+fn compile(
+    function: &UserFunction,
+    context: Arc<HashMapContext>,
+) -> ModuleResult<Function<evalexpr::DefaultNumericTypes>> {
+    let body: Node = build_operator_tree(&function.body).map_err(math_error)?;
+    let parameters = function.parameters.clone();
+
+    Ok(Function::new(move |argument| {
+        // evalexpr passes several arguments as a tuple
+        let values = match parameters.len() {
+            0 => Vec::new(),
+            1 => vec![argument.clone()],
+            n => argument.as_fixed_len_tuple(n)?,
+        };
+
+        let scope = FunctionScope {
+            arguments: parameters.iter().map(String::as_str).zip(values).collect(),
+            context: &context,
+        };
+        body.eval_with_context(&scope)
+    }))
+}
+
+impl MathState {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(SavedState {
+            variables: self
+                .variables()
+                .into_iter()
+                .map(|(name, value)| (name, value_to_json(value)))
+                .collect(),
+            functions: self.functions.clone(),
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn from_json(value: &serde_json::Value) -> ModuleResult<MathState> {
+        let saved: SavedState = serde_json::from_value(value.clone()).map_err(|e| {
+            ModuleError::ExecutionError(format!("Cannot restore math variables: {}", e))
+        })?;
+
+        let mut state = MathState {
+            context: HashMapContext::new(),
+            functions: saved.functions,
+        };
+        for (name, value) in saved.variables {
+            state
+                .context
+                .set_value(name, json_to_value(&value))
+                .map_err(math_error)?;
+        }
+        state.refresh_functions()?;
+
+        Ok(state)
+    }
+
+    /// Evaluates an expression, which may assign variables (`x = 2; x * 3`),
+    /// and stores its result in `ans`
+    pub fn eval(&mut self, expression: &str) -> ModuleResult<Value> {
+        let result = eval_with_context_mut(expression, &mut self.context).map_err(math_error)?;
+
+        if !result.is_empty() {
+            self.assign(ANSWER, result.clone())?;
+        } else {
+            self.refresh_functions()?;
+        }
+        Ok(result)
+    }
+
+    /// Evaluates an expression without changing anything
+    pub fn value_of(&self, expression: &str) -> ModuleResult<Value> {
+        eval_with_context(expression, &self.context).map_err(math_error)
+    }
+
+    /// Stores a result computed by another tool in `ans`
+    pub fn set_answer(&mut self, value: f64) -> ModuleResult<()> {
+        self.assign(ANSWER, Value::Float(value))
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: Value) -> ModuleResult<()> {
+        validate_identifier(name)?;
+        self.assign(name, value)
+    }
+
+    pub fn define_function(
+        &mut self,
+        name: &str,
+        parameters: &[String],
+        body: &str,
+    ) -> ModuleResult<UserFunction> {
+        validate_identifier(name)?;
+        for parameter in parameters {
+            validate_identifier(parameter)?;
+        }
+
+        let function = UserFunction {
+            name: name.to_string(),
+            parameters: parameters.to_vec(),
+            body: body.trim().to_string(),
+        };
+        // Catch syntax errors now rather than on the first call
+        build_operator_tree::<evalexpr::DefaultNumericTypes>(&function.body).map_err(math_error)?;
+
+        match self.functions.iter_mut().find(|f| f.name == name) {
+            Some(existing) => *existing = function.clone(),
+            None => self.functions.push(function.clone()),
+        }
+        self.refresh_functions()?;
+
+        Ok(function)
+    }
+
+    pub fn variables(&self) -> BTreeMap<String, Value> {
+        self.context.iter_variables().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty() && self.context.iter_variable_names().next().is_none()
+    }
+
+    pub fn functions(&self) -> &[UserFunction] {
+        &self.functions
+    }
+
+    /// Sets a variable, even to a value of another type which `HashMapContext`
+    /// refuses, by rebuilding the context without it
+    fn assign(&mut self, name: &str, value: Value) -> ModuleResult<()> {
+        if self
+            .context
+            .set_value(name.to_string(), value.clone())
+            .is_err()
+        {
+            let mut context = HashMapContext::new();
+            for (variable, existing) in self.context.iter_variables() {
+                if variable != name {
+                    context.set_value(variable, existing).map_err(math_error)?;
+                }
+            }
+            context
+                .set_value(name.to_string(), value)
+                .map_err(math_error)?;
+            self.context = context;
+        }
+
+        self.refresh_functions()
+    }
+
+    /// Registers the functions again so that they see the current variables.
+    /// Each function can call the ones defined before it.
+    fn refresh_functions(&mut self) -> ModuleResult<()> {
+        let mut context = self.context.clone();
+        context.clear_functions();
+
+        for function in &self.functions {
+            let compiled = compile(function, Arc::new(context.clone()))?;
+            context
+                .set_function(function.name.clone(), compiled)
+                .map_err(math_error)?;
+        }
+
+        self.context = context;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_and_answer() {
+        let mut state = MathState::default();
+
+        assert_eq!(state.eval("2 * 21").unwrap(), Value::Int(42));
+        assert_eq!(state.eval("ans / 4.0").unwrap(), Value::Float(10.5));
+        assert_eq!(
+            state.eval("rate = 0.2; 100 * rate").unwrap(),
+            Value::Float(20.0)
+        );
+
+        // `ans` went from an int to a float
+        assert_eq!(state.variables()[ANSWER], Value::Float(20.0));
+        assert_eq!(state.variables()["rate"], Value::Float(0.2));
+    }
+
+    #[test]
+    fn test_user_functions() {
+        let mut state = MathState::default();
+        state.set_variable("rate", Value::Float(0.5)).unwrap();
+        state
+            .define_function("tax", &["x".to_string()], "x * rate")
+            .unwrap();
+        state
+            .define_function(
+                "total",
+                &["x".to_string(), "n".to_string()],
+                "n * (x + tax(x))",
+            )
+            .unwrap();
+
+        assert_eq!(state.eval("total(10.0, 2)").unwrap(), Value::Float(30.0));
+
+        // Functions see variables changed after they were defined
+        state.set_variable("rate", Value::Float(0.1)).unwrap();
+        assert_eq!(state.eval("tax(10.0)").unwrap(), Value::Float(1.0));
+
+        assert!(state.define_function("bad", &[], "(1 + 2").is_err());
+        assert!(state.define_function("2x", &[], "1").is_err());
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut state = MathState::default();
+        state.eval("x = 3").unwrap();
+        state
+            .define_function("square", &["v".to_string()], "v * v")
+            .unwrap();
+
+        let mut restored = MathState::from_json(&state.to_json()).unwrap();
+
+        assert_eq!(restored.eval("square(x)").unwrap(), Value::Int(9));
+    }
+}
@@ -0,0 +1,250 @@
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use crate::memory::{MemoryStore, memory_path};
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Default number of memories returned by `recall`
+const RECALL_LIMIT: usize = 5;
+
+pub struct Memory {
+    path: PathBuf,
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory {
+            path: memory_path(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_path(path: PathBuf) -> Memory {
+        Memory { path }
+    }
+
+    pub fn name() -> &'static str {
+        "memory"
+    }
+
+    fn load(&self) -> ModuleResult<MemoryStore> {
+        MemoryStore::load(&self.path)
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot read memories: {}", e)))
+    }
+
+    fn save(&self, store: &MemoryStore) -> ModuleResult<()> {
+        store
+            .save(&self.path)
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot save memories: {}", e)))
+    }
+
+    fn remember(&self, fact: &str, tags: &[String]) -> ModuleResult<serde_json::Value> {
+        if fact.trim().is_empty() {
+            return Err(ModuleError::InvalidFunctionInput("'fact' is empty".into()));
+        }
+
+        let mut store = self.load()?;
+        let id = store.remember(fact, tags);
+        self.save(&store)?;
+
+        Ok(json!({ "id": id, "remembered": fact.trim() }))
+    }
+
+    fn recall(&self, query: &str, limit: usize) -> ModuleResult<serde_json::Value> {
+        let store = self.load()?;
+        let memories: Vec<serde_json::Value> = store
+            .keyword_search(query, limit)
+            .into_iter()
+            .map(|m| json!({ "id": m.id, "fact": m.fact, "tags": m.tags }))
+            .collect();
+
+        Ok(json!({ "memories": memories }))
+    }
+
+    fn forget(&self, id: u64) -> ModuleResult<serde_json::Value> {
+        let mut store = self.load()?;
+        let memory = store
+            .forget(id)
+            .ok_or_else(|| ModuleError::ExecutionError(format!("No memory with id {}", id)))?;
+        self.save(&store)?;
+
+        Ok(json!({ "forgot": memory.id, "fact": memory.fact }))
+    }
+}
+
+impl Module for Memory {
+    fn name(&self) -> &'static str {
+        Memory::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Remembers facts across sessions, and recalls or forgets them."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **memory**: Long-term memory that persists across sessions.
+  - **Rules**:
+    - Use `remember` when the user states a lasting fact, preference or convention, or asks you to remember something. Keep facts short and self-contained.
+    - Use `recall` to search memories when the relevant ones are not already listed in the `<memories>` context.
+    - Use `forget` with the memory id (e.g. `#3` is id 3) when the user says a memory is wrong or outdated."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<serde_json::Value> {
+        match func.name.as_str() {
+            "remember" => {
+                let fact = func
+                    .arguments
+                    .get("fact")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Missing 'fact' argument".into())
+                    })?;
+
+                let tags: Vec<String> = func
+                    .arguments
+                    .get("tags")
+                    .and_then(|v| v.as_array())
+                    .map(|tags| {
+                        tags.iter()
+                            .filter_map(|t| t.as_str())
+                            .map(|t| t.trim().to_lowercase())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                self.remember(fact, &tags)
+            }
+            "recall" => {
+                let query = func
+                    .arguments
+                    .get("query")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ModuleError::InvalidFunctionInput("Missing 'query' argument".into())
+                    })?;
+
+                let limit = func
+                    .arguments
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map(|l| l as usize)
+                    .unwrap_or(RECALL_LIMIT);
+
+                self.recall(query, limit)
+            }
+            "forget" => {
+                let id = match func.arguments.get("id") {
+                    Some(serde_json::Value::Number(n)) => n.as_u64(),
+                    Some(serde_json::Value::String(s)) => s.trim_start_matches('#').parse().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    ModuleError::InvalidFunctionInput("Missing or invalid 'id' argument".into())
+                })?;
+
+                self.forget(id)
+            }
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        vec![
+            Tool {
+                tool_type: "function".to_string(),
+                function: ToolFunction {
+                    name: "remember".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Stores a fact in long-term memory".to_string(),
+                    parameters: json!({
+                        "type": "object",
+                        "properties": {
+                            "fact": { "type": "string", "description": "The fact to remember" },
+                            "tags": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Keywords to find the fact by (e.g. ['style', 'rust'])"
+                            }
+                        },
+                        "required": ["fact"]
+                    }),
+                },
+            },
+            Tool {
+                tool_type: "function".to_string(),
+                function: ToolFunction {
+                    name: "recall".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Searches long-term memory".to_string(),
+                    parameters: json!({
+                        "type": "object",
+                        "properties": {
+                            "query": { "type": "string", "description": "What to look for" },
+                            "limit": { "type": "integer", "description": "Maximum number of memories to return" }
+                        },
+                        "required": ["query"]
+                    }),
+                },
+            },
+            Tool {
+                tool_type: "function".to_string(),
+                function: ToolFunction {
+                    name: "forget".to_string(),
+                    module: Self::name().to_string(),
+                    description: "Deletes a memory by its id".to_string(),
+                    parameters: json!({
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "integer", "description": "Id of the memory" }
+                        },
+                        "required": ["id"]
+                    }),
+                },
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(
+        memory: &Memory,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> ModuleResult<serde_json::Value> {
+        memory.run(&ToolCallFunction {
+            name: name.to_string(),
+            module: Memory::name().to_string(),
+            arguments,
+        })
+    }
+
+    #[test]
+    fn test_remember_recall_forget() {
+        let path = std::env::temp_dir().join(format!("jarvis-memory-{}.json", std::process::id()));
+        let memory = Memory::with_path(path.clone());
+
+        let result = call(
+            &memory,
+            "remember",
+            json!({ "fact": "Releases are cut on Fridays", "tags": ["Release"] }),
+        )
+        .unwrap();
+        assert_eq!(result["id"], json!(1));
+
+        let result = call(&memory, "recall", json!({ "query": "release day" })).unwrap();
+        assert_eq!(
+            result["memories"][0]["fact"],
+            json!("Releases are cut on Fridays")
+        );
+        assert_eq!(result["memories"][0]["tags"], json!(["release"]));
+
+        let result = call(&memory, "forget", json!({ "id": "#1" })).unwrap();
+        assert_eq!(result["forgot"], json!(1));
+        assert!(call(&memory, "forget", json!({ "id": 1 })).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,9 +1,29 @@
+mod code;
+mod data;
+mod git;
+mod http;
 mod math;
+mod memory;
 mod module;
+mod notes;
 mod registry;
+mod router;
+mod scope;
+mod system;
+mod time;
 
+pub use code::Code;
+pub use data::Data;
+pub use git::Git;
+pub use http::Http;
 pub use math::Math;
+pub use memory::Memory;
 pub use module::{
     Module, ModuleError, ModuleResult, Tool, ToolCall, ToolCallFunction, ToolFunction,
 };
+pub use notes::Notes;
 pub use registry::ModuleRegistry;
+pub use router::{DEFAULT_MAX_TOOLS, ROUTER_MODULE, SEARCH_TOOLS, ToolRouter, is_tool_search};
+pub use scope::ModuleScope;
+pub use system::System;
+pub use time::{Time, Zone, parse_datetime};
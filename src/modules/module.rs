@@ -76,4 +76,17 @@ pub trait Module: Send + Sync {
     fn run(&self, func: &ToolCallFunction) -> ModuleResult<serde_json::Value>;
     /// Available tools in this module in the OpenAI format
     fn tools(&self) -> Vec<Tool>;
+    /// State to save with a chat session, for modules that keep some
+    fn state(&self) -> Option<serde_json::Value> {
+        None
+    }
+    /// Tools that change something outside Jarvis (e.g. `git.commit`), only
+    /// enabled with `--execute`
+    fn mutating_tools(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Restores the state saved with a chat session, or resets it when `None`
+    fn restore_state(&self, _state: Option<&serde_json::Value>) -> ModuleResult<()> {
+        Ok(())
+    }
 }
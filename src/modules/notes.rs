@@ -0,0 +1,370 @@
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use crate::notes::{Note, Todo, TodoList, notes_dir, parse_due, todos_path};
+use chrono::{Local, NaiveDate};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+/// Default number of notes returned by `search_notes`, and at most
+const SEARCH_LIMIT: usize = 5;
+const MAX_SEARCH_LIMIT: usize = 20;
+
+/// Note texts longer than this are cut in search results
+const MAX_NOTE_CHARS: usize = 2_000;
+
+/// Notes as markdown files and todos in a JSON file, in the data directory
+pub struct Notes {
+    dir: PathBuf,
+    todos: PathBuf,
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: name.to_string(),
+            module: Notes::name().to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+    }
+}
+
+fn describe_todo(todo: &Todo, today: NaiveDate) -> Value {
+    json!({
+        "id": todo.id,
+        "text": todo.text,
+        "due": todo.due_label(today),
+        "done": todo.done_at.is_some(),
+    })
+}
+
+impl Notes {
+    pub fn new() -> Notes {
+        Notes {
+            dir: notes_dir(),
+            todos: todos_path(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_paths(dir: PathBuf, todos: PathBuf) -> Notes {
+        Notes { dir, todos }
+    }
+
+    pub fn name() -> &'static str {
+        "notes"
+    }
+
+    fn string_argument<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<&'a str> {
+        func.arguments
+            .get(name)
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!("Missing '{}' argument", name))
+            })
+    }
+
+    fn load_todos(&self) -> ModuleResult<TodoList> {
+        TodoList::load(&self.todos)
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot read todos: {}", e)))
+    }
+
+    fn save_todos(&self, list: &TodoList) -> ModuleResult<()> {
+        list.save(&self.todos)
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot save todos: {}", e)))
+    }
+
+    fn add_note(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let text = self.string_argument(func, "text")?;
+        let title = func.arguments.get("title").and_then(|v| v.as_str());
+        let tags: Vec<String> = func
+            .arguments
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str())
+                    .map(|t| t.trim().to_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let note = Note::add(&self.dir, text, title, &tags)
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot save the note: {}", e)))?;
+        Ok(json!({ "saved": note.file, "title": note.title }))
+    }
+
+    fn search_notes(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let query = func
+            .arguments
+            .get("query")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let limit = func
+            .arguments
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map_or(SEARCH_LIMIT, |l| l as usize)
+            .clamp(1, MAX_SEARCH_LIMIT);
+
+        let notes = Note::read_all(&self.dir)
+            .map_err(|e| ModuleError::ExecutionError(format!("Cannot read notes: {}", e)))?;
+        let found: Vec<Value> = Note::search(&notes, query, limit)
+            .into_iter()
+            .map(|note| {
+                let text = match note.text.char_indices().nth(MAX_NOTE_CHARS) {
+                    Some((end, _)) => format!("{}...", &note.text[..end]),
+                    None => note.text.clone(),
+                };
+                json!({
+                    "file": note.file,
+                    "title": note.title,
+                    "date": note.date.to_string(),
+                    "tags": note.tags,
+                    "text": text,
+                })
+            })
+            .collect();
+
+        Ok(json!({ "notes": found }))
+    }
+
+    fn add_todo(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let text = self.string_argument(func, "text")?;
+        let due = match func.arguments.get("due").and_then(|v| v.as_str()) {
+            Some(due) if !due.trim().is_empty() => Some(parse_due(due)?),
+            _ => None,
+        };
+
+        let mut list = self.load_todos()?;
+        let id = list.add(text, due);
+        self.save_todos(&list)?;
+
+        Ok(json!({ "id": id, "text": text.trim(), "due": due.map(|d| d.to_string()) }))
+    }
+
+    fn list_todos(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let include_done = func
+            .arguments
+            .get("include_done")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let list = self.load_todos()?;
+        let today = Local::now().date_naive();
+        let mut todos: Vec<Value> = list
+            .open()
+            .into_iter()
+            .map(|t| describe_todo(t, today))
+            .collect();
+        if include_done {
+            todos.extend(
+                list.todos
+                    .iter()
+                    .filter(|t| t.done_at.is_some())
+                    .map(|t| describe_todo(t, today)),
+            );
+        }
+
+        Ok(json!({ "todos": todos }))
+    }
+
+    fn complete_todo(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        let id = match func.arguments.get("id") {
+            Some(Value::Number(n)) => n.as_u64(),
+            Some(Value::String(s)) => s.trim_start_matches('#').parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            ModuleError::InvalidFunctionInput("Missing or invalid 'id' argument".into())
+        })?;
+
+        let mut list = self.load_todos()?;
+        let text = list
+            .complete(id)
+            .map(|t| t.text.clone())
+            .ok_or_else(|| ModuleError::ExecutionError(format!("No todo with id {}", id)))?;
+        self.save_todos(&list)?;
+
+        Ok(json!({ "completed": id, "text": text }))
+    }
+
+    fn due_today(&self) -> ModuleResult<Value> {
+        let list = self.load_todos()?;
+        let today = Local::now().date_naive();
+        let todos: Vec<Value> = list
+            .due_by(today)
+            .into_iter()
+            .map(|t| describe_todo(t, today))
+            .collect();
+
+        Ok(json!({ "today": today.to_string(), "todos": todos }))
+    }
+}
+
+impl Module for Notes {
+    fn name(&self) -> &'static str {
+        Notes::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Keeps notes and todos across sessions, as files in the data directory."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **notes**: The user's personal notes and todo list.
+  - **Rules**:
+    - Use `add_note` when the user asks to write something down, and `search_notes` before answering questions about what they noted.
+    - Use `add_todo` for tasks the user has to do, with `due` when they give a date (e.g. `tomorrow`, `friday`, `2026-10-20`).
+    - Use `due_today` when the user asks what they have to do today, it includes overdue todos.
+    - Use `complete_todo` with the todo id (e.g. `#3` is id 3) when the user says a task is done.
+    - Notes are different from memories: memories are facts about the user for you, notes and todos are for the user."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        match func.name.as_str() {
+            "add_note" => self.add_note(func),
+            "search_notes" => self.search_notes(func),
+            "add_todo" => self.add_todo(func),
+            "list_todos" => self.list_todos(func),
+            "complete_todo" => self.complete_todo(func),
+            "due_today" => self.due_today(),
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        vec![
+            tool(
+                "add_note",
+                "Saves a markdown note",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string", "description": "Content of the note, in markdown" },
+                        "title": { "type": "string", "description": "Title, the first line of the text by default" },
+                        "tags": { "type": "array", "items": { "type": "string" } }
+                    },
+                    "required": ["text"]
+                }),
+            ),
+            tool(
+                "search_notes",
+                "Searches notes by keywords, the latest ones without a query",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "What to look for" },
+                        "limit": { "type": "integer", "description": "Maximum number of notes to return" }
+                    }
+                }),
+            ),
+            tool(
+                "add_todo",
+                "Adds a task to the todo list",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string", "description": "The task" },
+                        "due": { "type": "string", "description": "Due date, e.g. '2026-10-20', 'tomorrow' or 'next friday'" }
+                    },
+                    "required": ["text"]
+                }),
+            ),
+            tool(
+                "list_todos",
+                "Lists the todos left to do, the ones due first",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "include_done": { "type": "boolean", "description": "Also list the completed todos" }
+                    }
+                }),
+            ),
+            tool(
+                "complete_todo",
+                "Marks a todo as done",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "description": "Id of the todo" }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            tool(
+                "due_today",
+                "Todos due today or overdue",
+                json!({ "type": "object", "properties": {} }),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(notes: &Notes, name: &str, arguments: Value) -> ModuleResult<Value> {
+        notes.run(&ToolCallFunction {
+            name: name.to_string(),
+            module: Notes::name().to_string(),
+            arguments,
+        })
+    }
+
+    #[test]
+    fn test_notes_and_todos() {
+        let dir = std::env::temp_dir().join(format!("jarvis-notes-module-{}", std::process::id()));
+        let notes = Notes::with_paths(dir.join("notes"), dir.join("todos.json"));
+
+        let saved = call(
+            &notes,
+            "add_note",
+            json!({ "text": "Gate code is 4521", "tags": ["Home"] }),
+        )
+        .unwrap();
+        assert_eq!(saved["title"], json!("Gate code is 4521"));
+        let found = call(&notes, "search_notes", json!({ "query": "gate code" })).unwrap();
+        assert_eq!(found["notes"][0]["tags"], json!(["home"]));
+
+        call(
+            &notes,
+            "add_todo",
+            json!({ "text": "Call the bank", "due": "today" }),
+        )
+        .unwrap();
+        call(&notes, "add_todo", json!({ "text": "Sort books" })).unwrap();
+        call(
+            &notes,
+            "add_todo",
+            json!({ "text": "Plan trip", "due": "in 3 days" }),
+        )
+        .unwrap();
+        assert!(call(&notes, "add_todo", json!({ "text": "x", "due": "someday" })).is_err());
+
+        let due = call(&notes, "due_today", json!({})).unwrap();
+        assert_eq!(due["todos"].as_array().unwrap().len(), 1);
+        assert_eq!(due["todos"][0]["text"], json!("Call the bank"));
+
+        let result = call(&notes, "complete_todo", json!({ "id": "#1" })).unwrap();
+        assert_eq!(result["completed"], json!(1));
+        assert!(call(&notes, "complete_todo", json!({ "id": 9 })).is_err());
+
+        let open = call(&notes, "list_todos", json!({})).unwrap();
+        let texts: Vec<&str> = open["todos"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["Plan trip", "Sort books"]);
+        let all = call(&notes, "list_todos", json!({ "include_done": true })).unwrap();
+        assert_eq!(all["todos"][2]["done"], json!(true));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,26 +1,73 @@
-use super::{Math, Module, Tool, ToolCallFunction};
+use super::{
+    Code, Data, Git, Http, Math, Memory, Module, ModuleScope, Notes, ROUTER_MODULE, SEARCH_TOOLS,
+    System, Time, Tool, ToolCallFunction, scope::CompiledScope,
+};
 use crate::{AppError, AppResult};
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
 
+/// Modules available to a run. A registry can be narrowed to a `ModuleScope`,
+/// the narrowed view shares the modules and only offers, describes and runs
+/// the tools in scope.
+#[derive(Clone)]
 pub struct ModuleRegistry {
-    modules: HashMap<String, Box<dyn Module + Send + Sync>>,
+    modules: HashMap<String, Arc<dyn Module + Send + Sync>>,
+    scope: CompiledScope,
 }
 
 #[allow(dead_code)]
 impl ModuleRegistry {
     pub fn new() -> ModuleRegistry {
-        let mut registry: HashMap<String, Box<dyn Module + Send + Sync>> = HashMap::new();
-        registry.insert(Math::name().to_string(), Box::new(Math::new()));
+        let mut registry: HashMap<String, Arc<dyn Module + Send + Sync>> = HashMap::new();
+        registry.insert(Code::name().to_string(), Arc::new(Code::new()));
+        registry.insert(Data::name().to_string(), Arc::new(Data::new()));
+        registry.insert(Git::name().to_string(), Arc::new(Git::new()));
+        registry.insert(Http::name().to_string(), Arc::new(Http::new()));
+        registry.insert(Math::name().to_string(), Arc::new(Math::new()));
+        registry.insert(Memory::name().to_string(), Arc::new(Memory::new()));
+        registry.insert(Notes::name().to_string(), Arc::new(Notes::new()));
+        registry.insert(System::name().to_string(), Arc::new(System::new()));
+        registry.insert(Time::name().to_string(), Arc::new(Time::new()));
 
-        ModuleRegistry { modules: registry }
+        ModuleRegistry {
+            modules: registry,
+            scope: CompiledScope::default(),
+        }
     }
 
     pub fn empty_registry() -> ModuleRegistry {
         ModuleRegistry {
             modules: HashMap::new(),
+            scope: CompiledScope::default(),
         }
     }
 
+    /// A view of this registry limited to `scope`
+    pub fn scoped(&self, scope: ModuleScope) -> AppResult<ModuleRegistry> {
+        let named = scope
+            .modules
+            .iter()
+            .flatten()
+            .chain(&scope.excluded_modules);
+        for name in named {
+            if !self.modules.contains_key(name) {
+                return Err(AppError::from(&format!("Module {} not found", name)));
+            }
+        }
+
+        Ok(ModuleRegistry {
+            modules: self.modules.clone(),
+            scope: CompiledScope::new(scope)?,
+        })
+    }
+
+    pub fn scope(&self) -> &ModuleScope {
+        self.scope.scope()
+    }
+
     pub fn list_modules(&self) -> Vec<String> {
         self.modules
             .iter()
@@ -28,8 +75,59 @@ impl ModuleRegistry {
             .collect()
     }
 
+    /// Whether a tool of `module` is in scope, tools making changes only
+    /// when the scope allows executing them
+    fn offers(&self, module: &(dyn Module + Send + Sync), tool: &str) -> bool {
+        self.scope.allows_tool(module.name(), tool)
+            && (self.scope.scope().execute || !module.mutating_tools().contains(&tool))
+    }
+
+    /// Every registered module, in scope or not, sorted by name
+    pub fn registered_modules(&self) -> Vec<&(dyn Module + Send + Sync)> {
+        let mut modules: Vec<_> = self.modules.values().map(|m| m.as_ref()).collect();
+        modules.sort_by_key(|m| m.name());
+        modules
+    }
+
+    /// Modules in scope with at least one tool in scope, sorted by name
+    pub fn modules(&self) -> Vec<&(dyn Module + Send + Sync)> {
+        let mut modules: Vec<_> = self
+            .modules
+            .values()
+            .map(|m| m.as_ref())
+            .filter(|m| m.tools().iter().any(|t| self.offers(*m, &t.function.name)))
+            .collect();
+        modules.sort_by_key(|m| m.name());
+        modules
+    }
+
+    /// Short listing of the modules for routed runs, where only the tools
+    /// relevant to each message are offered with their module rules
+    pub fn get_catalog_prompt(&self) -> String {
+        let mut prompt = format!(
+            "- **{}**: Finds the tools that are not offered yet. Only the tools relevant to the current message are offered.\n  - **Rules**:\n    - Call `{}` with a short description of the task (e.g. \"convert miles to km\") when you need a tool that is not offered, then call the tools it returns.\n",
+            ROUTER_MODULE, SEARCH_TOOLS
+        );
+        for module in self.modules() {
+            prompt.push_str(&format!(
+                "- **{}**: {}\n",
+                module.name(),
+                module.description()
+            ));
+        }
+
+        prompt
+    }
+
     pub fn all_tools(&self) -> Vec<Tool> {
-        self.modules.values().flat_map(|m| m.tools()).collect()
+        self.modules()
+            .into_iter()
+            .flat_map(|m| {
+                m.tools()
+                    .into_iter()
+                    .filter(|t| self.offers(m, &t.function.name))
+            })
+            .collect()
     }
 
     pub fn execute(&self, func: &ToolCallFunction) -> AppResult<serde_json::Value> {
@@ -37,26 +135,56 @@ impl ModuleRegistry {
             .get_module(func.module.as_str())
             .ok_or_else(|| AppError::from(&format!("Module {} not found", func.module)))?;
 
+        if !self.scope.allows_tool(&func.module, &func.name) {
+            return Err(AppError::from(&format!(
+                "Tool {}.{} is not enabled for this run",
+                func.module, func.name
+            )));
+        }
+        if !self.offers(module, &func.name) {
+            return Err(AppError::from(&format!(
+                "Tool {}.{} makes changes, run with --execute to enable it",
+                func.module, func.name
+            )));
+        }
+
         let result = module.run(func)?;
         Ok(result)
     }
 
     pub fn get_system_prompt(&self) -> String {
         let modules: String = self
-            .modules
+            .modules()
             .iter()
-            .map(|v| format!("{}\n", v.1.get_prompt()))
+            .map(|m| format!("{}\n", m.get_prompt()))
             .collect();
 
         modules
     }
 
-    pub fn register_module(&mut self, name: String, module: Box<dyn Module>) {
-        self.modules.insert(name, module);
+    /// State of the modules that keep some, to save with a chat session
+    pub fn states(&self) -> BTreeMap<String, serde_json::Value> {
+        self.modules
+            .iter()
+            .filter_map(|(name, module)| Some((name.clone(), module.state()?)))
+            .collect()
     }
 
-    pub fn get_module(&self, name: &str) -> Option<&Box<dyn Module + Send + Sync>> {
-        self.modules.get(name)
+    /// Restores the state of every module, resetting the ones missing from `states`
+    pub fn restore_states(&self, states: &BTreeMap<String, serde_json::Value>) -> AppResult<()> {
+        for (name, module) in &self.modules {
+            module.restore_state(states.get(name))?;
+        }
+        Ok(())
+    }
+
+    pub fn register_module(&mut self, name: String, module: Box<dyn Module + Send + Sync>) {
+        self.modules.insert(name, Arc::from(module));
+    }
+
+    /// Any registered module, in scope or not
+    pub fn get_module(&self, name: &str) -> Option<&(dyn Module + Send + Sync)> {
+        self.modules.get(name).map(|m| m.as_ref())
     }
 }
 
@@ -69,3 +197,94 @@ impl fmt::Debug for ModuleRegistry {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scoped_registry() {
+        let registry = ModuleRegistry::new();
+        let scoped = registry
+            .scoped(ModuleScope {
+                modules: Some(vec!["math".to_string()]),
+                denied_tools: vec!["math.s*".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let tools: Vec<String> = scoped
+            .all_tools()
+            .into_iter()
+            .map(|t| t.function.name)
+            .collect();
+        assert!(tools.contains(&"eval".to_string()));
+        assert!(!tools.contains(&"sqrt".to_string()));
+        assert!(!tools.contains(&"recall".to_string()));
+        assert!(!scoped.get_system_prompt().contains("**memory**"));
+
+        let sqrt = ToolCallFunction {
+            name: "sqrt".to_string(),
+            module: "math".to_string(),
+            arguments: json!({ "value": 4 }),
+        };
+        assert!(scoped.execute(&sqrt).is_err());
+        assert!(registry.execute(&sqrt).is_ok());
+
+        assert!(
+            registry
+                .scoped(ModuleScope {
+                    excluded_modules: vec!["nope".to_string()],
+                    ..Default::default()
+                })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_mutating_tools_need_execute() {
+        let registry = ModuleRegistry::new();
+        let names = |registry: &ModuleRegistry| -> Vec<String> {
+            registry
+                .all_tools()
+                .into_iter()
+                .map(|t| format!("{}.{}", t.function.module, t.function.name))
+                .collect()
+        };
+
+        let tools = names(&registry);
+        assert!(tools.contains(&"git.status".to_string()));
+        assert!(!tools.contains(&"git.commit".to_string()));
+
+        // Modules with only such tools are hidden from the model, not the listing
+        assert!(!registry.modules().iter().any(|m| m.name() == "code"));
+        assert!(
+            registry
+                .registered_modules()
+                .iter()
+                .any(|m| m.name() == "code")
+        );
+
+        let commit = ToolCallFunction {
+            name: "commit".to_string(),
+            module: "git".to_string(),
+            arguments: json!({}),
+        };
+        assert!(
+            registry
+                .execute(&commit)
+                .unwrap_err()
+                .to_string()
+                .contains("--execute")
+        );
+
+        let executing = registry
+            .scoped(ModuleScope {
+                execute: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(names(&executing).contains(&"git.commit".to_string()));
+    }
+}
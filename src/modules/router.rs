@@ -0,0 +1,303 @@
+use super::{Tool, ToolCallFunction, ToolFunction};
+use crate::{index::cosine_similarity, memory::keywords};
+
+/// Pseudo module of the tool search, handled by the client rather than the registry
+pub const ROUTER_MODULE: &str = "tools";
+
+pub const SEARCH_TOOLS: &str = "search_tools";
+
+/// Runs offering more tools than this get a routed subset for each message
+pub const DEFAULT_MAX_TOOLS: usize = 16;
+
+/// Tools loaded by one call to `search_tools`
+const SEARCH_RESULTS: usize = 5;
+
+/// Tools less similar than this to the message are only offered on keyword matches
+const MIN_TOOL_SIMILARITY: f32 = 0.5;
+
+/// Picks the tools relevant to each message when a run has too many to offer
+/// them all, so small models keep room in their context. Tools are ranked by
+/// embedding similarity when embeddings are available and by keywords
+/// otherwise. Tools loaded through `search_tools` stay offered.
+#[derive(Debug, Clone)]
+pub struct ToolRouter {
+    tools: Vec<Tool>,
+    max_tools: usize,
+    /// Embedding of each tool, in the same order as `tools`
+    embeddings: Option<Vec<Vec<f32>>>,
+    /// Set when the embedding model failed, keywords are used from then on
+    keywords_only: bool,
+    /// Indexes of the tools loaded through `search_tools`
+    loaded: Vec<usize>,
+}
+
+/// Lowercased keywords of a tool: its name, module, description and parameters
+fn tool_keywords(function: &ToolFunction) -> (Vec<String>, Vec<String>) {
+    let mut name = keywords(&function.name);
+    name.extend(keywords(&function.module));
+
+    let mut description = keywords(&function.description);
+    if let Some(properties) = function
+        .parameters
+        .get("properties")
+        .and_then(|p| p.as_object())
+    {
+        for (parameter, schema) in properties {
+            description.extend(keywords(parameter));
+            if let Some(text) = schema.get("description").and_then(|d| d.as_str()) {
+                description.extend(keywords(text));
+            }
+        }
+    }
+
+    (name, description)
+}
+
+/// Same word, or one is a prefix of the other (`convert` and `converts`)
+fn same_word(a: &str, b: &str) -> bool {
+    a == b || (a.len().min(b.len()) >= 4 && (a.starts_with(b) || b.starts_with(a)))
+}
+
+/// Whether a tool call is a call to the tool search
+pub fn is_tool_search(function: &ToolCallFunction) -> bool {
+    function.module == ROUTER_MODULE && function.name == SEARCH_TOOLS
+}
+
+impl ToolRouter {
+    pub fn new(tools: Vec<Tool>, max_tools: usize) -> Self {
+        Self {
+            tools,
+            max_tools,
+            embeddings: None,
+            keywords_only: false,
+            loaded: Vec::new(),
+        }
+    }
+
+    /// The tool the model calls to search for and load more tools
+    pub fn search_tool() -> Tool {
+        Tool {
+            tool_type: "function".to_string(),
+            function: ToolFunction {
+                name: SEARCH_TOOLS.to_string(),
+                description: "Searches the tools that are not offered yet and loads the best matches, which can be called right after".to_string(),
+                module: ROUTER_MODULE.to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "What the tool should do, e.g. \"convert miles to km\""
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
+        }
+    }
+
+    /// Whether embeddings should still be tried for ranking
+    pub fn wants_embeddings(&self) -> bool {
+        !self.keywords_only
+    }
+
+    pub fn has_embeddings(&self) -> bool {
+        self.embeddings.is_some()
+    }
+
+    /// Text embedded for each tool, in the same order as the tools
+    pub fn tool_texts(&self) -> Vec<String> {
+        self.tools
+            .iter()
+            .map(|t| {
+                format!(
+                    "{} {}: {}",
+                    t.function.module, t.function.name, t.function.description
+                )
+            })
+            .collect()
+    }
+
+    pub fn set_embeddings(&mut self, embeddings: Vec<Vec<f32>>) {
+        if embeddings.len() == self.tools.len() {
+            self.embeddings = Some(embeddings);
+        }
+    }
+
+    /// Falls back to keywords for the rest of the run
+    pub fn disable_embeddings(&mut self) {
+        self.keywords_only = true;
+        self.embeddings = None;
+    }
+
+    /// Keyword score of each tool for `query`, a name match counting more
+    /// than a description match
+    fn keyword_scores(&self, query: &str) -> Vec<usize> {
+        let query = keywords(query);
+
+        self.tools
+            .iter()
+            .map(|tool| {
+                let (name, description) = tool_keywords(&tool.function);
+                query
+                    .iter()
+                    .map(|word| {
+                        3 * name.iter().any(|w| same_word(w, word)) as usize
+                            + description.iter().any(|w| same_word(w, word)) as usize
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Indexes of the tools matching `query`, best first: similar ones when
+    /// the query embedding is given, then keyword matches
+    fn rank(&self, query: &str, query_embedding: Option<&[f32]>) -> Vec<usize> {
+        let mut ranked = Vec::new();
+
+        if let (Some(embeddings), Some(query_embedding)) = (&self.embeddings, query_embedding) {
+            let mut similar: Vec<(f32, usize)> = embeddings
+                .iter()
+                .enumerate()
+                .map(|(i, embedding)| (cosine_similarity(query_embedding, embedding), i))
+                .filter(|(score, _)| *score >= MIN_TOOL_SIMILARITY)
+                .collect();
+            similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+            ranked.extend(similar.into_iter().map(|(_, i)| i));
+        }
+
+        let mut matching: Vec<(usize, usize)> = self
+            .keyword_scores(query)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, score)| *score > 0)
+            .map(|(i, score)| (score, i))
+            .collect();
+        // Stable sort, so equal scores keep the registry order
+        matching.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        for (_, i) in matching {
+            if !ranked.contains(&i) {
+                ranked.push(i);
+            }
+        }
+
+        ranked
+    }
+
+    /// Tools to offer for a message: the loaded ones, then the best matches
+    /// up to the limit, and the tool search
+    pub fn route(&self, query: &str, query_embedding: Option<&[f32]>) -> Vec<Tool> {
+        let mut selected = self.loaded.clone();
+        for i in self.rank(query, query_embedding) {
+            if selected.len() >= self.max_tools {
+                break;
+            }
+            if !selected.contains(&i) {
+                selected.push(i);
+            }
+        }
+
+        selected
+            .into_iter()
+            .map(|i| self.tools[i].clone())
+            .chain(std::iter::once(Self::search_tool()))
+            .collect()
+    }
+
+    /// Loads the best matches for `query` among the tools not offered yet
+    /// and returns them
+    pub fn search(
+        &mut self,
+        query: &str,
+        query_embedding: Option<&[f32]>,
+        offered: &[Tool],
+    ) -> Vec<Tool> {
+        let found: Vec<usize> = self
+            .rank(query, query_embedding)
+            .into_iter()
+            .filter(|i| !offered.contains(&self.tools[*i]))
+            .take(SEARCH_RESULTS)
+            .collect();
+
+        self.loaded.extend(&found);
+        found.into_iter().map(|i| self.tools[i].clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::{Math, Memory, Module};
+
+    fn router(max_tools: usize) -> ToolRouter {
+        let mut tools = Math::new().tools();
+        tools.extend(Memory::new().tools());
+        ToolRouter::new(tools, max_tools)
+    }
+
+    fn names(tools: &[Tool]) -> Vec<String> {
+        tools
+            .iter()
+            .map(|t| format!("{}.{}", t.function.module, t.function.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_keyword_routing() {
+        let router = router(2);
+
+        let tools = names(&router.route("Please convert 5 miles to kilometers", None));
+        assert_eq!(tools[0], "math.convert");
+        assert!(tools.len() <= 3);
+        assert_eq!(tools.last().unwrap(), "tools.search_tools");
+
+        let tools = names(&router.route("Remember that I prefer tabs", None));
+        assert_eq!(tools[0], "memory.remember");
+
+        // Nothing relevant only offers the search
+        assert_eq!(
+            names(&router.route("Hello there", None)),
+            vec!["tools.search_tools"]
+        );
+    }
+
+    #[test]
+    fn test_search_loads_tools() {
+        let mut router = router(2);
+        let offered = router.route("Hello there", None);
+
+        let found = names(&router.search("matrix determinant", None, &offered));
+        assert_eq!(found[0], "math.matrix");
+
+        // Loaded tools stay offered on the next messages
+        let tools = names(&router.route("Hello again", None));
+        assert!(tools.contains(&"math.matrix".to_string()));
+    }
+
+    #[test]
+    fn test_embedding_routing() {
+        let mut router = router(1);
+        let count = router.tools.len();
+        let embeddings: Vec<Vec<f32>> = (0..count)
+            .map(|i| {
+                if i == count - 1 {
+                    vec![1.0, 0.0]
+                } else {
+                    vec![0.0, 1.0]
+                }
+            })
+            .collect();
+        router.set_embeddings(embeddings);
+
+        let last = names(&router.tools[count - 1..]);
+        let tools = names(&router.route("Hello there", Some(&[1.0, 0.0])));
+        assert_eq!(tools[0], last[0]);
+
+        router.disable_embeddings();
+        assert!(!router.wants_embeddings());
+        assert_eq!(
+            names(&router.route("Hello there", Some(&[1.0, 0.0]))),
+            vec!["tools.search_tools"]
+        );
+    }
+}
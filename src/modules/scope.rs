@@ -0,0 +1,143 @@
+use crate::{AppError, AppResult};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Which modules and tools a run may use. Tool patterns are globs matched
+/// against `module.tool` (e.g. `fs.read_*`), or against the tool name alone
+/// when they have no dot.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleScope {
+    /// Only these modules, every module when `None`
+    pub modules: Option<Vec<String>>,
+    pub excluded_modules: Vec<String>,
+    /// Only the tools matching one of these, every tool when empty
+    pub allowed_tools: Vec<String>,
+    pub denied_tools: Vec<String>,
+    /// No tools at all
+    pub no_tools: bool,
+    /// Tools that change things (e.g. `git.commit`) are enabled too
+    pub execute: bool,
+}
+
+/// A `ModuleScope` with its patterns compiled
+#[derive(Debug, Clone, Default)]
+pub(super) struct CompiledScope {
+    scope: ModuleScope,
+    allowed: Option<GlobSet>,
+    denied: GlobSet,
+}
+
+fn compile_patterns(patterns: &[String]) -> AppResult<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| AppError::from(&format!("Invalid tool pattern {}: {}", pattern, e)))?;
+        builder.add(glob);
+    }
+
+    builder
+        .build()
+        .map_err(|e| AppError::from(&format!("Invalid tool patterns: {}", e)))
+}
+
+fn matches(patterns: &GlobSet, module: &str, tool: &str) -> bool {
+    patterns.is_match(format!("{}.{}", module, tool)) || patterns.is_match(tool)
+}
+
+impl CompiledScope {
+    pub fn new(scope: ModuleScope) -> AppResult<Self> {
+        let allowed = (!scope.allowed_tools.is_empty())
+            .then(|| compile_patterns(&scope.allowed_tools))
+            .transpose()?;
+        let denied = compile_patterns(&scope.denied_tools)?;
+
+        Ok(Self {
+            scope,
+            allowed,
+            denied,
+        })
+    }
+
+    pub fn scope(&self) -> &ModuleScope {
+        &self.scope
+    }
+
+    pub fn allows_module(&self, module: &str) -> bool {
+        let included = match &self.scope.modules {
+            Some(modules) => modules.iter().any(|m| m == module),
+            None => true,
+        };
+
+        !self.scope.no_tools && included && !self.scope.excluded_modules.iter().any(|m| m == module)
+    }
+
+    pub fn allows_tool(&self, module: &str, tool: &str) -> bool {
+        let allowed = match &self.allowed {
+            Some(allowed) => matches(allowed, module, tool),
+            None => true,
+        };
+
+        self.allows_module(module) && allowed && !matches(&self.denied, module, tool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(scope: ModuleScope) -> CompiledScope {
+        CompiledScope::new(scope).unwrap()
+    }
+
+    #[test]
+    fn test_module_selection() {
+        let only_math = scope(ModuleScope {
+            modules: Some(vec!["math".to_string()]),
+            ..Default::default()
+        });
+        assert!(only_math.allows_tool("math", "eval"));
+        assert!(!only_math.allows_tool("memory", "recall"));
+
+        let no_memory = scope(ModuleScope {
+            excluded_modules: vec!["memory".to_string()],
+            ..Default::default()
+        });
+        assert!(no_memory.allows_module("math"));
+        assert!(!no_memory.allows_module("memory"));
+
+        let none = scope(ModuleScope {
+            no_tools: true,
+            ..Default::default()
+        });
+        assert!(!none.allows_tool("math", "eval"));
+    }
+
+    #[test]
+    fn test_tool_patterns() {
+        let read_only = scope(ModuleScope {
+            allowed_tools: vec!["fs.read_*".to_string(), "recall".to_string()],
+            denied_tools: vec!["fs.read_secret*".to_string()],
+            ..Default::default()
+        });
+
+        assert!(read_only.allows_tool("fs", "read_file"));
+        assert!(!read_only.allows_tool("fs", "write_file"));
+        assert!(!read_only.allows_tool("fs", "read_secrets"));
+        assert!(read_only.allows_tool("memory", "recall"));
+        assert!(!read_only.allows_tool("memory", "forget"));
+
+        let no_math = scope(ModuleScope {
+            denied_tools: vec!["math.*".to_string()],
+            ..Default::default()
+        });
+        assert!(!no_math.allows_tool("math", "eval"));
+        assert!(no_math.allows_tool("memory", "recall"));
+
+        assert!(
+            CompiledScope::new(ModuleScope {
+                denied_tools: vec!["[".to_string()],
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+}
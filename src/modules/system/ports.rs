@@ -0,0 +1,205 @@
+use crate::modules::{ModuleError, ModuleResult};
+use std::net::IpAddr;
+
+/// A socket waiting for connections, and the process holding it when known
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub protocol: &'static str,
+    pub address: IpAddr,
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/// `0100007F:1F90` from `/proc/net/tcp` as 127.0.0.1 and 8080. The address
+/// is printed as 32 bit words in the machine's byte order.
+#[cfg(any(target_os = "linux", test))]
+fn parse_address(text: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = text.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..address.len()).step_by(8) {
+        let word = u32::from_str_radix(address.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let address = match bytes.len() {
+        4 => IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?),
+        16 => IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?),
+        _ => return None,
+    };
+
+    Some((address, port))
+}
+
+/// Listening sockets of a `/proc/net/tcp` or `udp` table with their inode:
+/// TCP sockets in the LISTEN state, UDP sockets with no peer
+#[cfg(any(target_os = "linux", test))]
+fn parse_table(content: &str, protocol: &'static str) -> Vec<(Listener, u64)> {
+    let listening = if protocol == "tcp" { "0A" } else { "07" };
+
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != listening {
+                return None;
+            }
+            let (address, port) = parse_address(fields[1])?;
+            let inode = fields[9].parse().ok()?;
+            let listener = Listener {
+                protocol,
+                address,
+                port,
+                pid: None,
+                process: None,
+            };
+            Some((listener, inode))
+        })
+        .collect()
+}
+
+/// Socket inodes and the process that has them open, for the processes
+/// readable by the current user
+#[cfg(target_os = "linux")]
+fn socket_owners() -> std::collections::HashMap<u64, u32> {
+    let mut owners = std::collections::HashMap::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return owners;
+    };
+
+    let mut pids: Vec<u32> = entries
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort();
+    for pid in pids {
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue;
+        };
+        for fd in fds.filter_map(|fd| fd.ok()) {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse().ok());
+            // Workers started by a server share its sockets, the first
+            // process is kept
+            if let Some(inode) = inode {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+
+    owners
+}
+
+/// Listening TCP and UDP sockets, sorted by port
+#[cfg(target_os = "linux")]
+pub fn listeners() -> ModuleResult<Vec<Listener>> {
+    let mut sockets = Vec::new();
+    for (file, protocol) in [
+        ("tcp", "tcp"),
+        ("tcp6", "tcp"),
+        ("udp", "udp"),
+        ("udp6", "udp"),
+    ] {
+        // IPv6 tables are missing when it is disabled
+        if let Ok(content) = std::fs::read_to_string(format!("/proc/net/{}", file)) {
+            sockets.extend(parse_table(&content, protocol));
+        }
+    }
+    if sockets.is_empty() && std::fs::metadata("/proc/net/tcp").is_err() {
+        return Err(ModuleError::ExecutionError(
+            "Cannot read /proc/net/tcp".to_string(),
+        ));
+    }
+
+    let owners = socket_owners();
+    let mut listeners: Vec<Listener> = sockets
+        .into_iter()
+        .map(|(mut listener, inode)| {
+            listener.pid = owners.get(&inode).copied();
+            listener.process = listener.pid.and_then(|pid| {
+                std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                    .ok()
+                    .map(|name| name.trim().to_string())
+            });
+            listener
+        })
+        .collect();
+    listeners.sort_by_key(|l| (l.port, l.protocol, l.address));
+    listeners.dedup();
+
+    Ok(listeners)
+}
+
+/// Listening sockets from `lsof`, which other systems ship with
+#[cfg(not(target_os = "linux"))]
+pub fn listeners() -> ModuleResult<Vec<Listener>> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-iUDP"])
+        .output()
+        .map_err(|e| ModuleError::ExecutionError(format!("Cannot run lsof: {}", e)))?;
+
+    // COMMAND PID USER FD TYPE DEVICE SIZE/OFF NODE NAME
+    let mut listeners: Vec<Listener> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let protocol = match *fields.get(7)? {
+                "TCP" => "tcp",
+                "UDP" => "udp",
+                _ => return None,
+            };
+            let (address, port) = fields.get(8)?.rsplit_once(':')?;
+            let address = match address.trim_matches(['[', ']']) {
+                "*" => IpAddr::from([0, 0, 0, 0]),
+                address => address.parse().ok()?,
+            };
+            Some(Listener {
+                protocol,
+                address,
+                port: port.parse().ok()?,
+                pid: fields[1].parse().ok(),
+                process: Some(fields[0].to_string()),
+            })
+        })
+        .collect();
+    listeners.sort_by_key(|l| (l.port, l.protocol, l.address));
+    listeners
+        .dedup_by(|a, b| a.port == b.port && a.protocol == b.protocol && a.address == b.address);
+
+    Ok(listeners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table() {
+        let localhost = if cfg!(target_endian = "little") {
+            "0100007F"
+        } else {
+            "7F000001"
+        };
+        let tcp = format!(
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   0: {localhost}:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0000000000000000 100 0 0 10 0\n   1: {localhost}:A2C4 {localhost}:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0000000000000000 20 4 30 10 -1\n"
+        );
+
+        let listeners = parse_table(&tcp, "tcp");
+        assert_eq!(listeners.len(), 1);
+        assert_eq!(listeners[0].0.address.to_string(), "127.0.0.1");
+        assert_eq!(listeners[0].0.port, 8080);
+        assert_eq!(listeners[0].1, 4242);
+
+        let (address, port) = parse_address("00000000000000000000000000000000:0035").unwrap();
+        assert_eq!((address.to_string().as_str(), port), ("::", 53));
+        assert!(parse_address("0100007F").is_none());
+    }
+}
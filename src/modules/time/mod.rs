@@ -0,0 +1,329 @@
+mod parse;
+mod zone;
+
+pub use parse::parse_datetime;
+pub use zone::Zone;
+
+use super::{Module, ModuleError, ModuleResult, Tool, ToolCallFunction, ToolFunction};
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use parse::parse_duration;
+use serde_json::{Value, json};
+
+/// Current date and time, timezones and date arithmetic, so the model never
+/// has to guess them
+pub struct Time;
+
+fn tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: ToolFunction {
+            name: name.to_string(),
+            module: Time::name().to_string(),
+            description: description.to_string(),
+            parameters,
+        },
+    }
+}
+
+/// A date and time with the details the model tends to get wrong
+fn describe(datetime: DateTime<FixedOffset>, zone: &Zone) -> Value {
+    json!({
+        "datetime": datetime.to_rfc3339_opts(SecondsFormat::Secs, false),
+        "date": datetime.format("%Y-%m-%d").to_string(),
+        "time": datetime.format("%H:%M:%S").to_string(),
+        "weekday": datetime.format("%A").to_string(),
+        "timezone": zone.name(),
+        "utc_offset": datetime.format("%:z").to_string(),
+        "unix": datetime.timestamp(),
+    })
+}
+
+/// `2 days 3 hours 4 minutes`, with a `-` when negative
+fn format_seconds(seconds: i64) -> String {
+    let units = [
+        ("day", 86_400),
+        ("hour", 3_600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let mut remaining = seconds.unsigned_abs();
+    let mut parts = Vec::new();
+    for (name, size) in units {
+        let count = remaining / size;
+        remaining %= size;
+        if count > 0 {
+            parts.push(format!(
+                "{} {}{}",
+                count,
+                name,
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+    }
+
+    match (parts.is_empty(), seconds < 0) {
+        (true, _) => "0 seconds".to_string(),
+        (false, true) => format!("-{}", parts.join(" ")),
+        (false, false) => parts.join(" "),
+    }
+}
+
+impl Time {
+    pub fn new() -> Time {
+        Time
+    }
+
+    pub fn name() -> &'static str {
+        "time"
+    }
+
+    fn string_argument<'a>(&self, func: &'a ToolCallFunction, name: &str) -> ModuleResult<&'a str> {
+        func.arguments
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!("Missing '{}' argument", name))
+            })
+    }
+
+    /// The zone of an argument, the local one when it is missing
+    fn zone_argument(&self, func: &ToolCallFunction, name: &str) -> ModuleResult<Zone> {
+        match func.arguments.get(name).and_then(|v| v.as_str()) {
+            Some(zone) => Zone::parse(zone),
+            None => Ok(Zone::local()),
+        }
+    }
+
+    fn datetime_argument(
+        &self,
+        func: &ToolCallFunction,
+        name: &str,
+        zone: &Zone,
+        now: DateTime<Utc>,
+    ) -> ModuleResult<DateTime<FixedOffset>> {
+        match func.arguments.get(name).and_then(|v| v.as_str()) {
+            Some(text) => parse_datetime(text, zone, now),
+            None => Ok(zone.at(now)),
+        }
+    }
+
+    /// Runs a tool as if the current time was `now`
+    fn run_at(&self, func: &ToolCallFunction, now: DateTime<Utc>) -> ModuleResult<Value> {
+        match func.name.as_str() {
+            "now" => {
+                let zone = self.zone_argument(func, "tz")?;
+                Ok(describe(zone.at(now), &zone))
+            }
+            "convert" => {
+                let from = self.zone_argument(func, "from_tz")?;
+                let to = Zone::parse(self.string_argument(func, "to_tz")?)?;
+                let datetime = parse_datetime(self.string_argument(func, "datetime")?, &from, now)?;
+
+                Ok(json!({
+                    "from": describe(datetime, &from),
+                    "to": describe(to.at(datetime.to_utc()), &to),
+                }))
+            }
+            "diff" => {
+                let zone = self.zone_argument(func, "tz")?;
+                let a = parse_datetime(self.string_argument(func, "a")?, &zone, now)?;
+                let b = self.datetime_argument(func, "b", &zone, now)?;
+                let seconds = (b - a).num_seconds();
+                let days = (zone.at(b.to_utc()).date_naive() - zone.at(a.to_utc()).date_naive())
+                    .num_days();
+
+                Ok(json!({
+                    "seconds": seconds,
+                    "hours": seconds as f64 / 3_600.0,
+                    "days": seconds as f64 / 86_400.0,
+                    "calendar_days": days,
+                    "weeks": seconds as f64 / 604_800.0,
+                    "duration": format_seconds(seconds),
+                }))
+            }
+            "add" => {
+                let zone = self.zone_argument(func, "tz")?;
+                let datetime = self.datetime_argument(func, "datetime", &zone, now)?;
+                let span = parse_duration(self.string_argument(func, "duration")?)?;
+
+                Ok(describe(span.add_to(datetime, &zone)?, &zone))
+            }
+            "parse" => {
+                let zone = self.zone_argument(func, "tz")?;
+                let datetime = parse_datetime(self.string_argument(func, "text")?, &zone, now)?;
+
+                Ok(describe(datetime, &zone))
+            }
+            _ => Err(ModuleError::UnknownFunction(func.name.clone())),
+        }
+    }
+}
+
+impl Module for Time {
+    fn name(&self) -> &'static str {
+        Time::name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Gives the current date and time, converts between timezones and computes with dates and durations."
+    }
+
+    fn get_prompt(&self) -> &'static str {
+        r#"
+- **time**: Current date and time, timezones and date arithmetic.
+  - **Rules**:
+    - Never guess the current time or compute dates yourself, always use a tool.
+    - Use `now` for the current date and time, in another timezone with `tz` (e.g. `Asia/Tokyo`, `London`, `PST` or `+05:30`, local by default).
+    - Use `convert` to give a date and time in another timezone.
+    - Use `diff` for the time between two dates (e.g. days until a deadline, `b` is now by default).
+    - Use `add` to add or subtract a duration (e.g. `3 days`, `-2h30m`, `1 month`).
+    - Use `parse` to turn phrases like `next friday at 9am` or `in 3 weeks` into a date.
+    - Dates can be written as `2026-10-20 14:30`, RFC 3339 or phrases like `tomorrow at noon`, `last monday` or `2 hours ago`."#
+    }
+
+    fn run(&self, func: &ToolCallFunction) -> ModuleResult<Value> {
+        self.run_at(func, Utc::now())
+    }
+
+    fn tools(&self) -> Vec<Tool> {
+        let tz = |description: &str| json!({ "type": "string", "description": format!("{} (e.g. 'Europe/Paris', 'Tokyo', 'PST' or '+02:00'), local by default", description) });
+        let datetime = |description: &str| json!({ "type": "string", "description": format!("{} (e.g. '2026-10-20 14:30' or 'tomorrow at 9am')", description) });
+
+        vec![
+            tool(
+                "now",
+                "Gives the current date, time and weekday",
+                json!({
+                    "type": "object",
+                    "properties": { "tz": tz("Timezone") }
+                }),
+            ),
+            tool(
+                "convert",
+                "Converts a date and time from one timezone to another",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "datetime": datetime("The date and time"),
+                        "from_tz": tz("Timezone of the date and time"),
+                        "to_tz": tz("Timezone to convert to")
+                    },
+                    "required": ["datetime", "to_tz"]
+                }),
+            ),
+            tool(
+                "diff",
+                "Computes the time from date a to date b",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "a": datetime("The start"),
+                        "b": datetime("The end, now by default"),
+                        "tz": tz("Timezone of the dates")
+                    },
+                    "required": ["a"]
+                }),
+            ),
+            tool(
+                "add",
+                "Adds a duration to a date and time, negative durations subtract",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "datetime": datetime("The date and time, now by default"),
+                        "duration": { "type": "string", "description": "The duration (e.g. '3 days', '2h30m', '-1 week' or '1 month')" },
+                        "tz": tz("Timezone of the date")
+                    },
+                    "required": ["duration"]
+                }),
+            ),
+            tool(
+                "parse",
+                "Reads a date written in words, such as 'next friday at 9am', 'in 3 weeks' or 'December 24th'",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string", "description": "The date in words" },
+                        "tz": tz("Timezone of the date")
+                    },
+                    "required": ["text"]
+                }),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, arguments: Value) -> ModuleResult<Value> {
+        let now = "2026-10-18T08:00:00Z".parse().unwrap();
+        Time::new().run_at(
+            &ToolCallFunction {
+                name: name.to_string(),
+                module: Time::name().to_string(),
+                arguments,
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn test_now_and_convert() {
+        let now = call("now", json!({ "tz": "Tokyo" })).unwrap();
+        assert_eq!(now["datetime"], json!("2026-10-18T17:00:00+09:00"));
+        assert_eq!(now["weekday"], json!("Sunday"));
+        assert_eq!(now["timezone"], json!("Asia/Tokyo"));
+
+        let converted = call(
+            "convert",
+            json!({ "datetime": "2026-10-20 09:00", "from_tz": "New York", "to_tz": "Europe/Paris" }),
+        )
+        .unwrap();
+        assert_eq!(converted["to"]["time"], json!("15:00:00"));
+        assert_eq!(converted["to"]["utc_offset"], json!("+02:00"));
+
+        assert!(
+            call(
+                "convert",
+                json!({ "datetime": "2026-10-20", "to_tz": "Mars" })
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_diff_and_add() {
+        let diff = call(
+            "diff",
+            json!({ "a": "2026-10-18 10:00", "b": "2026-12-25", "tz": "UTC" }),
+        )
+        .unwrap();
+        assert_eq!(diff["calendar_days"], json!(68));
+        assert_eq!(diff["duration"], json!("67 days 14 hours"));
+
+        let added = call(
+            "add",
+            json!({ "datetime": "2026-10-18 10:00", "duration": "-2h30m", "tz": "UTC" }),
+        )
+        .unwrap();
+        assert_eq!(added["datetime"], json!("2026-10-18T07:30:00+00:00"));
+
+        let parsed = call(
+            "parse",
+            json!({ "text": "next friday at 9am", "tz": "UTC" }),
+        )
+        .unwrap();
+        assert_eq!(parsed["date"], json!("2026-10-23"));
+        assert_eq!(parsed["weekday"], json!("Friday"));
+    }
+
+    #[test]
+    fn test_format_seconds() {
+        assert_eq!(format_seconds(0), "0 seconds");
+        assert_eq!(format_seconds(90_061), "1 day 1 hour 1 minute 1 second");
+        assert_eq!(format_seconds(-7_200), "-2 hours");
+    }
+}
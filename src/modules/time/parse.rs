@@ -0,0 +1,502 @@
+use super::zone::Zone;
+use crate::modules::{ModuleError, ModuleResult};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc,
+    Weekday,
+};
+
+/// Formats tried for dates, the ones without a year use the current year
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y", "%B %d %Y", "%d %B %Y", "%b %d %Y", "%d %b %Y",
+];
+const DAY_FORMATS: &[&str] = &["%B %d", "%d %B", "%b %d", "%d %b"];
+
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+
+const WEEKDAYS: &[(Weekday, &[&str])] = &[
+    (Weekday::Mon, &["monday", "mon"]),
+    (Weekday::Tue, &["tuesday", "tue", "tues"]),
+    (Weekday::Wed, &["wednesday", "wed"]),
+    (Weekday::Thu, &["thursday", "thu", "thur", "thurs"]),
+    (Weekday::Fri, &["friday", "fri"]),
+    (Weekday::Sat, &["saturday", "sat"]),
+    (Weekday::Sun, &["sunday", "sun"]),
+];
+
+/// A duration with its calendar parts kept apart, so `1 month` from January
+/// 31st is the end of February and `1 day` keeps the time across a daylight
+/// saving change
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub months: i64,
+    pub days: i64,
+    pub seconds: i64,
+}
+
+impl Span {
+    fn negate(self) -> Span {
+        Span {
+            months: -self.months,
+            days: -self.days,
+            seconds: -self.seconds,
+        }
+    }
+
+    fn is_calendar_only(&self) -> bool {
+        self.seconds == 0
+    }
+
+    fn add_to_date(&self, date: NaiveDateTime) -> Option<NaiveDateTime> {
+        let date = match u32::try_from(self.months.unsigned_abs()).ok()? {
+            0 => date,
+            months if self.months > 0 => date.checked_add_months(Months::new(months))?,
+            months => date.checked_sub_months(Months::new(months))?,
+        };
+
+        date.checked_add_signed(TimeDelta::try_days(self.days)?)
+    }
+
+    /// Adds the span to a date and time of `zone`: months and days on its
+    /// wall clock, then the hours, minutes and seconds
+    pub fn add_to(
+        &self,
+        datetime: DateTime<FixedOffset>,
+        zone: &Zone,
+    ) -> ModuleResult<DateTime<FixedOffset>> {
+        let out_of_range = || ModuleError::ExecutionError("The date is out of range".into());
+
+        let local = zone.at(datetime.to_utc()).naive_local();
+        let shifted = zone.at_local(self.add_to_date(local).ok_or_else(out_of_range)?)?;
+
+        let seconds = TimeDelta::try_seconds(self.seconds).ok_or_else(out_of_range)?;
+        let instant = shifted
+            .to_utc()
+            .checked_add_signed(seconds)
+            .ok_or_else(out_of_range)?;
+
+        Ok(zone.at(instant))
+    }
+}
+
+/// Seconds in a unit, or months for the calendar units
+enum Unit {
+    Seconds(i64),
+    Days(i64),
+    Months(i64),
+}
+
+fn unit(word: &str) -> Option<Unit> {
+    Some(match word {
+        "s" | "sec" | "secs" | "second" | "seconds" => Unit::Seconds(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Unit::Seconds(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Unit::Seconds(3600),
+        "d" | "day" | "days" => Unit::Days(1),
+        "w" | "wk" | "wks" | "week" | "weeks" => Unit::Days(7),
+        "mo" | "month" | "months" => Unit::Months(1),
+        "y" | "yr" | "yrs" | "year" | "years" => Unit::Months(12),
+        _ => return None,
+    })
+}
+
+/// Runs of digits and runs of letters, e.g. `2h30m` is `2 h 30 m`
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut previous: Option<bool> = None;
+
+    for c in text.chars() {
+        let is_number = c.is_ascii_digit() || c == '.';
+        if !is_number && !c.is_alphabetic() {
+            previous = None;
+            continue;
+        }
+
+        match tokens.last_mut() {
+            Some(token) if previous == Some(is_number) => token.push(c),
+            _ => tokens.push(c.to_string()),
+        }
+        previous = Some(is_number);
+    }
+
+    tokens
+}
+
+/// Parses durations such as `3 days`, `2h30m`, `-1 week`, `an hour` or
+/// `1 year, 2 months and 3 days`
+pub fn parse_duration(text: &str) -> ModuleResult<Span> {
+    let invalid = || {
+        ModuleError::InvalidFunctionInput(format!(
+            "Invalid duration '{}', use e.g. '2 hours 30 minutes', '3d' or '-1 week'",
+            text
+        ))
+    };
+
+    let text = text.trim().to_lowercase();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(&text)),
+    };
+
+    let too_long =
+        || ModuleError::InvalidFunctionInput(format!("The duration '{}' is too long", text));
+    // `amount` of a unit of `size`, rounded, when it fits in an i64
+    let scaled = |amount: f64, size: i64| {
+        let value = (amount * size as f64).round();
+        if value.abs() < i64::MAX as f64 {
+            Ok(value as i64)
+        } else {
+            Err(too_long())
+        }
+    };
+
+    let mut span = Span::default();
+    let mut words = tokens(text).into_iter().filter(|w| w != "and");
+    let mut parsed = false;
+    while let Some(word) = words.next() {
+        let amount: f64 = match word.as_str() {
+            "a" | "an" | "one" => 1.0,
+            number => number.parse().map_err(|_| invalid())?,
+        };
+        let unit = words.next().and_then(|w| unit(&w)).ok_or_else(invalid)?;
+
+        let (total, value) = match unit {
+            Unit::Seconds(seconds) => (&mut span.seconds, scaled(amount, seconds)?),
+            // A fraction of a day is counted in seconds
+            Unit::Days(days) if amount.fract() == 0.0 => (&mut span.days, scaled(amount, days)?),
+            Unit::Days(days) => (&mut span.seconds, scaled(amount, days * 86_400)?),
+            Unit::Months(months) if amount.fract() == 0.0 => {
+                (&mut span.months, scaled(amount, months)?)
+            }
+            Unit::Months(_) => {
+                return Err(ModuleError::InvalidFunctionInput(format!(
+                    "Months and years must be whole numbers in '{}'",
+                    text
+                )));
+            }
+        };
+        *total = total.checked_add(value).ok_or_else(too_long)?;
+        parsed = true;
+    }
+
+    if !parsed {
+        return Err(invalid());
+    }
+
+    Ok(if negative { span.negate() } else { span })
+}
+
+/// `in 3 days`, `3 days ago`, `2 weeks from now` or `1 hour later`
+fn parse_relative(text: &str) -> Option<Span> {
+    if let Some(duration) = text.strip_prefix("in ") {
+        return parse_duration(duration).ok();
+    }
+    if let Some(duration) = text.strip_suffix(" ago") {
+        return parse_duration(duration).ok().map(Span::negate);
+    }
+
+    let duration = text
+        .strip_suffix(" from now")
+        .or_else(|| text.strip_suffix(" later"))?;
+    parse_duration(duration).ok()
+}
+
+/// `20th` is `20`, leaving words like `august` alone
+fn strip_ordinals(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            let suffix = &word[digits.len()..];
+            if !digits.is_empty()
+                && digits.chars().all(|c| c.is_ascii_digit())
+                && ["st", "nd", "rd", "th"].contains(&suffix)
+            {
+                digits
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(_, names)| names.contains(&word))
+        .map(|(day, _)| *day)
+}
+
+/// A date from `today`, `next friday`, `last month`, `2026-10-20`,
+/// `october 20th` and the like
+fn parse_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let midnight = today.and_time(NaiveTime::MIN);
+    let shift = |span: Span| span.add_to_date(midnight).map(|d| d.date());
+
+    match text {
+        "today" | "tonight" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        "yesterday" => return today.pred_opt(),
+        "day after tomorrow" | "the day after tomorrow" => {
+            return shift(Span {
+                days: 2,
+                ..Default::default()
+            });
+        }
+        "day before yesterday" | "the day before yesterday" => {
+            return shift(Span {
+                days: -2,
+                ..Default::default()
+            });
+        }
+        _ => {}
+    }
+
+    if let Some(span) = parse_relative(text).filter(Span::is_calendar_only) {
+        return shift(span);
+    }
+
+    let (direction, rest) = match text.split_once(' ') {
+        Some(("next", rest)) => (1, rest),
+        Some(("last", rest)) => (-1, rest),
+        Some(("this" | "coming", rest)) => (0, rest),
+        _ => (0, text),
+    };
+
+    if let Some(day) = weekday(rest) {
+        let ahead = (day.num_days_from_monday() as i64
+            - today.weekday().num_days_from_monday() as i64)
+            .rem_euclid(7);
+        let days = match direction {
+            // The coming one, today included
+            0 => ahead,
+            1 if ahead == 0 => 7,
+            1 => ahead,
+            _ if ahead == 0 => -7,
+            _ => ahead - 7,
+        };
+        return shift(Span {
+            days,
+            ..Default::default()
+        });
+    }
+
+    if direction != 0 {
+        let unit = unit(rest)?;
+        let span = match unit {
+            Unit::Days(days) => Span {
+                days: days * direction,
+                ..Default::default()
+            },
+            Unit::Months(months) => Span {
+                months: months * direction,
+                ..Default::default()
+            },
+            Unit::Seconds(_) => return None,
+        };
+        return shift(span);
+    }
+
+    let text = strip_ordinals(&text.replace(',', ""));
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(&text, format).ok())
+        .or_else(|| {
+            let with_year = format!("{} {}", text, today.year());
+            DAY_FORMATS.iter().find_map(|format| {
+                NaiveDate::parse_from_str(&with_year, &format!("{} %Y", format)).ok()
+            })
+        })
+}
+
+/// A time of day: `14:30`, `9am`, `9:30 pm`, `noon` or `midnight`
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    match text {
+        "noon" | "midday" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => {}
+    }
+
+    let text = text.replace(['.', ' '], "");
+    let (clock, afternoon) = match (text.strip_suffix("am"), text.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(false)),
+        (_, Some(clock)) => (clock, Some(true)),
+        _ => (text.as_str(), None),
+    };
+
+    let mut parts = clock.split(':');
+    let hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let second: u32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    // A bare number is a time only with am or pm
+    if parts.next().is_some() || (afternoon.is_none() && !clock.contains(':')) {
+        return None;
+    }
+
+    let hour = match afternoon {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+/// Parses a date and time written as `2026-10-20 14:30`, RFC 3339, or a
+/// phrase such as `now`, `tomorrow at 9am`, `next friday`, `in 3 days` or
+/// `2 hours ago`. Times without an offset are read in `zone`, dates without
+/// a time are at midnight.
+pub fn parse_datetime(
+    text: &str,
+    zone: &Zone,
+    now: DateTime<Utc>,
+) -> ModuleResult<DateTime<FixedOffset>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text.trim()) {
+        return Ok(datetime);
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc2822(text.trim()) {
+        return Ok(datetime);
+    }
+
+    let text = text
+        .trim()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let now = zone.at(now);
+
+    if text == "now" {
+        return Ok(now);
+    }
+    if let Some(span) = parse_relative(&text) {
+        return span.add_to(now, zone);
+    }
+    if let Some(datetime) = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&text.to_uppercase(), format).ok())
+    {
+        return zone.at_local(datetime);
+    }
+
+    let today = now.date_naive();
+    let (date, time) = match text.split_once(" at ") {
+        Some((date, time)) => (parse_date(date, today), parse_time(time)),
+        None => match parse_time(&text) {
+            Some(time) => (Some(today), Some(time)),
+            // `friday 5pm`, `2026-10-20 9:30 am`
+            None => {
+                let words: Vec<&str> = text.split(' ').collect();
+                (1..words.len().min(3))
+                    .find_map(|n| {
+                        let (date, time) = words.split_at(words.len() - n);
+                        Some((
+                            Some(parse_date(&date.join(" "), today)?),
+                            Some(parse_time(&time.join(" "))?),
+                        ))
+                    })
+                    .unwrap_or_else(|| (parse_date(&text, today), Some(NaiveTime::MIN)))
+            }
+        },
+    };
+
+    match (date, time) {
+        (Some(date), Some(time)) => zone.at_local(date.and_time(time)),
+        _ => Err(ModuleError::InvalidFunctionInput(format!(
+            "Cannot read the date '{}', use e.g. '2026-10-20 14:30', 'tomorrow at 9am', 'next friday' or 'in 3 days'",
+            text
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sunday 2026-10-18 10:00 in Paris
+    fn now() -> DateTime<Utc> {
+        "2026-10-18T08:00:00Z".parse().unwrap()
+    }
+
+    fn parse(text: &str) -> String {
+        let paris = Zone::parse("Europe/Paris").unwrap();
+        parse_datetime(text, &paris, now()).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let span = |months, days, seconds| Span {
+            months,
+            days,
+            seconds,
+        };
+
+        assert_eq!(parse_duration("3 days").unwrap(), span(0, 3, 0));
+        assert_eq!(parse_duration("2h30m").unwrap(), span(0, 0, 9000));
+        assert_eq!(parse_duration("-1 week").unwrap(), span(0, -7, 0));
+        assert_eq!(parse_duration("an hour").unwrap(), span(0, 0, 3600));
+        assert_eq!(parse_duration("1.5 days").unwrap(), span(0, 0, 129_600));
+        assert_eq!(
+            parse_duration("1 year, 2 months and 3 days").unwrap(),
+            span(14, 3, 0)
+        );
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("1.5 months").is_err());
+        assert!(parse_duration("10000000000000000000 weeks").is_err());
+        assert!(parse_duration("9223372036854775807 seconds 9223372036854775807 seconds").is_err());
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        assert_eq!(parse("now"), "2026-10-18T10:00:00+02:00");
+        assert_eq!(parse("2026-10-20 14:30"), "2026-10-20T14:30:00+02:00");
+        assert_eq!(parse("2026-10-20T14:30"), "2026-10-20T14:30:00+02:00");
+        assert_eq!(parse("2026-10-20T14:30:00Z"), "2026-10-20T14:30:00+00:00");
+        assert_eq!(parse("tomorrow at 9am"), "2026-10-19T09:00:00+02:00");
+        assert_eq!(parse("Tomorrow 9:30 pm"), "2026-10-19T21:30:00+02:00");
+        assert_eq!(parse("today"), "2026-10-18T00:00:00+02:00");
+        assert_eq!(parse("noon"), "2026-10-18T12:00:00+02:00");
+        assert_eq!(parse("in 3 days"), "2026-10-21T10:00:00+02:00");
+        assert_eq!(parse("2 hours ago"), "2026-10-18T08:00:00+02:00");
+        assert_eq!(parse("friday 5pm"), "2026-10-23T17:00:00+02:00");
+        assert_eq!(parse("next sunday"), "2026-10-25T00:00:00+02:00");
+        assert_eq!(parse("sunday"), "2026-10-18T00:00:00+02:00");
+        assert_eq!(parse("last monday"), "2026-10-12T00:00:00+02:00");
+        assert_eq!(parse("next month"), "2026-11-18T00:00:00+01:00");
+        assert_eq!(parse("December 24th at noon"), "2026-12-24T12:00:00+01:00");
+        assert_eq!(parse("1 Jan 2027"), "2027-01-01T00:00:00+01:00");
+
+        let paris = Zone::parse("Europe/Paris").unwrap();
+        assert!(parse_datetime("someday", &paris, now()).is_err());
+        assert!(parse_datetime("tomorrow at 25:00", &paris, now()).is_err());
+    }
+
+    #[test]
+    fn test_span_keeps_wall_clock() {
+        let paris = Zone::parse("Europe/Paris").unwrap();
+        let start = parse_datetime("2026-10-24 09:00", &paris, now()).unwrap();
+
+        // The clocks go back on October 25th, a day later is still 9:00
+        let day = parse_duration("1 day")
+            .unwrap()
+            .add_to(start, &paris)
+            .unwrap();
+        assert_eq!(day.to_rfc3339(), "2026-10-25T09:00:00+01:00");
+        let hours = parse_duration("24h")
+            .unwrap()
+            .add_to(start, &paris)
+            .unwrap();
+        assert_eq!(hours.to_rfc3339(), "2026-10-25T08:00:00+01:00");
+
+        let end_of_january = parse_datetime("2027-01-31", &paris, now()).unwrap();
+        let month = parse_duration("1 month")
+            .unwrap()
+            .add_to(end_of_january, &paris)
+            .unwrap();
+        assert_eq!(month.to_rfc3339(), "2027-02-28T00:00:00+01:00");
+    }
+}
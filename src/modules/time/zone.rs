@@ -0,0 +1,178 @@
+use crate::modules::{ModuleError, ModuleResult};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::{TZ_VARIANTS, Tz};
+
+/// Common abbreviations, mapped to a zone observing them so daylight saving
+/// time follows the date
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("est", "America/New_York"),
+    ("edt", "America/New_York"),
+    ("cst", "America/Chicago"),
+    ("cdt", "America/Chicago"),
+    ("mst", "America/Denver"),
+    ("mdt", "America/Denver"),
+    ("pst", "America/Los_Angeles"),
+    ("pdt", "America/Los_Angeles"),
+    ("bst", "Europe/London"),
+    ("cet", "Europe/Paris"),
+    ("cest", "Europe/Paris"),
+    ("eet", "Europe/Athens"),
+    ("eest", "Europe/Athens"),
+    ("ist", "Asia/Kolkata"),
+    ("jst", "Asia/Tokyo"),
+    ("aest", "Australia/Sydney"),
+    ("aedt", "Australia/Sydney"),
+];
+
+/// A timezone: an IANA zone with its daylight saving rules, or a fixed offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+/// `+2`, `+02`, `+0200`, `+02:00` or `-5:30`
+fn parse_offset(text: &str) -> Option<FixedOffset> {
+    let (sign, digits) = match text.chars().next()? {
+        '+' => (1, &text[1..]),
+        '-' => (-1, &text[1..]),
+        _ => return None,
+    };
+
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+        None => (digits, "0"),
+    };
+    let (hours, minutes) = (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?);
+    if hours > 24 || minutes >= 60 {
+        return None;
+    }
+    let seconds = hours * 3600 + minutes * 60;
+
+    FixedOffset::east_opt(sign * seconds)
+}
+
+impl Zone {
+    /// The timezone of this machine
+    pub fn local() -> Zone {
+        iana_time_zone::get_timezone()
+            .ok()
+            .and_then(|name| name.parse::<Tz>().ok())
+            .map(Zone::Named)
+            .unwrap_or_else(|| Zone::Fixed(*Local::now().offset()))
+    }
+
+    /// A zone from an IANA name (`Europe/Paris`), a city (`Tokyo`), an
+    /// abbreviation (`PST`), an offset (`+05:30`, `UTC-3`) or `local`
+    pub fn parse(text: &str) -> ModuleResult<Zone> {
+        let name = text.trim().to_lowercase();
+
+        match name.as_str() {
+            "" | "local" => return Ok(Zone::local()),
+            "utc" | "gmt" | "z" | "zulu" => return Ok(Zone::Named(Tz::UTC)),
+            _ => {}
+        }
+
+        let offset = name
+            .strip_prefix("utc")
+            .or_else(|| name.strip_prefix("gmt"))
+            .unwrap_or(&name);
+        if let Some(offset) = parse_offset(offset) {
+            return Ok(Zone::Fixed(offset));
+        }
+
+        if let Some((_, zone)) = ABBREVIATIONS.iter().find(|(a, _)| *a == name) {
+            return zone
+                .parse()
+                .map(Zone::Named)
+                .map_err(|e: chrono_tz::ParseError| ModuleError::ExecutionError(e.to_string()));
+        }
+
+        // IANA names first, then cities (`new york` is `America/New_York`)
+        let name = name.replace(' ', "_");
+        TZ_VARIANTS
+            .iter()
+            .find(|tz| tz.name().eq_ignore_ascii_case(&name))
+            .or_else(|| {
+                TZ_VARIANTS.iter().find(|tz| {
+                    tz.name()
+                        .rsplit('/')
+                        .next()
+                        .is_some_and(|city| city.eq_ignore_ascii_case(&name))
+                })
+            })
+            .map(|tz| Zone::Named(*tz))
+            .ok_or_else(|| {
+                ModuleError::InvalidFunctionInput(format!(
+                    "Unknown timezone '{}', use an IANA name like 'Europe/Paris', a city like 'Tokyo' or an offset like '+02:00'",
+                    text
+                ))
+            })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Zone::Named(tz) => tz.name().to_string(),
+            Zone::Fixed(offset) => format!("UTC{}", offset),
+        }
+    }
+
+    /// An instant as the wall clock time of this zone
+    pub fn at(&self, instant: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Named(tz) => instant.with_timezone(tz).fixed_offset(),
+            Zone::Fixed(offset) => instant.with_timezone(offset),
+        }
+    }
+
+    /// The instant a wall clock time of this zone stands for. A time repeated
+    /// when the clocks go back is taken the first time.
+    pub fn at_local(&self, local: NaiveDateTime) -> ModuleResult<DateTime<FixedOffset>> {
+        let instant = match self {
+            Zone::Named(tz) => tz.from_local_datetime(&local).map(|d| d.fixed_offset()),
+            Zone::Fixed(offset) => offset.from_local_datetime(&local),
+        };
+
+        instant.earliest().ok_or_else(|| {
+            ModuleError::ExecutionError(format!(
+                "{} does not exist in {}, the clocks skip it",
+                local,
+                self.name()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zone() {
+        assert_eq!(Zone::parse("Europe/Paris").unwrap().name(), "Europe/Paris");
+        assert_eq!(Zone::parse("tokyo").unwrap().name(), "Asia/Tokyo");
+        assert_eq!(Zone::parse("New York").unwrap().name(), "America/New_York");
+        assert_eq!(Zone::parse("PST").unwrap().name(), "America/Los_Angeles");
+        assert_eq!(Zone::parse("utc").unwrap().name(), "UTC");
+        assert_eq!(Zone::parse("+05:30").unwrap().name(), "UTC+05:30");
+        assert_eq!(Zone::parse("UTC-3").unwrap().name(), "UTC-03:00");
+        assert_eq!(Zone::parse("+0845").unwrap().name(), "UTC+08:45");
+        assert!(Zone::parse("Atlantis").is_err());
+        assert!(Zone::parse("+99999999").is_err());
+        assert!(Zone::parse("+25").is_err());
+    }
+
+    #[test]
+    fn test_daylight_saving_gaps() {
+        let paris = Zone::parse("Europe/Paris").unwrap();
+        let skipped = "2026-03-29T02:30:00".parse::<NaiveDateTime>().unwrap();
+        assert!(paris.at_local(skipped).is_err());
+
+        let summer = "2026-07-01T12:00:00".parse::<NaiveDateTime>().unwrap();
+        assert_eq!(
+            paris.at_local(summer).unwrap().to_rfc3339(),
+            "2026-07-01T12:00:00+02:00"
+        );
+    }
+}
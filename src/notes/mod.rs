@@ -0,0 +1,257 @@
+mod todos;
+
+pub use todos::{Todo, TodoList, parse_due, todos_path};
+
+use crate::{AppError, AppResult, memory::keywords, utils::data_dir};
+use chrono::{DateTime, Local, NaiveDate};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Titles taken from the text are cut at this length
+const MAX_TITLE_CHARS: usize = 60;
+
+/// A markdown note. Notes the user writes in the directory are read too,
+/// the title and tags lines being optional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    /// File name in the notes directory
+    pub file: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub date: NaiveDate,
+    pub text: String,
+}
+
+pub fn notes_dir() -> PathBuf {
+    data_dir().join("notes")
+}
+
+/// `Groceries for Sunday!` as `groceries-for-sunday`
+fn slug(title: &str) -> String {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    let mut slug = String::new();
+    for word in words {
+        if !slug.is_empty() && slug.len() + word.len() >= 40 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+
+    if slug.is_empty() {
+        "note".to_string()
+    } else {
+        slug
+    }
+}
+
+impl Note {
+    /// Reads `# Title`, then an optional `Tags: a, b` line, then the text
+    fn parse(file: &str, content: &str, date: NaiveDate) -> Note {
+        let mut lines = content.lines().peekable();
+        while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+
+        let stem = file.strip_suffix(".md").unwrap_or(file);
+        let title = match lines.next_if(|line| line.starts_with("# ")) {
+            Some(line) => line[2..].trim().to_string(),
+            None => stem.to_string(),
+        };
+        while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+
+        let tags = match lines.next_if(|line| line.starts_with("Tags:")) {
+            Some(line) => line[5..]
+                .split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Note {
+            file: file.to_string(),
+            title,
+            tags,
+            date,
+            text: lines.collect::<Vec<_>>().join("\n").trim().to_string(),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut content = format!("# {}\n\n", self.title);
+        if !self.tags.is_empty() {
+            content.push_str(&format!("Tags: {}\n\n", self.tags.join(", ")));
+        }
+        if !self.text.is_empty() {
+            content.push_str(&self.text);
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Saves a new note as `<date>-<slug>.md`. Without a title, the first
+    /// line of the text is used.
+    pub fn add(dir: &Path, text: &str, title: Option<&str>, tags: &[String]) -> AppResult<Note> {
+        let text = text.trim();
+        let (title, text) = match title.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(title) => (title.to_string(), text.to_string()),
+            None => {
+                let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+                let first = first.trim().trim_start_matches('#').trim();
+                match first.char_indices().nth(MAX_TITLE_CHARS) {
+                    Some((end, _)) => (format!("{}...", &first[..end]), text.to_string()),
+                    None => (first.to_string(), rest.trim().to_string()),
+                }
+            }
+        };
+        if title.is_empty() {
+            return Err(AppError::from("The note is empty"));
+        }
+
+        fs::create_dir_all(dir)?;
+        let date = Local::now().date_naive();
+        let base = format!("{}-{}", date.format("%Y-%m-%d"), slug(&title));
+        let mut file = format!("{}.md", base);
+        let mut n = 2;
+        while dir.join(&file).exists() {
+            file = format!("{}-{}.md", base, n);
+            n += 1;
+        }
+
+        let note = Note {
+            file,
+            title,
+            tags: tags.to_vec(),
+            date,
+            text,
+        };
+        fs::write(dir.join(&note.file), note.to_markdown())?;
+        Ok(note)
+    }
+
+    /// Every note of the directory, newest first
+    pub fn read_all(dir: &Path) -> AppResult<Vec<Note>> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut notes = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(file) = path.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            if !file.ends_with(".md") || !path.is_file() {
+                continue;
+            }
+
+            // The date of the file name, or when it was last changed
+            let date = file
+                .get(..10)
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .or_else(|| {
+                    let modified = fs::metadata(&path).ok()?.modified().ok()?;
+                    Some(DateTime::<Local>::from(modified).date_naive())
+                })
+                .unwrap_or_default();
+            notes.push(Note::parse(file, &fs::read_to_string(&path)?, date));
+        }
+
+        notes.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| b.file.cmp(&a.file)));
+        Ok(notes)
+    }
+
+    /// Notes sharing words with the query, best first. Title and tags count
+    /// double. An empty query gives the latest notes.
+    pub fn search<'a>(notes: &'a [Note], query: &str, limit: usize) -> Vec<&'a Note> {
+        let query = keywords(query);
+        if query.is_empty() {
+            return notes.iter().take(limit).collect();
+        }
+
+        let mut scored: Vec<(usize, &Note)> = notes
+            .iter()
+            .map(|note| {
+                let title = keywords(&note.title);
+                let text = keywords(&note.text);
+                let score = query
+                    .iter()
+                    .map(|word| {
+                        2 * title.contains(word) as usize
+                            + 2 * note.tags.contains(word) as usize
+                            + text.contains(word) as usize
+                    })
+                    .sum();
+                (score, note)
+            })
+            .filter(|(score, _)| *score > 0)
+            .collect();
+
+        // Stable sort, so equal scores keep the newest note first
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().take(limit).map(|(_, n)| n).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_note() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let note = Note::parse(
+            "2026-10-18-groceries.md",
+            "# Groceries\n\nTags: Shopping, home\n\nmilk\neggs\n",
+            date,
+        );
+        assert_eq!(note.title, "Groceries");
+        assert_eq!(note.tags, ["shopping", "home"]);
+        assert_eq!(note.text, "milk\neggs");
+        assert_eq!(Note::parse(&note.file, &note.to_markdown(), date), note);
+
+        let plain = Note::parse("ideas.md", "just text", date);
+        assert_eq!(
+            (plain.title.as_str(), plain.text.as_str()),
+            ("ideas", "just text")
+        );
+    }
+
+    #[test]
+    fn test_add_and_search() {
+        let dir = std::env::temp_dir().join(format!("jarvis-notes-{}", std::process::id()));
+
+        let first = Note::add(&dir, "Buy milk and eggs\nfor Sunday", None, &[]).unwrap();
+        assert_eq!(first.title, "Buy milk and eggs");
+        assert_eq!(first.text, "for Sunday");
+        assert!(first.file.ends_with("-buy-milk-and-eggs.md"));
+
+        let second = Note::add(
+            &dir,
+            "The router password is on the fridge",
+            Some("Buy milk and eggs"),
+            &["home".to_string()],
+        )
+        .unwrap();
+        assert!(second.file.ends_with("-buy-milk-and-eggs-2.md"));
+        assert!(Note::add(&dir, "  ", None, &[]).is_err());
+
+        let notes = Note::read_all(&dir).unwrap();
+        assert_eq!(notes.len(), 2);
+        let found = Note::search(&notes, "where is the router password?", 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].file, second.file);
+        assert_eq!(Note::search(&notes, "home", 5)[0].tags, ["home"]);
+        assert_eq!(Note::search(&notes, "", 1).len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    AppResult,
+    modules::{ModuleResult, Zone, parse_datetime},
+    utils::{data_dir, write_atomic},
+};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Something to do, with an optional due date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub id: u64,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    pub created_at: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub done_at: Option<DateTime<Local>>,
+}
+
+/// On-disk list of todos, done ones included
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TodoList {
+    next_id: u64,
+    pub todos: Vec<Todo>,
+}
+
+pub fn todos_path() -> PathBuf {
+    data_dir().join("todos.json")
+}
+
+/// A due date written as `2026-10-20`, `tomorrow`, `friday`, `in 3 days`...
+pub fn parse_due(text: &str) -> ModuleResult<NaiveDate> {
+    Ok(parse_datetime(text, &Zone::local(), Utc::now())?.date_naive())
+}
+
+impl TodoList {
+    /// Loads the list, empty if nothing was added yet
+    pub fn load(path: &Path) -> AppResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    /// Adds a todo and returns its id
+    pub fn add(&mut self, text: &str, due: Option<NaiveDate>) -> u64 {
+        self.next_id += 1;
+        self.todos.push(Todo {
+            id: self.next_id,
+            text: text.trim().to_string(),
+            due,
+            created_at: Local::now(),
+            done_at: None,
+        });
+
+        self.next_id
+    }
+
+    /// Marks a todo as done, keeping when it was first completed
+    pub fn complete(&mut self, id: u64) -> Option<&Todo> {
+        let todo = self.todos.iter_mut().find(|t| t.id == id)?;
+        todo.done_at.get_or_insert_with(Local::now);
+        Some(todo)
+    }
+
+    /// Todos left to do, the ones due first, then the ones without a date
+    pub fn open(&self) -> Vec<&Todo> {
+        let mut open: Vec<&Todo> = self.todos.iter().filter(|t| t.done_at.is_none()).collect();
+        open.sort_by_key(|t| (t.due.is_none(), t.due, t.id));
+        open
+    }
+
+    /// Todos left to do that are due on `date` or earlier
+    pub fn due_by(&self, date: NaiveDate) -> Vec<&Todo> {
+        self.open()
+            .into_iter()
+            .filter(|t| t.due.is_some_and(|due| due <= date))
+            .collect()
+    }
+}
+
+impl Todo {
+    /// When it is due, relative to `today`, e.g. `2026-10-20 (overdue)`
+    pub fn due_label(&self, today: NaiveDate) -> String {
+        match self.due {
+            None => String::new(),
+            Some(due) if self.done_at.is_some() => due.to_string(),
+            Some(due) if due < today => format!("{} (overdue)", due),
+            Some(due) if due == today => format!("{} (today)", due),
+            Some(due) => due.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_todo_list() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let mut list = TodoList::default();
+        let bank = list.add(" Call the bank ", Some(today));
+        let taxes = list.add("File taxes", today.pred_opt());
+        let books = list.add("Sort books", None);
+        list.add("Plan trip", today.succ_opt());
+
+        assert_eq!(list.todos[0].text, "Call the bank");
+        let open: Vec<u64> = list.open().iter().map(|t| t.id).collect();
+        assert_eq!(open, [taxes, bank, 4, books]);
+
+        let due: Vec<u64> = list.due_by(today).iter().map(|t| t.id).collect();
+        assert_eq!(due, [taxes, bank]);
+        assert_eq!(list.todos[1].due_label(today), "2026-10-17 (overdue)");
+        assert_eq!(list.todos[0].due_label(today), "2026-10-18 (today)");
+
+        let done_at = list.complete(taxes).unwrap().done_at;
+        assert!(done_at.is_some());
+        assert_eq!(list.complete(taxes).unwrap().done_at, done_at);
+        assert!(list.complete(42).is_none());
+        assert_eq!(list.due_by(today).len(), 1);
+
+        let path = std::env::temp_dir().join(format!("jarvis-todos-{}.json", std::process::id()));
+        list.save(&path).unwrap();
+        let mut loaded = TodoList::load(&path).unwrap();
+        assert_eq!(loaded.open().len(), 3);
+        assert_eq!(loaded.add("Water plants", None), 5);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    AppError, AppResult, model::ApprovalPolicy, providers::OllamaModelOptions, utils::config_dir,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profiles shipped with Jarvis, user profiles with the same name override them
+const BUILTIN_PROFILES: &[(&str, &str)] = &[
+    (DEFAULT_PROFILE, include_str!("profiles/default.toml")),
+    ("reviewer", include_str!("profiles/reviewer.toml")),
+];
+
+/// A named persona bundling the prompt, model and tools used for a kind of job
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    #[serde(skip)]
+    pub name: String,
+    pub description: Option<String>,
+    /// Prompt template name
+    pub prompt: Option<String>,
+    pub model: Option<String>,
+    /// Model options, merged over the defaults
+    pub options: Option<OllamaModelOptions>,
+    /// Modules exposed to the model, all modules when unset
+    pub modules: Option<Vec<String>>,
+    /// Only the tools matching these patterns (e.g. `fs.read_*`) are exposed
+    pub allow_tools: Option<Vec<String>>,
+    /// Tools matching these patterns are never exposed
+    pub deny_tools: Option<Vec<String>>,
+    /// Most tools offered per message, more are routed by relevance
+    pub max_tools: Option<usize>,
+    pub approval: Option<ApprovalPolicy>,
+}
+
+pub fn profiles_dir() -> PathBuf {
+    config_dir().join("profiles")
+}
+
+fn parse_profile(name: &str, content: &str) -> AppResult<Profile> {
+    let mut profile: Profile = toml::from_str(content)
+        .map_err(|e| AppError::from(&format!("Invalid profile {}: {}", name, e)))?;
+    profile.name = name.to_string();
+    Ok(profile)
+}
+
+/// Loads a profile from the profiles directory (`<name>.toml`), falling back to
+/// the built-in profiles
+pub fn load_profile(name: &str) -> AppResult<Profile> {
+    let path = profiles_dir().join(format!("{}.toml", name));
+    if path.is_file() {
+        log::info!("Using profile {}", path.display());
+        return parse_profile(name, &fs::read_to_string(path)?);
+    }
+
+    let (_, content) = BUILTIN_PROFILES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .ok_or_else(|| AppError::from(&format!("Profile {} not found", name)))?;
+
+    parse_profile(name, content)
+}
+
+/// All available profiles, user profiles replacing built-in ones
+pub fn list_profiles() -> AppResult<Vec<Profile>> {
+    let mut profiles = BUILTIN_PROFILES
+        .iter()
+        .map(|(name, content)| parse_profile(name, content))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let dir = profiles_dir();
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+                continue;
+            }
+
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                let profile = parse_profile(name, &fs::read_to_string(&path)?)?;
+                profiles.retain(|p| p.name != profile.name);
+                profiles.push(profile);
+            }
+        }
+    }
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles_parse() {
+        for (name, content) in BUILTIN_PROFILES {
+            parse_profile(name, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = parse_profile(
+            "tutor",
+            r#"
+                prompt = "tutor"
+                model = "qwen3:8b"
+                modules = ["math"]
+                approval = "ask"
+
+                [options]
+                temperature = 0.3
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(profile.name, "tutor");
+        assert_eq!(profile.modules, Some(vec!["math".to_string()]));
+        assert_eq!(profile.approval, Some(ApprovalPolicy::Ask));
+
+        let options = OllamaModelOptions::default().merge(&profile.options.unwrap());
+        assert_eq!(options.temperature, Some(0.3));
+        assert_eq!(options.num_ctx, OllamaModelOptions::default().num_ctx);
+    }
+
+    #[test]
+    fn test_parse_profile_rejects_unknown_fields() {
+        assert!(parse_profile("typo", "modle = \"llama3.2\"").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+mod template;
+
+pub use template::render_template;
+
+use crate::{AppError, AppResult, modules::Zone, utils::config_dir};
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+pub const DEFAULT_PROMPT: &str = "default";
+
+/// Templates shipped with Jarvis, user templates with the same name override them
+const BUILTIN_PROMPTS: &[(&str, &str)] = &[
+    (DEFAULT_PROMPT, include_str!("templates/default.md")),
+    ("reviewer", include_str!("templates/reviewer.md")),
+];
+
+/// Extensions tried when looking up a template in the prompts directory
+const PROMPT_EXTENSIONS: &[&str] = &["md", "txt"];
+
+pub fn prompts_dir() -> PathBuf {
+    config_dir().join("prompts")
+}
+
+/// Loads a template by name from the prompts directory, falling back to the
+/// built-in templates. A path to an existing file is also accepted.
+pub fn load_template(name: &str) -> AppResult<String> {
+    if name.contains(std::path::MAIN_SEPARATOR) || name.contains('/') {
+        return Ok(fs::read_to_string(name)?);
+    }
+
+    for extension in PROMPT_EXTENSIONS {
+        let path = prompts_dir().join(format!("{}.{}", name, extension));
+        if path.is_file() {
+            log::info!("Using prompt template {}", path.display());
+            return Ok(fs::read_to_string(path)?);
+        }
+    }
+
+    BUILTIN_PROMPTS
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, template)| template.to_string())
+        .ok_or_else(|| AppError::from(&format!("Prompt template {} not found", name)))
+}
+
+/// Names of all available templates with where they come from
+pub fn list_templates() -> AppResult<Vec<(String, String)>> {
+    let mut templates: Vec<(String, String)> = BUILTIN_PROMPTS
+        .iter()
+        .map(|(name, _)| (name.to_string(), "built-in".to_string()))
+        .collect();
+
+    let dir = prompts_dir();
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let is_template = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| PROMPT_EXTENSIONS.contains(&e));
+
+            if let (true, Some(stem)) = (is_template, path.file_stem().and_then(|s| s.to_str())) {
+                templates.retain(|(name, _)| name != stem);
+                templates.push((stem.to_string(), path.display().to_string()));
+            }
+        }
+    }
+
+    templates.sort();
+    Ok(templates)
+}
+
+/// Values available to templates
+#[derive(Debug, Clone, Default)]
+pub struct PromptVariables {
+    variables: HashMap<String, String>,
+}
+
+impl PromptVariables {
+    /// Variables describing the environment: `date`, `time`, `timezone`, `cwd`,
+    /// `os` and `user`
+    pub fn from_environment() -> Self {
+        let now = chrono::Local::now();
+        let cwd = env::current_dir()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        let mut vars = Self::default();
+        vars.set("date", now.format("%A, %Y-%m-%d").to_string());
+        vars.set("time", now.format("%H:%M").to_string());
+        vars.set(
+            "timezone",
+            format!("{} (UTC{})", Zone::local().name(), now.format("%:z")),
+        );
+        vars.set("cwd", cwd);
+        vars.set("os", env::consts::OS.to_string());
+        vars.set("user", user);
+        vars
+    }
+
+    pub fn set(&mut self, name: &str, value: String) -> &mut Self {
+        self.variables.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn render(&self, template: &str) -> AppResult<String> {
+        render_template(template, &self.variables)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{AppError, AppResult};
+use std::collections::HashMap;
+
+/// Parsed piece of a prompt template
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(String),
+    /// `{{#if name}}...{{else}}...{{/if}}`, `{{#unless name}}` sets `negate`
+    Conditional {
+        name: String,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+enum Tag<'a> {
+    Variable(&'a str),
+    If(&'a str, bool),
+    Else,
+    End,
+}
+
+fn parse_tag(tag: &str) -> AppResult<Tag<'_>> {
+    let tag = tag.trim();
+
+    if let Some(name) = tag.strip_prefix("#if ") {
+        Ok(Tag::If(name.trim(), false))
+    } else if let Some(name) = tag.strip_prefix("#unless ") {
+        Ok(Tag::If(name.trim(), true))
+    } else if tag == "else" {
+        Ok(Tag::Else)
+    } else if tag == "/if" || tag == "/unless" {
+        Ok(Tag::End)
+    } else if tag.is_empty() || tag.contains(char::is_whitespace) {
+        Err(AppError::from(&format!(
+            "Invalid template tag: {{{{{}}}}}",
+            tag
+        )))
+    } else {
+        Ok(Tag::Variable(tag))
+    }
+}
+
+/// Parses nodes until `{{else}}`/`{{/if}}` (returned as the stop tag) or the end
+fn parse_nodes<'a>(input: &mut &'a str) -> AppResult<(Vec<Node>, Option<Tag<'a>>)> {
+    let mut nodes = Vec::new();
+
+    while !input.is_empty() {
+        let Some(start) = input.find("{{") else {
+            nodes.push(Node::Text(input.to_string()));
+            *input = "";
+            break;
+        };
+
+        if start > 0 {
+            nodes.push(Node::Text(input[..start].to_string()));
+        }
+
+        let rest = &input[start + 2..];
+        let end = rest
+            .find("}}")
+            .ok_or_else(|| AppError::from("Unclosed template tag"))?;
+        let tag = parse_tag(&rest[..end])?;
+        *input = &rest[end + 2..];
+
+        if let Tag::Variable(name) = tag {
+            nodes.push(Node::Variable(name.to_string()));
+            continue;
+        }
+
+        // Block tags on their own line don't leave an empty line behind
+        if let Some(stripped) = input.strip_prefix('\n') {
+            *input = stripped;
+        }
+
+        match tag {
+            Tag::If(name, negate) => {
+                let (then, stop) = parse_nodes(input)?;
+                let otherwise = match stop {
+                    Some(Tag::Else) => match parse_nodes(input)? {
+                        (nodes, Some(Tag::End)) => nodes,
+                        _ => return Err(AppError::from(&format!("Unclosed {{{{#if {}}}}}", name))),
+                    },
+                    Some(Tag::End) => Vec::new(),
+                    _ => return Err(AppError::from(&format!("Unclosed {{{{#if {}}}}}", name))),
+                };
+
+                nodes.push(Node::Conditional {
+                    name: name.to_string(),
+                    negate,
+                    then,
+                    otherwise,
+                });
+            }
+            stop => return Ok((nodes, Some(stop))),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse(template: &str) -> AppResult<Vec<Node>> {
+    let mut input = template;
+    match parse_nodes(&mut input)? {
+        (nodes, None) => Ok(nodes),
+        (_, Some(_)) => Err(AppError::from("Unexpected {{else}} or {{/if}} in template")),
+    }
+}
+
+fn render_nodes(nodes: &[Node], variables: &HashMap<String, String>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(name) => {
+                if let Some(value) = variables.get(name) {
+                    output.push_str(value);
+                }
+            }
+            Node::Conditional {
+                name,
+                negate,
+                then,
+                otherwise,
+            } => {
+                let is_set = variables.get(name).is_some_and(|v| !v.trim().is_empty());
+                let branch = if is_set != *negate { then } else { otherwise };
+                render_nodes(branch, variables, output);
+            }
+        }
+    }
+}
+
+/// Renders `{{variable}}` placeholders and `{{#if variable}}` sections. A
+/// variable counts as set when it is present and not blank, unknown variables
+/// render as nothing.
+pub fn render_template(template: &str, variables: &HashMap<String, String>) -> AppResult<String> {
+    let nodes = parse(template)?;
+    let mut output = String::with_capacity(template.len());
+    render_nodes(&nodes, variables, &mut output);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_variables() {
+        let result = render_template(
+            "Hello {{ user }} on {{os}}!",
+            &vars(&[("user", "ana"), ("os", "linux")]),
+        );
+        assert_eq!(result.unwrap(), "Hello ana on linux!");
+    }
+
+    #[test]
+    fn test_render_conditionals_on_own_lines() {
+        let template =
+            "A\n{{#if context}}\n<context>\n{{context}}\n</context>\n{{else}}\nnone\n{{/if}}\nB";
+
+        let with_context = render_template(template, &vars(&[("context", "data")])).unwrap();
+        assert_eq!(with_context, "A\n<context>\ndata\n</context>\nB");
+
+        let without_context = render_template(template, &vars(&[("context", "  ")])).unwrap();
+        assert_eq!(without_context, "A\nnone\nB");
+    }
+
+    #[test]
+    fn test_render_nested_and_unless() {
+        let template = "{{#if a}}a{{#unless b}}!b{{/unless}}{{/if}}";
+        assert_eq!(
+            render_template(template, &vars(&[("a", "1")])).unwrap(),
+            "a!b"
+        );
+        assert_eq!(
+            render_template(template, &vars(&[("a", "1"), ("b", "1")])).unwrap(),
+            "a"
+        );
+    }
+
+    #[test]
+    fn test_render_rejects_unbalanced_blocks() {
+        assert!(render_template("{{#if a}}open", &HashMap::new()).is_err());
+        assert!(render_template("close{{/if}}", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_render_keeps_single_braces() {
+        let template = r#"{ "arguments": { /* schema */ } }"#;
+        assert_eq!(
+            render_template(template, &HashMap::new()).unwrap(),
+            template
+        );
+    }
+}
@@ -1,3 +1,5 @@
 mod ollama;
 
-pub use ollama::{OllamaConfig, create_ollama_client};
+pub use ollama::{
+    OllamaClient, OllamaConfig, OllamaModelOptions, OllamaProvider, create_ollama_client,
+};
@@ -1,5 +1,9 @@
-use super::ollama_api::OllamaModelOptions;
-use crate::{AppError, AppResult, model::ModelConfig, modules::Tool};
+use super::{OllamaProvider, ollama_api::OllamaModelOptions};
+use crate::{
+    AppError, AppResult,
+    model::{DEFAULT_CONTEXT_WINDOW, ModelCapabilities, ModelConfig},
+    modules::Tool,
+};
 
 #[derive(Debug, Clone)]
 pub struct OllamaConfig {
@@ -10,9 +14,16 @@ pub struct OllamaConfig {
     pub raw: bool,
     pub tools: Option<Vec<Tool>>,
     pub template: Option<String>,
+    /// Send tools through the native tool calling API instead of the prompt
+    pub native_tools: bool,
+    /// Model used for embeddings, falls back to `model`
+    pub embedding_model: Option<String>,
+    /// JSON schema the response must follow
+    pub format: Option<serde_json::Value>,
 }
 
 impl OllamaConfig {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> OllamaConfigBuilder {
         OllamaConfigBuilder::new()
     }
@@ -20,22 +31,61 @@ impl OllamaConfig {
     pub fn endpoint_url(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    pub fn embedding_model_name(&self) -> &str {
+        self.embedding_model.as_deref().unwrap_or(&self.model)
+    }
 }
 
+#[async_trait::async_trait]
 impl ModelConfig for OllamaConfig {
     fn model_name(&self) -> &str {
         &self.model
     }
 
-    fn validate(&self) -> AppResult<()> {
+    async fn validate(&self) -> AppResult<()> {
         if self.model.is_empty() {
             return Err(AppError::from("Model name cannot be empty"));
         }
         if self.port == 0 {
             return Err(AppError::from("Port cannot be 0"));
         }
+
+        let installed = OllamaProvider::new()
+            .is_model_installed(self, &self.model)
+            .await?;
+        if !installed {
+            return Err(AppError::from(&format!(
+                "Model {} is not installed. Run `jarvis models pull {}` first",
+                self.model, self.model
+            )));
+        }
+
         Ok(())
     }
+
+    fn apply_capabilities(&mut self, capabilities: &ModelCapabilities) {
+        self.native_tools = capabilities.tools;
+    }
+
+    fn context_window(&self) -> usize {
+        self.options
+            .num_ctx
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+    }
+
+    fn set_context_window(&mut self, tokens: usize) {
+        self.options.num_ctx = Some(tokens as i32);
+    }
+
+    fn tools(&self) -> Option<Vec<Tool>> {
+        self.tools.clone()
+    }
+
+    fn set_tools(&mut self, tools: Option<Vec<Tool>>) {
+        self.tools = tools;
+    }
 }
 
 // Builder for OllamaConfig
@@ -48,6 +98,8 @@ pub struct OllamaConfigBuilder {
     tools: Option<Vec<Tool>>,
     raw: bool,
     template: Option<String>,
+    embedding_model: Option<String>,
+    format: Option<serde_json::Value>,
 }
 
 #[allow(dead_code)]
@@ -61,6 +113,8 @@ impl OllamaConfigBuilder {
             options: OllamaModelOptions::default(),
             raw: false,
             template: None,
+            embedding_model: None,
+            format: None,
         }
     }
 
@@ -84,6 +138,16 @@ impl OllamaConfigBuilder {
         self
     }
 
+    pub fn embedding_model(mut self, model: String) -> Self {
+        self.embedding_model = Some(model);
+        self
+    }
+
+    pub fn format(mut self, schema: serde_json::Value) -> Self {
+        self.format = Some(schema);
+        self
+    }
+
     pub fn options(mut self, options: OllamaModelOptions) -> Self {
         self.options = options;
         self
@@ -112,6 +176,9 @@ impl OllamaConfigBuilder {
             options: self.options,
             raw: self.raw,
             template: self.template,
+            native_tools: false,
+            embedding_model: self.embedding_model,
+            format: self.format,
         })
     }
 }
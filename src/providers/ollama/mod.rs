@@ -7,11 +7,12 @@ use std::sync::Arc;
 use crate::{AppResult, modules::ModuleRegistry};
 
 pub use config::OllamaConfig;
+pub use ollama_api::OllamaModelOptions;
 pub use provider::OllamaProvider;
 
 pub type OllamaClient = crate::model::AIClient<provider::OllamaProvider>;
 
-pub fn create_ollama_client(
+pub async fn create_ollama_client(
     config: OllamaConfig,
     modules: Arc<ModuleRegistry>,
 ) -> AppResult<OllamaClient> {
@@ -19,7 +20,8 @@ pub fn create_ollama_client(
         .config(config)
         .provider(OllamaProvider::new())
         .modules(modules)
-        .build()?;
+        .build()
+        .await?;
 
     Ok(client)
 }
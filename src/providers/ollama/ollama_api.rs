@@ -1,6 +1,7 @@
 use crate::{
-    model::{Message, MessageRole},
-    modules::{Tool, ToolCall},
+    model::{Message, MessageRole, Usage},
+    modules::{Tool, ToolCall, ToolCallFunction},
+    utils::image_mime_type,
 };
 use serde::{Deserialize, Serialize};
 
@@ -35,6 +36,24 @@ impl Default for OllamaModelOptions {
     }
 }
 
+impl OllamaModelOptions {
+    /// Returns these options with every value set in `overrides` replaced
+    pub fn merge(self, overrides: &OllamaModelOptions) -> Self {
+        Self {
+            num_ctx: overrides.num_ctx.or(self.num_ctx),
+            repeat_last_n: overrides.repeat_last_n.or(self.repeat_last_n),
+            repeat_penalty: overrides.repeat_penalty.or(self.repeat_penalty),
+            temperature: overrides.temperature.or(self.temperature),
+            seed: overrides.seed.or(self.seed),
+            stop: overrides.stop.clone().or(self.stop),
+            num_predict: overrides.num_predict.or(self.num_predict),
+            top_k: overrides.top_k.or(self.top_k),
+            top_p: overrides.top_p.or(self.top_p),
+            min_p: overrides.min_p.or(self.min_p),
+        }
+    }
+}
+
 // Generate API types
 #[derive(Debug, Serialize)]
 pub struct OllamaGenerateRequest {
@@ -45,12 +64,112 @@ pub struct OllamaGenerateRequest {
     pub raw: bool,
     pub template: Option<String>,
     pub system: Option<String>,
+    /// `"json"` or a JSON schema the response must follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+    /// Base64 encoded images for multimodal models
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct OllamaGenerateResponse {
     pub response: String,
     pub done: bool,
+    #[serde(flatten)]
+    pub stats: OllamaStats,
+}
+
+impl OllamaGenerateResponse {
+    /// Usage reported with the final response
+    pub fn usage(&self) -> Usage {
+        self.stats.usage()
+    }
+}
+
+/// Statistics of the native APIs, only sent with the final response.
+/// Durations are in nanoseconds.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct OllamaStats {
+    pub prompt_eval_count: Option<u64>,
+    pub eval_count: Option<u64>,
+    pub prompt_eval_duration: Option<u64>,
+    pub eval_duration: Option<u64>,
+    pub total_duration: Option<u64>,
+}
+
+impl OllamaStats {
+    pub fn usage(&self) -> Usage {
+        let ms = |ns: Option<u64>| ns.unwrap_or_default() / 1_000_000;
+
+        Usage {
+            requests: 1,
+            prompt_tokens: self.prompt_eval_count.unwrap_or_default(),
+            completion_tokens: self.eval_count.unwrap_or_default(),
+            prompt_ms: ms(self.prompt_eval_duration),
+            completion_ms: ms(self.eval_duration),
+            total_ms: ms(self.total_duration),
+        }
+    }
+}
+
+// Chat API types, used for native tool calling since, unlike the OpenAI
+// compatible endpoint, it honours `options` such as `num_ctx`
+#[derive(Debug, Serialize)]
+pub struct OllamaChatRequest {
+    pub model: String,
+    pub messages: Vec<OllamaChatMessage>,
+    pub stream: bool,
+    pub options: Option<OllamaModelOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    /// `"json"` or a JSON schema the response must follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OllamaChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    /// Base64 encoded images for multimodal models
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+impl From<&Message> for OllamaChatMessage {
+    fn from(msg: &Message) -> Self {
+        let role = match msg.role {
+            MessageRole::System => "system",
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+        };
+
+        Self {
+            role: role.to_string(),
+            content: msg.content.clone(),
+            images: msg.images.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaChatResponse {
+    #[serde(default)]
+    pub message: Option<OllamaChatResponseMessage>,
+    pub done: bool,
+    #[serde(flatten)]
+    pub stats: OllamaStats,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaChatResponseMessage {
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub tool_calls: Option<Vec<OllamaToolCall>>,
 }
 
 // Completions API types
@@ -60,16 +179,46 @@ pub struct OllamaCompletionRequest {
     pub messages: Vec<OllamaMessage>,
     pub stream: bool,
     pub options: Option<OllamaModelOptions>,
-    pub tools: Option<Vec<Tool>>,
+    /// OpenAI style spelling of the `format` parameter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
+    pub stream_options: Option<OllamaStreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OllamaStreamOptions {
+    /// Send the token usage in a last chunk without choices
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OllamaMessage {
     pub role: String,
-    pub content: String,
+    pub content: OllamaMessageContent,
     pub tool_calls: Option<Vec<ToolCall>>,
 }
 
+/// Plain text, or OpenAI style content parts when images are attached
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OllamaMessageContent {
+    Text(String),
+    Parts(Vec<OllamaContentPart>),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OllamaContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OllamaImageUrl },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OllamaImageUrl {
+    /// Data URL, e.g. `data:image/png;base64,...`
+    pub url: String,
+}
+
 impl From<&Message> for OllamaMessage {
     fn from(msg: &Message) -> Self {
         let role = match msg.role {
@@ -78,9 +227,23 @@ impl From<&Message> for OllamaMessage {
             MessageRole::Assistant => "assistant",
         };
 
+        let content = if msg.images.is_empty() {
+            OllamaMessageContent::Text(msg.content.clone())
+        } else {
+            let mut parts = vec![OllamaContentPart::Text {
+                text: msg.content.clone(),
+            }];
+            parts.extend(msg.images.iter().map(|image| OllamaContentPart::ImageUrl {
+                image_url: OllamaImageUrl {
+                    url: format!("data:{};base64,{}", image_mime_type(image), image),
+                },
+            }));
+            OllamaMessageContent::Parts(parts)
+        };
+
         Self {
             role: role.to_string(),
-            content: msg.content.clone(),
+            content,
             tool_calls: None,
         }
     }
@@ -111,18 +274,59 @@ pub struct OllamaCompletionDelta {
     pub content: Option<String>,
     #[serde(skip_deserializing)]
     pub role: Option<String>,
-    pub tool_calls: Option<Vec<ToolCall>>,
+    pub tool_calls: Option<Vec<OllamaToolCall>>,
+}
+
+/// Tool call returned by the native tool calling API
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaToolCall {
+    pub function: OllamaToolCallFunction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaToolCallFunction {
+    pub name: String,
+    /// JSON encoded arguments, some servers send an object instead
+    pub arguments: serde_json::Value,
+}
+
+impl OllamaToolCall {
+    /// Converts the call into a `ToolCall`, looking up the module from the
+    /// tools that were offered since the native API doesn't echo it back
+    pub fn into_tool_call(self, tools: &[Tool]) -> ToolCall {
+        let module = tools
+            .iter()
+            .find(|t| t.function.name == self.function.name)
+            .map(|t| t.function.module.clone())
+            .unwrap_or_default();
+
+        let arguments = match self.function.arguments {
+            serde_json::Value::String(s) => {
+                serde_json::from_str(&s).unwrap_or(serde_json::Value::String(s))
+            }
+            other => other,
+        };
+
+        ToolCall {
+            tool_type: "function".to_string(),
+            function: ToolCallFunction {
+                name: self.function.name,
+                module,
+                arguments,
+            },
+        }
+    }
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct OllamaCompletionUsage {
-    #[serde(skip_deserializing)]
-    pub prompt_tokens: i32,
-    #[serde(skip_deserializing)]
-    pub completion_tokens: i32,
-    #[serde(skip_deserializing)]
-    pub total_tokens: i32,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
 }
 
 #[allow(dead_code)]
@@ -142,6 +346,103 @@ pub struct OllamaCompletionResponse {
     /// Contains all the generated completions
     pub choices: Vec<OllamaCompletionChoice>,
 
-    #[serde(skip_deserializing)]
+    /// Only in the last chunk, when requested with `stream_options`
+    #[serde(default)]
     pub usage: Option<OllamaCompletionUsage>,
 }
+
+// Model management API types
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaModelRequest {
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaPullRequest {
+    pub model: String,
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaModelDetails {
+    pub parent_model: String,
+    pub format: String,
+    pub family: String,
+    pub families: Option<Vec<String>>,
+    pub parameter_size: String,
+    pub quantization_level: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaModelInfo {
+    pub name: String,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub digest: String,
+    #[serde(default)]
+    pub details: OllamaModelDetails,
+    /// Only present for running models (`/api/ps`)
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// Only present for running models (`/api/ps`)
+    #[serde(default)]
+    pub size_vram: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaModelList {
+    pub models: Vec<OllamaModelInfo>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OllamaShowResponse {
+    pub license: Option<String>,
+    pub modelfile: Option<String>,
+    pub parameters: Option<String>,
+    pub template: Option<String>,
+    pub details: OllamaModelDetails,
+    pub model_info: Option<serde_json::Map<String, serde_json::Value>>,
+    pub capabilities: Option<Vec<String>>,
+}
+
+impl OllamaShowResponse {
+    /// Context length reported by the model architecture (e.g. `llama.context_length`)
+    pub fn context_length(&self) -> Option<u64> {
+        self.model_info.as_ref()?.iter().find_map(|(key, value)| {
+            if key.ends_with(".context_length") {
+                value.as_u64()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct OllamaPullResponse {
+    #[serde(default)]
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+    pub error: Option<String>,
+}
+
+// Embed API types
+#[derive(Debug, Serialize)]
+pub struct OllamaEmbedRequest {
+    pub model: String,
+    pub input: Vec<String>,
+    pub truncate: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaEmbedResponse {
+    pub embeddings: Vec<Vec<f32>>,
+}
@@ -1,32 +1,95 @@
 use super::config::OllamaConfig;
 use super::ollama_api::*;
 use crate::{
-    AppResult,
-    model::{GenerateResult, Message, ModelProvider},
+    AppError, AppResult,
+    model::{GenerateResult, Message, ModelCapabilities, ModelProvider, Usage},
     modules::ToolCall,
     streaming::{OutputStreamer, ProgressInfo, StreamEvent},
 };
 use async_trait::async_trait;
 use futures_util::{StreamExt, TryStreamExt};
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::de::Error;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio_stream::wrappers::LinesStream;
 use tokio_util::io::StreamReader;
 
 const GENERATE_API: &str = "/api/generate";
 const COMPLETION_API: &str = "/v1/chat/completions";
+const CHAT_API: &str = "/api/chat";
+const TAGS_API: &str = "/api/tags";
+const SHOW_API: &str = "/api/show";
+const PULL_API: &str = "/api/pull";
+const DELETE_API: &str = "/api/delete";
+const PS_API: &str = "/api/ps";
+const EMBED_API: &str = "/api/embed";
+
+/// Number of texts sent in a single embed request
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// Checks whether an installed model name (as reported by `/api/tags`) refers to
+/// the requested model. A request without a tag matches the `latest` tag.
+pub fn model_name_matches(installed: &str, requested: &str) -> bool {
+    if installed == requested {
+        return true;
+    }
+
+    !requested.contains(':') && installed == format!("{}:latest", requested)
+}
+
+/// Splits the texts into embed requests of at most `EMBED_BATCH_SIZE` texts
+fn embed_requests(texts: &[String], config: &OllamaConfig) -> Vec<OllamaEmbedRequest> {
+    texts
+        .chunks(EMBED_BATCH_SIZE)
+        .map(|batch| OllamaEmbedRequest {
+            model: config.embedding_model_name().to_string(),
+            input: batch.to_vec(),
+            truncate: true,
+        })
+        .collect()
+}
+
+fn capabilities_from_show(info: &OllamaShowResponse) -> ModelCapabilities {
+    let template = info.template.clone().filter(|t| !t.is_empty());
+
+    let (tools, vision) = match &info.capabilities {
+        Some(caps) => (
+            caps.iter().any(|c| c == "tools"),
+            caps.iter().any(|c| c == "vision"),
+        ),
+        // Older servers don't report capabilities, so look at the template instead
+        None => (
+            template.as_deref().is_some_and(|t| t.contains(".Tools")),
+            false,
+        ),
+    };
+
+    ModelCapabilities {
+        tools,
+        vision,
+        context_length: info.context_length().map(|n| n as usize),
+        template,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct OllamaProvider {
     client: Client,
+    /// Capabilities discovered through `/api/show`, keyed by model name
+    capabilities: Arc<Mutex<HashMap<String, ModelCapabilities>>>,
 }
 
 impl OllamaProvider {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            capabilities: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -75,17 +138,19 @@ impl OllamaProvider {
         let trimmed = content.trim();
 
         // Case 1: Entire response is a JSON array of tool calls
-        if trimmed.starts_with('[') && trimmed.ends_with(']') {
-            if let Ok(calls) = serde_json::from_str::<Vec<ToolCall>>(trimmed) {
-                return (calls, String::new());
-            }
+        if trimmed.starts_with('[')
+            && trimmed.ends_with(']')
+            && let Ok(calls) = serde_json::from_str::<Vec<ToolCall>>(trimmed)
+        {
+            return (calls, String::new());
         }
 
         // Case 2: Entire response is a single tool call JSON object
-        if trimmed.starts_with('{') && trimmed.ends_with('}') {
-            if let Ok(call) = serde_json::from_str::<ToolCall>(trimmed) {
-                return (vec![call], String::new());
-            }
+        if trimmed.starts_with('{')
+            && trimmed.ends_with('}')
+            && let Ok(call) = serde_json::from_str::<ToolCall>(trimmed)
+        {
+            return (vec![call], String::new());
         }
 
         // Case 3: Mixed content with code blocks
@@ -150,8 +215,12 @@ impl OllamaProvider {
             raw: config.raw,
             template: config.template.clone(),
             system: system_message,
+            format: config.format.clone(),
+            // The generate API takes the images of the whole conversation at once
+            images: messages.iter().flat_map(|m| m.images.clone()).collect(),
         };
 
+        let started = Instant::now();
         let response = self
             .client
             .post(format!("{}{}", config.endpoint_url(), GENERATE_API))
@@ -161,10 +230,9 @@ impl OllamaProvider {
             .error_for_status()?;
 
         let mut full_response = String::new();
+        let mut usage = Usage::default();
         let byte_stream = response.bytes_stream();
-        let stream_reader = StreamReader::new(
-            byte_stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
-        );
+        let stream_reader = StreamReader::new(byte_stream.map_err(std::io::Error::other));
         let mut lines = LinesStream::new(BufReader::new(stream_reader).lines());
 
         while let Some(line) = lines.next().await {
@@ -172,6 +240,10 @@ impl OllamaProvider {
                 Ok(l) if !l.trim().is_empty() => {
                     match serde_json::from_str::<OllamaGenerateResponse>(&l) {
                         Ok(result) => {
+                            if result.done {
+                                usage = result.usage();
+                            }
+
                             if !result.response.is_empty() {
                                 full_response.push_str(&result.response);
                                 streamer
@@ -201,6 +273,10 @@ impl OllamaProvider {
             }
         }
 
+        if usage.total_ms == 0 {
+            usage.total_ms = started.elapsed().as_millis() as u64;
+        }
+
         let (tool_calls, clean_response) = self.extract_tool_calls_from_content(&full_response);
 
         Ok(GenerateResult {
@@ -210,6 +286,7 @@ impl OllamaProvider {
             } else {
                 Some(tool_calls)
             },
+            usage,
         })
     }
 
@@ -234,9 +311,18 @@ impl OllamaProvider {
             messages: ollama_messages,
             stream: true,
             options: Some(config.options.clone()),
-            tools: config.tools.clone(),
+            response_format: config.format.as_ref().map(|schema| {
+                serde_json::json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": "response", "schema": schema }
+                })
+            }),
+            stream_options: Some(OllamaStreamOptions {
+                include_usage: true,
+            }),
         };
 
+        let started = Instant::now();
         let response = self
             .client
             .post(format!("{}{}", config.endpoint_url(), COMPLETION_API))
@@ -246,37 +332,37 @@ impl OllamaProvider {
             .error_for_status()?;
 
         let mut full_response = String::new();
-        let mut all_tool_calls = Vec::new();
+        let mut reported_usage = None;
+        let mut first_token = None;
         let byte_stream = response.bytes_stream();
-        let stream_reader = StreamReader::new(
-            byte_stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
-        );
+        let stream_reader = StreamReader::new(byte_stream.map_err(std::io::Error::other));
         let mut lines = LinesStream::new(BufReader::new(stream_reader).lines());
 
         while let Some(line) = lines.next().await {
             match line {
                 Ok(l) if !l.trim().is_empty() => {
                     let line = l.trim();
-                    if line.starts_with("data: ") {
-                        let data = &line[6..];
+                    if let Some(data) = line.strip_prefix("data: ") {
                         if data == "[DONE]" {
                             break;
                         }
 
                         match serde_json::from_str::<OllamaCompletionResponse>(data) {
                             Ok(result) => {
-                                if let Some(choice) = result.choices.first() {
-                                    if let Some(delta) = &choice.delta {
-                                        if let Some(content) = &delta.content {
-                                            full_response.push_str(content);
-                                            streamer
-                                                .handle_event(StreamEvent::Token(content.clone()))
-                                                .await?;
-                                        }
-
-                                        if let Some(tool_calls) = &delta.tool_calls {
-                                            all_tool_calls.extend(tool_calls.clone());
-                                        }
+                                if result.usage.is_some() {
+                                    reported_usage = result.usage;
+                                }
+
+                                if let Some(choice) = result.choices.first()
+                                    && let Some(delta) = &choice.delta
+                                {
+                                    first_token.get_or_insert_with(Instant::now);
+
+                                    if let Some(content) = &delta.content {
+                                        full_response.push_str(content);
+                                        streamer
+                                            .handle_event(StreamEvent::Token(content.clone()))
+                                            .await?;
                                     }
                                 }
                             }
@@ -299,6 +385,124 @@ impl OllamaProvider {
             }
         }
 
+        // The OpenAI API reports no timings, so measure them
+        let finished = Instant::now();
+        let first_token = first_token.unwrap_or(finished);
+        let usage = Usage {
+            requests: 1,
+            prompt_tokens: reported_usage.as_ref().map_or(0, |u| u.prompt_tokens),
+            completion_tokens: reported_usage.as_ref().map_or(0, |u| u.completion_tokens),
+            prompt_ms: (first_token - started).as_millis() as u64,
+            completion_ms: (finished - first_token).as_millis() as u64,
+            total_ms: (finished - started).as_millis() as u64,
+        };
+
+        let (tool_calls, clean_response) = self.extract_tool_calls_from_content(&full_response);
+
+        Ok(GenerateResult {
+            response: clean_response,
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            },
+            usage,
+        })
+    }
+
+    fn chat_request(&self, messages: &[Message], config: &OllamaConfig) -> OllamaChatRequest {
+        OllamaChatRequest {
+            model: config.model.clone(),
+            messages: messages.iter().map(|m| m.into()).collect(),
+            stream: true,
+            options: Some(config.options.clone()),
+            tools: config.tools.clone(),
+            format: config.format.clone(),
+        }
+    }
+
+    /// Generates through the chat API, which takes the tools natively
+    async fn generate_via_chat_api(
+        &self,
+        messages: &[Message],
+        config: &OllamaConfig,
+        streamer: &mut dyn OutputStreamer,
+    ) -> AppResult<GenerateResult> {
+        let request = self.chat_request(messages, config);
+
+        let started = Instant::now();
+        let response = self
+            .client
+            .post(format!("{}{}", config.endpoint_url(), CHAT_API))
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let mut full_response = String::new();
+        let mut all_tool_calls = Vec::new();
+        let mut usage = Usage::default();
+        let offered = config.tools.as_deref().unwrap_or_default();
+        let byte_stream = response.bytes_stream();
+        let stream_reader = StreamReader::new(byte_stream.map_err(std::io::Error::other));
+        let mut lines = LinesStream::new(BufReader::new(stream_reader).lines());
+
+        while let Some(line) = lines.next().await {
+            match line {
+                Ok(l) if !l.trim().is_empty() => {
+                    match serde_json::from_str::<OllamaChatResponse>(&l) {
+                        Ok(result) => {
+                            if let Some(message) = result.message {
+                                if !message.content.is_empty() {
+                                    full_response.push_str(&message.content);
+                                    streamer
+                                        .handle_event(StreamEvent::Token(message.content))
+                                        .await?;
+                                }
+
+                                if let Some(tool_calls) = message.tool_calls {
+                                    all_tool_calls.extend(
+                                        tool_calls
+                                            .into_iter()
+                                            .map(|call| call.into_tool_call(offered)),
+                                    );
+                                }
+                            }
+
+                            if result.done {
+                                usage = result.stats.usage();
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to parse chat response: {}, line: {}", e, l);
+                            streamer
+                                .handle_event(StreamEvent::Error(format!("Parse error: {}", e)))
+                                .await?;
+                        }
+                    }
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    log::error!("Stream error: {}", e);
+                    streamer
+                        .handle_event(StreamEvent::Error(format!("Stream error: {}", e)))
+                        .await?;
+                }
+            }
+        }
+
+        if usage.total_ms == 0 {
+            usage.total_ms = started.elapsed().as_millis() as u64;
+        }
+
+        // Models may still follow the prompt protocol instead of calling natively
+        if all_tool_calls.is_empty() {
+            let (tool_calls, clean_response) = self.extract_tool_calls_from_content(&full_response);
+            all_tool_calls = tool_calls;
+            full_response = clean_response;
+        }
+
         Ok(GenerateResult {
             response: full_response,
             tool_calls: if all_tool_calls.is_empty() {
@@ -306,8 +510,169 @@ impl OllamaProvider {
             } else {
                 Some(all_tool_calls)
             },
+            usage,
         })
     }
+
+    /// Lists the models installed locally
+    pub async fn list_models(&self, config: &OllamaConfig) -> AppResult<Vec<OllamaModelInfo>> {
+        let response = self
+            .client
+            .get(format!("{}{}", config.endpoint_url(), TAGS_API))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let list: OllamaModelList = response.json().await?;
+        Ok(list.models)
+    }
+
+    /// Lists the models currently loaded into memory
+    pub async fn running_models(&self, config: &OllamaConfig) -> AppResult<Vec<OllamaModelInfo>> {
+        let response = self
+            .client
+            .get(format!("{}{}", config.endpoint_url(), PS_API))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let list: OllamaModelList = response.json().await?;
+        Ok(list.models)
+    }
+
+    /// Returns true if the model is installed locally
+    pub async fn is_model_installed(&self, config: &OllamaConfig, model: &str) -> AppResult<bool> {
+        let models = self.list_models(config).await?;
+        Ok(models.iter().any(|m| model_name_matches(&m.name, model)))
+    }
+
+    /// Shows the details of a local model
+    pub async fn show_model(
+        &self,
+        config: &OllamaConfig,
+        model: &str,
+    ) -> AppResult<OllamaShowResponse> {
+        let request = OllamaModelRequest {
+            model: model.to_string(),
+        };
+
+        let response = self
+            .client
+            .post(format!("{}{}", config.endpoint_url(), SHOW_API))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(AppError::from(&format!("Model {} not found", model)));
+        }
+
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Capabilities of a model, fetched once per model and cached afterwards
+    pub async fn model_capabilities(
+        &self,
+        config: &OllamaConfig,
+        model: &str,
+    ) -> AppResult<ModelCapabilities> {
+        if let Some(capabilities) = self.capabilities.lock().unwrap().get(model) {
+            return Ok(capabilities.clone());
+        }
+
+        let info = self.show_model(config, model).await?;
+        let capabilities = capabilities_from_show(&info);
+        log::info!("Capabilities for {}: {:?}", model, capabilities);
+
+        self.capabilities
+            .lock()
+            .unwrap()
+            .insert(model.to_string(), capabilities.clone());
+
+        Ok(capabilities)
+    }
+
+    /// Pulls a model from the registry, reporting download progress to the streamer
+    pub async fn pull_model(
+        &self,
+        config: &OllamaConfig,
+        model: &str,
+        streamer: &mut dyn OutputStreamer,
+    ) -> AppResult<()> {
+        let request = OllamaPullRequest {
+            model: model.to_string(),
+            stream: true,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}{}", config.endpoint_url(), PULL_API))
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let byte_stream = response.bytes_stream();
+        let stream_reader = StreamReader::new(byte_stream.map_err(std::io::Error::other));
+        let mut lines = LinesStream::new(BufReader::new(stream_reader).lines());
+
+        while let Some(line) = lines.next().await {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let progress: OllamaPullResponse = serde_json::from_str(&line)?;
+
+            if let Some(error) = progress.error {
+                return Err(AppError::from(&format!(
+                    "Failed to pull {}: {}",
+                    model, error
+                )));
+            }
+
+            if progress.status == "success" {
+                streamer
+                    .handle_event(StreamEvent::Status(format!("Pulled {}", model)))
+                    .await?;
+                return Ok(());
+            }
+
+            streamer
+                .handle_event(StreamEvent::Progress(ProgressInfo {
+                    current: progress.completed.unwrap_or(0),
+                    total: progress.total,
+                    message: progress.status,
+                }))
+                .await?;
+        }
+
+        Err(AppError::from(&format!(
+            "Pull of {} ended before completion",
+            model
+        )))
+    }
+
+    /// Deletes a local model
+    pub async fn delete_model(&self, config: &OllamaConfig, model: &str) -> AppResult<()> {
+        let request = OllamaModelRequest {
+            model: model.to_string(),
+        };
+
+        let response = self
+            .client
+            .delete(format!("{}{}", config.endpoint_url(), DELETE_API))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(AppError::from(&format!("Model {} not found", model)));
+        }
+
+        response.error_for_status()?;
+        Ok(())
+    }
 }
 
 impl Default for OllamaProvider {
@@ -326,6 +691,10 @@ impl ModelProvider for OllamaProvider {
         config: &Self::Config,
         streamer: &mut dyn OutputStreamer,
     ) -> AppResult<GenerateResult> {
+        if config.native_tools && config.tools.is_some() {
+            return self.generate_via_chat_api(messages, config, streamer).await;
+        }
+
         // Try completion API first, fallback to generate API
         match self
             .generate_via_completion_api(messages, config, streamer)
@@ -346,10 +715,46 @@ impl ModelProvider for OllamaProvider {
         config: &Self::Config,
         streamer: &mut dyn OutputStreamer,
     ) -> AppResult<GenerateResult> {
+        if config.native_tools && config.tools.is_some() {
+            return self.generate_via_chat_api(messages, config, streamer).await;
+        }
+
         self.generate_via_generate_api(messages, config, streamer)
             .await
     }
 
+    async fn embed(&self, texts: &[String], config: &Self::Config) -> AppResult<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+
+        for request in embed_requests(texts, config) {
+            let response: OllamaEmbedResponse = self
+                .client
+                .post(format!("{}{}", config.endpoint_url(), EMBED_API))
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            if response.embeddings.len() != request.input.len() {
+                return Err(AppError::from(&format!(
+                    "Expected {} embeddings, got {}",
+                    request.input.len(),
+                    response.embeddings.len()
+                )));
+            }
+
+            embeddings.extend(response.embeddings);
+        }
+
+        Ok(embeddings)
+    }
+
+    async fn capabilities(&self, config: &Self::Config) -> AppResult<ModelCapabilities> {
+        self.model_capabilities(config, &config.model).await
+    }
+
     fn provider_name(&self) -> &'static str {
         "ollama"
     }
@@ -361,15 +766,11 @@ impl ModelProvider for OllamaProvider {
     fn supports_system_messages(&self) -> bool {
         true
     }
-
-    fn max_context_length(&self) -> Option<usize> {
-        None // Depends on the specific model
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::modules::ToolCallFunction;
+    use crate::modules::{Module, ToolCallFunction};
 
     use super::*;
 
@@ -378,6 +779,60 @@ mod tests {
         OllamaProvider::new()
     }
 
+    #[test]
+    fn test_model_name_matches() {
+        assert!(model_name_matches("llama3.2:latest", "llama3.2"));
+        assert!(model_name_matches("llama3.2:latest", "llama3.2:latest"));
+        assert!(model_name_matches("qwen3:8b", "qwen3:8b"));
+        assert!(!model_name_matches("qwen3:8b", "qwen3"));
+        assert!(!model_name_matches("llama3.2:latest", "llama3"));
+    }
+
+    #[test]
+    fn test_capabilities_from_show() {
+        let info: OllamaShowResponse = serde_json::from_value(serde_json::json!({
+            "template": "{{ .Prompt }}",
+            "model_info": { "general.architecture": "llama", "llama.context_length": 131072 },
+            "capabilities": ["completion", "tools"]
+        }))
+        .unwrap();
+
+        let capabilities = capabilities_from_show(&info);
+        assert!(capabilities.tools);
+        assert!(!capabilities.vision);
+        assert_eq!(capabilities.context_length, Some(131072));
+    }
+
+    #[test]
+    fn test_capabilities_from_show_without_capabilities_list() {
+        let info: OllamaShowResponse = serde_json::from_value(serde_json::json!({
+            "template": "{{ if .Tools }}{{ .Tools }}{{ end }}{{ .Prompt }}"
+        }))
+        .unwrap();
+
+        let capabilities = capabilities_from_show(&info);
+        assert!(capabilities.tools);
+        assert_eq!(capabilities.context_length, None);
+    }
+
+    #[test]
+    fn test_native_tool_call_resolves_module() {
+        let tools = crate::modules::Math::new().tools();
+        let call: OllamaToolCall = serde_json::from_value(serde_json::json!({
+            "id": "call_1",
+            "type": "function",
+            "function": { "name": "sqrt", "arguments": "{\"value\": 81}" }
+        }))
+        .unwrap();
+
+        let tool_call = call.into_tool_call(&tools);
+        assert_eq!(tool_call.function.module, "math");
+        assert_eq!(
+            tool_call.function.arguments,
+            serde_json::json!({ "value": 81 })
+        );
+    }
+
     #[test]
     fn test_extract_tool_calls_from_content_json_array_only() {
         let provider = setup();
@@ -536,4 +991,136 @@ mod tests {
         assert_ne!(tool_calls, expected_calls);
         assert_ne!(cleaned_content, expected_cleaned_content);
     }
+
+    #[test]
+    fn test_message_with_images_uses_content_parts() {
+        let mut context = crate::model::Context::new(10);
+        context.add_system_message("system".to_string());
+        context.add_user_message_with_images(
+            "what is this?".to_string(),
+            vec!["/9j/4AAQ".to_string()],
+        );
+
+        let messages: Vec<OllamaMessage> =
+            context.get_messages().iter().map(|m| m.into()).collect();
+
+        assert_eq!(
+            serde_json::to_value(&messages[0].content).unwrap(),
+            serde_json::json!("system")
+        );
+        assert_eq!(
+            serde_json::to_value(&messages[1].content).unwrap(),
+            serde_json::json!([
+                { "type": "text", "text": "what is this?" },
+                { "type": "image_url", "image_url": { "url": "data:image/jpeg;base64,/9j/4AAQ" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_generate_response_usage() {
+        let line = r#"{"response":"","done":true,"total_duration":900000000,"prompt_eval_count":26,"prompt_eval_duration":100000000,"eval_count":40,"eval_duration":800000000}"#;
+
+        let usage = serde_json::from_str::<OllamaGenerateResponse>(line)
+            .unwrap()
+            .usage();
+
+        assert_eq!(usage.prompt_tokens, 26);
+        assert_eq!(usage.completion_tokens, 40);
+        assert_eq!(usage.total_ms, 900);
+        assert_eq!(usage.tokens_per_second(), Some(50.0));
+    }
+
+    #[test]
+    fn test_chat_request_keeps_options_with_tools() {
+        let mut config = OllamaConfig::new()
+            .model("llama3.2".to_string())
+            .build()
+            .unwrap();
+        config.tools = Some(crate::modules::Math::new().tools());
+        config.native_tools = true;
+        config.options.num_ctx = Some(16384);
+
+        let mut context = crate::model::Context::new(10);
+        context.add_user_message_with_images(
+            "what is this?".to_string(),
+            vec!["/9j/4AAQ".to_string()],
+        );
+
+        let request =
+            serde_json::to_value(setup().chat_request(&context.get_messages(), &config)).unwrap();
+
+        assert_eq!(request["options"]["num_ctx"], 16384);
+        assert!(!request["tools"].as_array().unwrap().is_empty());
+        assert_eq!(
+            request["messages"][0]["images"],
+            serde_json::json!(["/9j/4AAQ"])
+        );
+    }
+
+    #[test]
+    fn test_chat_response_tool_calls_and_usage() {
+        let line = r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"sqrt","arguments":{"value":81}}}]},"done":true,"total_duration":900000000,"prompt_eval_count":26,"eval_count":40,"eval_duration":800000000}"#;
+
+        let response = serde_json::from_str::<OllamaChatResponse>(line).unwrap();
+        let usage = response.stats.usage();
+        let calls = response.message.unwrap().tool_calls.unwrap();
+
+        assert_eq!(usage.prompt_tokens, 26);
+        assert_eq!(usage.total_ms, 900);
+        assert_eq!(
+            calls[0]
+                .clone()
+                .into_tool_call(&crate::modules::Math::new().tools())
+                .function
+                .module,
+            "math"
+        );
+    }
+
+    #[test]
+    fn test_embed_requests_batching() {
+        let config = OllamaConfig::new()
+            .model("llama3.2".to_string())
+            .embedding_model("nomic-embed-text".to_string())
+            .build()
+            .unwrap();
+        let texts: Vec<String> = (0..70).map(|i| format!("text {}", i)).collect();
+
+        let requests = embed_requests(&texts, &config);
+
+        let sizes: Vec<usize> = requests.iter().map(|r| r.input.len()).collect();
+        assert_eq!(sizes, vec![32, 32, 6]);
+        assert_eq!(
+            requests
+                .iter()
+                .flat_map(|r| r.input.clone())
+                .collect::<Vec<_>>(),
+            texts
+        );
+        assert!(embed_requests(&[], &config).is_empty());
+    }
+
+    #[test]
+    fn test_embed_payloads() {
+        let request = OllamaEmbedRequest {
+            model: "nomic-embed-text".to_string(),
+            input: vec!["first".to_string(), "second".to_string()],
+            truncate: true,
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "model": "nomic-embed-text",
+                "input": ["first", "second"],
+                "truncate": true
+            })
+        );
+
+        let response: OllamaEmbedResponse = serde_json::from_str(
+            r#"{"model":"nomic-embed-text","embeddings":[[0.1,-0.2],[0.3,0.4]],"total_duration":14143917}"#,
+        )
+        .unwrap();
+        assert_eq!(response.embeddings, vec![vec![0.1, -0.2], vec![0.3, 0.4]]);
+    }
 }
@@ -0,0 +1,174 @@
+use crate::{
+    AppError, AppResult,
+    model::{Context, Usage},
+    utils::{data_dir, write_atomic},
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BTreeMap, fs, path::PathBuf};
+
+/// A saved chat, with the whole conversation tree so every branch survives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(skip)]
+    pub name: String,
+    /// Profile the session was started with
+    pub profile: String,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    pub context: Context,
+    /// State of the modules that keep some, e.g. math variables
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<String, serde_json::Value>,
+}
+
+pub fn sessions_dir() -> PathBuf {
+    data_dir().join("sessions")
+}
+
+fn session_path(name: &str) -> AppResult<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+
+    if !valid {
+        return Err(AppError::from(&format!(
+            "Invalid session name {} (use letters, digits, '-', '_' and '.')",
+            name
+        )));
+    }
+
+    Ok(sessions_dir().join(format!("{}.json", name)))
+}
+
+impl Session {
+    pub fn new(name: &str, profile: &str, context: Context) -> AppResult<Self> {
+        session_path(name)?;
+
+        let now = Local::now();
+        Ok(Self {
+            name: name.to_string(),
+            profile: profile.to_string(),
+            created_at: now,
+            updated_at: now,
+            context,
+            modules: BTreeMap::new(),
+        })
+    }
+
+    pub fn save(&mut self) -> AppResult<()> {
+        self.updated_at = Local::now();
+        write_atomic(&session_path(&self.name)?, &serde_json::to_string(self)?)
+    }
+}
+
+/// Usage of all answers in a conversation, including other branches, per model
+pub fn usage_by_model(context: &Context) -> BTreeMap<String, Usage> {
+    let mut usage: BTreeMap<String, Usage> = BTreeMap::new();
+
+    for metadata in context.all_messages().filter_map(|m| m.metadata.as_ref()) {
+        let model = metadata.get("model").and_then(|m| m.as_str());
+        let answer = metadata
+            .get("usage")
+            .and_then(|u| serde_json::from_value::<Usage>(u.clone()).ok());
+
+        if let (Some(model), Some(answer)) = (model, answer) {
+            *usage.entry(model.to_string()).or_default() += answer;
+        }
+    }
+
+    usage
+}
+
+/// Loads a saved session, `None` if there is no session with that name
+pub fn load_session(name: &str) -> AppResult<Option<Session>> {
+    let path = session_path(name)?;
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let mut session: Session = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| AppError::from(&format!("Invalid session {}: {}", name, e)))?;
+    session.name = name.to_string();
+
+    Ok(Some(session))
+}
+
+/// Saved sessions, most recently updated first
+pub fn list_sessions() -> AppResult<Vec<Session>> {
+    let dir = sessions_dir();
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        if let Some(name) = path.file_stem().and_then(|s| s.to_str())
+            && let Some(session) = load_session(name)?
+        {
+            sessions.push(session);
+        }
+    }
+
+    sessions.sort_by_key(|s| Reverse(s.updated_at));
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_names() {
+        assert!(session_path("standup-2024.05").is_ok());
+        assert!(session_path("../escape").is_err());
+        assert!(session_path(".hidden").is_err());
+        assert!(session_path("").is_err());
+    }
+
+    #[test]
+    fn test_session_keeps_tree() {
+        let mut context = Context::new(100);
+        context.add_user_message("question".to_string());
+        context.add_assistant_message("first".to_string());
+        context.checkout(0);
+        context.add_assistant_message("second".to_string());
+
+        let session = Session::new("test", "default", context).unwrap();
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.context.branches(), [1, 2]);
+        assert_eq!(restored.context.head(), Some(2));
+    }
+
+    #[test]
+    fn test_usage_by_model() {
+        let mut context = Context::new(100);
+        for (model, tokens) in [("llama3.2", 10), ("qwen3", 5), ("llama3.2", 20)] {
+            context.add_assistant_message("answer".to_string());
+            let usage = Usage {
+                requests: 1,
+                completion_tokens: tokens,
+                ..Default::default()
+            };
+            context.set_metadata(
+                context.head().unwrap(),
+                serde_json::json!({ "model": model, "usage": usage }),
+            );
+        }
+
+        let usage = usage_by_model(&context);
+
+        assert_eq!(usage["llama3.2"].completion_tokens, 30);
+        assert_eq!(usage["llama3.2"].requests, 2);
+        assert_eq!(usage["qwen3"].completion_tokens, 5);
+    }
+}
@@ -1,11 +1,79 @@
-use crate::{AppError, AppResult};
-use std::fs;
+use crate::AppResult;
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
 
-pub fn get_file_content(file_path: String) -> AppResult<String> {
-    let file_content = fs::read_to_string(file_path);
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
 
-    match file_content {
-        Ok(content) => Ok(content),
-        Err(e) => Err(AppError::IO(e)),
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
     }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Prints rows in left-aligned columns under the headers
+pub fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", format_row(headers.to_vec()));
+    for row in &rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// Asks a yes/no question on stderr, anything but `y`/`yes` counts as no
+pub fn confirm(question: &str) -> AppResult<bool> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Writes a file through a temporary sibling and a rename, so a crash never
+/// leaves it half written
+pub fn write_atomic(path: &Path, contents: &str) -> AppResult<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = OsString::from(path.as_os_str());
+    tmp_name.push(".tmp");
+
+    fs::write(&tmp_name, contents)?;
+    fs::rename(&tmp_name, path)?;
+
+    Ok(())
 }
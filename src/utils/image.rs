@@ -0,0 +1,77 @@
+use crate::{AppError, AppResult};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::{fs, path::Path};
+
+/// Largest image accepted as an attachment
+pub const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Magic bytes of the supported image formats, as they start once base64 encoded
+const IMAGE_SIGNATURES: [(&str, &str); 4] = [
+    ("iVBORw0KGgo", "image/png"),
+    ("/9j/", "image/jpeg"),
+    ("R0lGOD", "image/gif"),
+    ("UklGR", "image/webp"),
+];
+
+/// Mime type of a base64 encoded image, sniffed from its first bytes
+pub fn image_mime_type(image: &str) -> &'static str {
+    IMAGE_SIGNATURES
+        .iter()
+        .find(|(signature, _)| image.starts_with(signature))
+        .map(|(_, mime)| *mime)
+        .unwrap_or("image/png")
+}
+
+/// Reads an image file and returns it base64 encoded
+pub fn load_image(path: &str) -> AppResult<String> {
+    let size = fs::metadata(path)
+        .map_err(|e| AppError::from(&format!("Cannot read image {}: {}", path, e)))?
+        .len();
+
+    if size > MAX_IMAGE_BYTES {
+        return Err(AppError::from(&format!(
+            "Image {} is too large ({}, max {})",
+            path,
+            super::format_bytes(size),
+            super::format_bytes(MAX_IMAGE_BYTES)
+        )));
+    }
+
+    let image = STANDARD.encode(fs::read(Path::new(path))?);
+    if !IMAGE_SIGNATURES
+        .iter()
+        .any(|(signature, _)| image.starts_with(signature))
+    {
+        return Err(AppError::from(&format!(
+            "{} is not a supported image (png, jpeg, gif or webp)",
+            path
+        )));
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_mime_type() {
+        let png = STANDARD.encode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        let jpeg = STANDARD.encode(b"\xff\xd8\xff\xe0\0\x10JFIF");
+
+        assert_eq!(image_mime_type(&png), "image/png");
+        assert_eq!(image_mime_type(&jpeg), "image/jpeg");
+    }
+
+    #[test]
+    fn test_load_image_rejects_other_files() {
+        let path = std::env::temp_dir().join("jarvis-not-an-image.txt");
+        fs::write(&path, "hello").unwrap();
+
+        let result = load_image(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+}
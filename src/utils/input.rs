@@ -0,0 +1,318 @@
+use crate::{AppError, AppResult};
+use globset::GlobBuilder;
+use ignore::WalkBuilder;
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Maximum bytes of a single input included in the context
+pub const MAX_INPUT_FILE_BYTES: usize = 64 * 1024;
+
+/// Maximum bytes of all inputs included in the context
+pub const MAX_INPUT_TOTAL_BYTES: usize = 256 * 1024;
+
+/// Bytes inspected when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl InputSource {
+    pub fn label(&self) -> String {
+        match self {
+            InputSource::Stdin => "stdin".to_string(),
+            InputSource::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// Inputs rendered for the context section, plus notices about anything that
+/// was skipped or truncated
+#[derive(Debug, Default)]
+pub struct LoadedInputs {
+    pub context: String,
+    /// Labels of the inputs included in the context
+    pub files: Vec<String>,
+    pub notices: Vec<String>,
+}
+
+fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?', '[', '{'])
+}
+
+/// Directory to start walking from for a glob, i.e. the components before the
+/// first one containing a wildcard
+fn glob_base(pattern: &str) -> PathBuf {
+    let base: PathBuf = Path::new(pattern)
+        .components()
+        .take_while(|c| !is_glob(&c.as_os_str().to_string_lossy()))
+        .collect();
+
+    if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    }
+}
+
+fn walk(base: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(base)
+        .require_git(false)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| {
+            let path = entry.into_path();
+            path.strip_prefix("./")
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+}
+
+/// Expands `--input` values into sources. `-` reads stdin, directories and globs
+/// are walked respecting `.gitignore`, plain paths are used as they are.
+pub fn expand_inputs(inputs: &[String]) -> AppResult<Vec<InputSource>> {
+    let mut sources = Vec::new();
+
+    for input in inputs {
+        let mut expanded = if input == "-" {
+            vec![InputSource::Stdin]
+        } else if is_glob(input) {
+            // Walked paths have no leading `./`, so neither may the pattern
+            let pattern = input.trim_start_matches("./");
+            let matcher = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| AppError::from(&format!("Invalid pattern {}: {}", input, e)))?
+                .compile_matcher();
+
+            let mut files: Vec<PathBuf> = walk(&glob_base(pattern))
+                .filter(|path| matcher.is_match(path))
+                .collect();
+            files.sort();
+
+            if files.is_empty() {
+                return Err(AppError::from(&format!("No files match {}", input)));
+            }
+            files.into_iter().map(InputSource::File).collect()
+        } else if Path::new(input).is_dir() {
+            let mut files: Vec<PathBuf> = walk(Path::new(input)).collect();
+            files.sort();
+            files.into_iter().map(InputSource::File).collect()
+        } else {
+            vec![InputSource::File(PathBuf::from(input))]
+        };
+
+        expanded.retain(|source| !sources.contains(source));
+        sources.extend(expanded);
+    }
+
+    Ok(sources)
+}
+
+/// Tables and databases, queried with the data module rather than included
+/// as text. JSON only when it is too large to include whole.
+pub fn is_data_file(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "csv" | "tsv" | "jsonl" | "ndjson" | "db" | "sqlite" | "sqlite3" => true,
+        "json" => fs::metadata(path).is_ok_and(|m| m.len() > MAX_INPUT_FILE_BYTES as u64),
+        _ => false,
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// Cuts text to at most `max` bytes without splitting a character
+fn truncate_at_char(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Wraps each input in a labeled block, enforcing the size limits
+pub fn render_inputs(inputs: Vec<(String, Vec<u8>)>) -> LoadedInputs {
+    let mut loaded = LoadedInputs::default();
+    let mut blocks = Vec::new();
+    let mut total = 0;
+
+    for (label, bytes) in inputs {
+        if is_binary(&bytes) {
+            loaded
+                .notices
+                .push(format!("Skipped {}: binary file", label));
+            continue;
+        }
+
+        let remaining = MAX_INPUT_TOTAL_BYTES.saturating_sub(total);
+        if remaining == 0 {
+            loaded.notices.push(format!(
+                "Skipped {}: total input limit of {} bytes reached",
+                label, MAX_INPUT_TOTAL_BYTES
+            ));
+            continue;
+        }
+
+        let text = String::from_utf8_lossy(&bytes);
+        let limit = MAX_INPUT_FILE_BYTES.min(remaining);
+        let content = truncate_at_char(&text, limit);
+        total += content.len();
+
+        let mut block = format!("<file path=\"{}\">\n{}", label, content);
+        if content.len() < text.len() {
+            let notice = format!(
+                "Truncated {}: included the first {} bytes",
+                label,
+                content.len()
+            );
+            block.push_str(&format!("\n[{}]", notice));
+            loaded.notices.push(notice);
+        }
+        block.push_str("\n</file>");
+        blocks.push(block);
+        loaded.files.push(label);
+    }
+
+    loaded.context = blocks.join("\n\n");
+    loaded
+}
+
+/// Reads one byte more than an input can include, so large inputs are
+/// known to be truncated without reading them whole
+fn read_limited(reader: impl Read) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    reader
+        .take(MAX_INPUT_FILE_BYTES as u64 + 1)
+        .read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Reads all sources and renders them for the context section
+pub fn load_inputs(sources: &[InputSource]) -> AppResult<LoadedInputs> {
+    let mut inputs = Vec::with_capacity(sources.len());
+
+    for source in sources {
+        let bytes = match source {
+            InputSource::Stdin => {
+                let buffer = read_limited(io::stdin())?;
+
+                // Nothing was piped in
+                if buffer.is_empty() {
+                    continue;
+                }
+                buffer
+            }
+            InputSource::File(path) => read_limited(fs::File::open(path)?)?,
+        };
+
+        inputs.push((source.label(), bytes));
+    }
+
+    Ok(render_inputs(inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_base() {
+        assert_eq!(glob_base("src/**/*.rs"), PathBuf::from("src"));
+        assert_eq!(glob_base("*.md"), PathBuf::from("."));
+        assert_eq!(glob_base("docs/guide/*.md"), PathBuf::from("docs/guide"));
+    }
+
+    #[test]
+    fn test_expand_inputs_glob() {
+        let plain = expand_inputs(&["src/utils/*.rs".to_string()]).unwrap();
+        let dotted = expand_inputs(&["./src/utils/*.rs".to_string()]).unwrap();
+
+        assert!(plain.contains(&InputSource::File(PathBuf::from("src/utils/input.rs"))));
+        assert_eq!(dotted, plain);
+        assert!(expand_inputs(&["./src/*.nothing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_is_data_file() {
+        assert!(is_data_file(Path::new("reports/sales.csv")));
+        assert!(is_data_file(Path::new("shop.SQLite")));
+        assert!(!is_data_file(Path::new("notes.md")));
+        // Small or missing JSON files are included as text
+        assert!(!is_data_file(Path::new("missing.json")));
+    }
+
+    #[test]
+    fn test_render_inputs_labels_and_skips_binary() {
+        let loaded = render_inputs(vec![
+            ("notes.md".to_string(), b"hello".to_vec()),
+            ("image.png".to_string(), vec![0x89, 0x50, 0x00, 0x47]),
+        ]);
+
+        assert_eq!(loaded.context, "<file path=\"notes.md\">\nhello\n</file>");
+        assert_eq!(loaded.files, vec!["notes.md"]);
+        assert_eq!(loaded.notices, vec!["Skipped image.png: binary file"]);
+    }
+
+    #[test]
+    fn test_render_inputs_truncates_large_files() {
+        let big = "é".repeat(MAX_INPUT_FILE_BYTES);
+        let loaded = render_inputs(vec![("big.txt".to_string(), big.into_bytes())]);
+
+        assert!(loaded.context.contains("[Truncated big.txt: included"));
+        assert_eq!(loaded.notices.len(), 1);
+        assert!(loaded.context.len() < MAX_INPUT_FILE_BYTES + 200);
+    }
+
+    #[test]
+    fn test_load_inputs_reads_a_bounded_prefix() {
+        let loaded = load_inputs(&[InputSource::File(PathBuf::from("/dev/zero"))]).unwrap();
+
+        // Zero bytes look binary, what matters is that reading stopped
+        assert_eq!(loaded.notices, vec!["Skipped /dev/zero: binary file"]);
+
+        let path = std::env::temp_dir().join("jarvis-large-input.txt");
+        fs::write(&path, "a".repeat(MAX_INPUT_FILE_BYTES * 2)).unwrap();
+        let loaded = load_inputs(&[InputSource::File(path.clone())]).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            loaded.notices,
+            vec![format!(
+                "Truncated {}: included the first {} bytes",
+                path.display(),
+                MAX_INPUT_FILE_BYTES
+            )]
+        );
+    }
+
+    #[test]
+    fn test_render_inputs_enforces_total_limit() {
+        let file = "a".repeat(MAX_INPUT_FILE_BYTES);
+        let inputs = (0..5)
+            .map(|i| (format!("{}.txt", i), file.clone().into_bytes()))
+            .collect();
+
+        let loaded = render_inputs(inputs);
+
+        assert_eq!(loaded.notices.len(), 1);
+        assert!(loaded.notices[0].starts_with("Skipped 4.txt"));
+    }
+}
@@ -1,5 +1,11 @@
 mod functions;
+mod image;
+mod input;
 mod logger;
+mod paths;
 
 pub use functions::*;
+pub use image::*;
+pub use input::*;
 pub use logger::logger_init;
+pub use paths::*;
@@ -0,0 +1,34 @@
+use std::{env, path::PathBuf};
+
+fn home_dir() -> PathBuf {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Directory where Jarvis keeps its data (indexes, sessions, ...)
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("JARVIS_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    if let Some(dir) = env::var_os("XDG_DATA_HOME") {
+        return PathBuf::from(dir).join("jarvis");
+    }
+
+    home_dir().join(".local").join("share").join("jarvis")
+}
+
+/// Directory where Jarvis looks for user configuration (prompts, profiles, ...)
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("JARVIS_CONFIG_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return PathBuf::from(dir).join("jarvis");
+    }
+
+    home_dir().join(".config").join("jarvis")
+}